@@ -2,25 +2,95 @@ use crate::parser;
 use crate::source::{CodeRange, Source, SourceCoords, Span};
 use crate::{interpreter, typechecker};
 
-use colored::Colorize;
+use colored::{Color, Colorize};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BauError {
     ParserError(parser::ParserError),
     TypecheckerError(typechecker::TypecheckerError),
     ExecutionError(interpreter::ExecutionError),
+    /// An error in the prelude. Its range refers to the prelude's source.
+    PreludeError(Box<BauError>),
+    /// A file that could not be read, with the reason why.
+    IoError {
+        path: String,
+        message: String,
+    },
+    /// A source larger than the configured limit, in bytes.
+    SourceTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// A native function whose signature is not a function type.
+    InvalidNativeSignature {
+        name: String,
+        signature: typechecker::Type,
+    },
+    /// A native function named after a builtin or another native function.
+    NativeFunctionAlreadyDefined {
+        name: String,
+    },
 }
 
 impl BauError {
+    pub fn range(&self) -> Option<&CodeRange> {
+        match self {
+            Self::ParserError(error) => Some(error.range()),
+            Self::TypecheckerError(error) => Some(error.range()),
+            Self::ExecutionError(error) => error.range(),
+            Self::PreludeError(error) => error.range(),
+            Self::IoError { .. }
+            | Self::SourceTooLarge { .. }
+            | Self::InvalidNativeSignature { .. }
+            | Self::NativeFunctionAlreadyDefined { .. } => None,
+        }
+    }
+
     pub fn print(&self, source: &Source) {
         match self {
             Self::ParserError(error) => error.print(source),
             Self::TypecheckerError(error) => error.print(source),
             Self::ExecutionError(error) => error.print(source),
+            Self::PreludeError(error) => error.print(source),
+            Self::IoError { path, message } => print_error(
+                source,
+                None,
+                &format!("Could not read file `{}`: {}", path, message),
+            ),
+            Self::SourceTooLarge { size, limit } => print_error(
+                source,
+                None,
+                &format!(
+                    "File too large: it is {} bytes, but the limit is {} bytes",
+                    size, limit
+                ),
+            ),
+            Self::InvalidNativeSignature { name, signature } => print_error(
+                source,
+                None,
+                &format!(
+                    "Native function `{}` has the type `{}`, which is not a function type",
+                    name, signature
+                ),
+            ),
+            Self::NativeFunctionAlreadyDefined { name } => print_error(
+                source,
+                None,
+                &format!("Native function `{}` is already defined", name),
+            ),
         }
     }
 }
 
+/// Sorts errors by their position in the source, so they read top to bottom
+/// regardless of the order they were found in. Errors without a range go last.
+pub fn sort_errors(errors: &mut [BauError]) {
+    errors.sort_by_key(|error| match error.range() {
+        Some(range) => (false, range.coords.line, range.coords.column),
+        None => (true, 0, 0),
+    });
+}
+
 impl From<parser::ParserError> for BauError {
     fn from(error: parser::ParserError) -> Self {
         Self::ParserError(error)
@@ -39,101 +109,158 @@ impl From<interpreter::ExecutionError> for BauError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum BauWarning {
+    TypecheckerWarning(typechecker::TypecheckerWarning),
+    /// A warning in the prelude. Its range refers to the prelude's source.
+    PreludeWarning(Box<BauWarning>),
+}
+
+impl BauWarning {
+    pub fn range(&self) -> &CodeRange {
+        match self {
+            Self::TypecheckerWarning(warning) => warning.range(),
+            Self::PreludeWarning(warning) => warning.range(),
+        }
+    }
+
+    pub fn print(&self, source: &Source) {
+        match self {
+            Self::TypecheckerWarning(warning) => warning.print(source),
+            Self::PreludeWarning(warning) => warning.print(source),
+        }
+    }
+}
+
+impl From<typechecker::TypecheckerWarning> for BauWarning {
+    fn from(warning: typechecker::TypecheckerWarning) -> Self {
+        Self::TypecheckerWarning(warning)
+    }
+}
+
 pub fn print_error(source: &Source, range: Option<&CodeRange>, message: &str) {
-    // Show error message
-    eprintln!("{}: {}", "error".bright_red(), message);
+    eprint!("{}", format_error(source, range, message));
+}
+
+pub fn print_warning(source: &Source, range: Option<&CodeRange>, message: &str) {
+    eprint!("{}", format_warning(source, range, message));
+}
+
+/// Renders an error the way [`print_error`] shows it, without printing it.
+pub fn format_error(source: &Source, range: Option<&CodeRange>, message: &str) -> String {
+    format_diagnostic(source, range, "error", Color::BrightRed, message)
+}
+
+/// Renders a warning the way [`print_warning`] shows it, without printing it.
+pub fn format_warning(source: &Source, range: Option<&CodeRange>, message: &str) -> String {
+    format_diagnostic(source, range, "warning", Color::BrightYellow, message)
+}
+
+// Columns and lengths are counted in characters, like the tokenizer counts
+// columns, so multi-byte characters don't shift the underline.
+fn format_diagnostic(
+    source: &Source,
+    range: Option<&CodeRange>,
+    label: &str,
+    color: Color,
+    message: &str,
+) -> String {
+    // Show diagnostic message
+    let mut output = format!("{}: {}\n", label.color(color), message);
 
     // If there is no range associated with the error, don't show the source code
-    if range.is_none() {
-        return;
-    }
-    let range = range.unwrap();
+    let Some(range) = range else {
+        return output;
+    };
 
     let max_line_number_len = source.lines().len().to_string().len();
 
+    // A range into another source can't be shown against this one
+    let Some(text) = source.text().get(range.span.start..range.span.end) else {
+        return output;
+    };
+
     // Show the line(s) of code that caused the error
-    let lines = source.text()[range.span.start..range.span.end].lines();
-    let line_count = lines.clone().count();
-    let mut cursor = 0;
+    let lines = text.lines();
     for (line_number, line) in lines.clone().enumerate() {
-        if line_number == 0 {
-            print_source_line(
-                source,
-                max_line_number_len,
-                range.coords.line,
-                range.coords.column,
-                line.len(),
-            )
-        } else if line_number == line_count - 1 {
-            let len = range.span.len() - cursor;
-            print_source_line(
-                source,
-                max_line_number_len,
-                range.coords.line + line_number,
-                0,
-                len,
-            )
-        } else {
-            print_source_line(
-                source,
-                max_line_number_len,
-                range.coords.line + line_number,
-                0,
-                line.len(),
-            )
-        }
-        cursor += line.len() + 1;
+        let column = match line_number {
+            0 => range.coords.column,
+            _ => 0,
+        };
+        output += &format_source_line(
+            source,
+            color,
+            max_line_number_len,
+            range.coords.line + line_number,
+            column,
+            line.chars().count(),
+        );
     }
 
     // Don't print the underline if it's a general error.
     if range.span == Span::new(0, 0) && range.coords == SourceCoords::new(0, 0) {
-        return;
+        return output;
     }
 
     // Print a underline to show where the error occurred
-    let underline_length = match line_count {
-        1 => range.span.len(),
-        _ => lines.map(|line| line.len()).max().unwrap_or(0),
-    };
-    print_line_gutter(max_line_number_len, None);
-    eprintln!(
-        "{}",
+    let underline_length = lines.map(|line| line.chars().count()).max().unwrap_or(0);
+    output += &format_line_gutter(max_line_number_len, None, color);
+    output += &format!(
+        "{}\n",
         format!(
             "{}{} {}",
             " ".repeat(range.coords.column),
             "^".repeat(usize::max(1, underline_length)),
             message,
         )
-        .bright_red()
+        .color(color)
     );
+    output
 }
 
-fn print_line_gutter(max_line_number_len: usize, line_number: Option<usize>) {
-    match line_number {
+fn format_line_gutter(
+    max_line_number_len: usize,
+    line_number: Option<usize>,
+    color: Color,
+) -> String {
+    let number = match line_number {
         Some(line_number) => {
             let padding = max_line_number_len - line_number.to_string().len();
-            eprint!(" {}{}", " ".repeat(padding), line_number);
-        }
-        None => {
-            eprint!(" {}", " ".repeat(max_line_number_len));
+            format!(" {}{}", " ".repeat(padding), line_number)
         }
-    }
-    eprint!(" {} ", "|".bright_red());
+        None => format!(" {}", " ".repeat(max_line_number_len)),
+    };
+    format!("{} {} ", number, "|".color(color))
 }
 
-fn print_source_line(
+fn format_source_line(
     source: &Source,
+    color: Color,
     max_line_number_len: usize,
     line_number: usize,
     column: usize,
     len: usize,
-) {
-    let line_number = match line_number >= source.lines().len() {
-        true => source.lines().len() - 1,
+) -> String {
+    let lines = source.lines();
+    let line_number = match line_number >= lines.len() {
+        true => lines.len() - 1,
         false => line_number,
     };
-    let (start, end) = source.lines()[line_number].split_at(column);
-    let (mid_error, end) = end.split_at(len);
-    print_line_gutter(max_line_number_len, Some(line_number + 1));
-    eprintln!("{}{}{}", start.white(), mid_error.bright_red(), end.white());
+    let line = lines[line_number];
+    let (start, end) = line.split_at(char_offset(line, column));
+    let (mid_error, end) = end.split_at(char_offset(end, len));
+    format!(
+        "{}{}{}{}\n",
+        format_line_gutter(max_line_number_len, Some(line_number + 1), color),
+        start.white(),
+        mid_error.color(color),
+        end.white()
+    )
+}
+
+/// Returns the byte offset of the `n`th character in `text`, clamped to its end.
+fn char_offset(text: &str, n: usize) -> usize {
+    text.char_indices()
+        .nth(n)
+        .map_or(text.len(), |(offset, _)| offset)
 }
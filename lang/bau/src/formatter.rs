@@ -0,0 +1,703 @@
+use crate::parser::error::ParserError;
+use crate::parser::{
+    infix_binding_power, is_comparison_operator, AssignmentOperator, Identifier, ParsedAttribute,
+    ParsedExpression, ParsedExpressionKind, ParsedFunctionCall, ParsedFunctionItem,
+    ParsedFunctionParameter, ParsedItemKind, ParsedPattern, ParsedStatement, ParsedStatementKind,
+    Parser, PrefixOperator, TypeName, TERNARY_BINDING_POWER,
+};
+use crate::source::{Source, Span};
+use crate::tokenizer::token::TokenKind;
+use crate::tokenizer::{Token, Tokenizer};
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comment {
+    start: usize,
+    text: String,
+    /// The end of the token this comment trails on the same line, if any.
+    trails: Option<usize>,
+}
+
+/// Pretty-prints a source file: four-space indentation, one space around
+/// infix operators and opening braces on the same line. Comments are
+/// preserved, as is a single blank line between statements.
+pub struct Formatter<'source> {
+    source: &'source Source<'source>,
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    comment_cursor: usize,
+    /// Comments taken out of the line being formatted, written above it.
+    moved_comments: Vec<String>,
+    /// Text put in front of the next line, like the `defer` of a statement.
+    line_prefix: &'static str,
+    output: String,
+    indent: usize,
+}
+
+impl<'source> Formatter<'source> {
+    pub fn new(source: &'source Source) -> Self {
+        let mut tokens = Tokenizer::new(source.text()).tokenize();
+
+        let mut comments = vec![];
+        let mut previous: Option<&Token> = None;
+        for token in tokens.iter() {
+            match token.kind() {
+                TokenKind::Whitespace => {}
+                TokenKind::Comment => {
+                    let range = token.range();
+                    comments.push(Comment {
+                        start: range.span.start,
+                        text: source.text()[range.span.start..range.span.end]
+                            .trim_end()
+                            .to_string(),
+                        trails: previous
+                            .filter(|p| p.range().coords.line == range.coords.line)
+                            .map(|p| p.range().span.end),
+                    });
+                }
+                _ => previous = Some(token),
+            }
+        }
+
+        tokens.retain(|token| !token.is(TokenKind::Whitespace) && !token.is(TokenKind::Comment));
+
+        Self {
+            source,
+            tokens,
+            comments,
+            comment_cursor: 0,
+            moved_comments: vec![],
+            line_prefix: "",
+            output: String::new(),
+            indent: 0,
+        }
+    }
+
+    pub fn format(mut self) -> Result<String, Vec<ParserError>> {
+        let items = Parser::new(self.source).parse_top_level()?;
+
+        for (index, item) in items.iter().enumerate() {
+            // Consecutive constants are kept together.
+            let follows_const = index > 0
+                && matches!(items[index - 1].kind(), ParsedItemKind::Const(_))
+                && matches!(item.kind(), ParsedItemKind::Const(_));
+            if !follows_const {
+                self.blank_line();
+            }
+            self.comments_before(item.range().span.start);
+            self.preserve_blank_line(item.range().span.start);
+            match item.kind() {
+                ParsedItemKind::Function(function) => self.function(function),
+                ParsedItemKind::Extend(extend) => {
+                    let open =
+                        self.next_token_offset(item.range().span.start, TokenKind::BraceOpen);
+                    let header = match &extend.trait_name {
+                        Some(trait_name) => format!(
+                            "extend {}: {} {{",
+                            extend.type_name.name(),
+                            trait_name.name()
+                        ),
+                        None => format!("extend {} {{", extend.type_name.name()),
+                    };
+                    self.line(header, open);
+                    self.indent += 1;
+                    for (index, function) in extend.functions.iter().enumerate() {
+                        if index > 0 {
+                            self.blank_line();
+                        }
+                        self.comments_before(function.range.span.start);
+                        self.preserve_blank_line(function.range.span.start);
+                        self.function(function);
+                    }
+                    self.comments_before(item.range().span.end);
+                    self.indent -= 1;
+                    self.line("}".to_string(), item.range().span.end);
+                }
+                ParsedItemKind::Const(constant) => {
+                    let text = format!(
+                        "const {} {} = {};",
+                        constant.type_name.name(),
+                        constant.name.name(),
+                        self.expression(&constant.value, 0)
+                    );
+                    self.line(text, item.range().span.end);
+                }
+                ParsedItemKind::Enum(enum_item) => {
+                    let open =
+                        self.next_token_offset(item.range().span.start, TokenKind::BraceOpen);
+                    self.line(format!("enum {} {{", enum_item.name.name()), open);
+                    self.indent += 1;
+                    for variant in enum_item.variants.iter() {
+                        let start = variant.name.token().range().span.start;
+                        self.comments_before(start);
+                        self.preserve_blank_line(start);
+                        let (text, end) = match &variant.value {
+                            Some(value) => (
+                                format!("{} = {},", variant.name.name(), self.expression(value, 0)),
+                                value.range().span.end,
+                            ),
+                            None => (
+                                format!("{},", variant.name.name()),
+                                variant.name.token().range().span.end,
+                            ),
+                        };
+                        self.line(text, end);
+                    }
+                    self.comments_before(item.range().span.end);
+                    self.indent -= 1;
+                    self.line("}".to_string(), item.range().span.end);
+                }
+                ParsedItemKind::Struct(struct_item) => {
+                    let open =
+                        self.next_token_offset(item.range().span.start, TokenKind::BraceOpen);
+                    self.line(format!("struct {} {{", struct_item.name.name()), open);
+                    self.indent += 1;
+                    for field in struct_item.fields.iter() {
+                        let start = field.type_name.token().range().span.start;
+                        self.comments_before(start);
+                        self.preserve_blank_line(start);
+                        let text = format!("{} {};", field.type_name.name(), field.name.name());
+                        let end = self.next_token_offset(
+                            field.name.token().range().span.end,
+                            TokenKind::Semicolon,
+                        );
+                        self.line(text, end);
+                    }
+                    self.comments_before(item.range().span.end);
+                    self.indent -= 1;
+                    self.line("}".to_string(), item.range().span.end);
+                }
+                ParsedItemKind::Trait(trait_item) => {
+                    let open =
+                        self.next_token_offset(item.range().span.start, TokenKind::BraceOpen);
+                    self.line(format!("trait {} {{", trait_item.name.name()), open);
+                    self.indent += 1;
+                    for method in trait_item.methods.iter() {
+                        self.comments_before(method.range.span.start);
+                        self.preserve_blank_line(method.range.span.start);
+                        let text = format!(
+                            "{};",
+                            signature(&method.name, &method.parameters, &method.return_type_name)
+                        );
+                        self.line(text, method.range.span.end);
+                    }
+                    self.comments_before(item.range().span.end);
+                    self.indent -= 1;
+                    self.line("}".to_string(), item.range().span.end);
+                }
+            }
+        }
+        self.comments_before(usize::MAX);
+
+        Ok(self.output)
+    }
+
+    fn function(&mut self, function: &ParsedFunctionItem) {
+        for attribute in function.attributes.iter() {
+            let text = self.attribute(attribute);
+            self.line(text, attribute.range.span.end);
+        }
+
+        let header = format!(
+            "{} {{",
+            signature(
+                &function.name,
+                &function.parameters,
+                &function.return_type_name
+            )
+        );
+        let open =
+            self.next_token_offset(function.name.token().range().span.end, TokenKind::BraceOpen);
+        self.line(header, open);
+        self.block(&function.body, function.range.span.end);
+        self.line("}".to_string(), function.range.span.end);
+    }
+
+    fn attribute(&mut self, attribute: &ParsedAttribute) -> String {
+        let mut text = format!("@{}", attribute.name.name());
+        if self.next_token_kind(attribute.name.token().range().span.end)
+            == Some(TokenKind::ParenOpen)
+        {
+            text.push_str(&format!("({})", self.arguments(&attribute.arguments)));
+        }
+        text
+    }
+
+    /// Formats the statements of a block whose closing brace ends at `close`.
+    fn block(&mut self, statements: &[ParsedStatement], close: usize) {
+        self.indent += 1;
+        for statement in statements {
+            let start = statement.range().span.start;
+            self.comments_before(start);
+            self.preserve_blank_line(start);
+            self.statement(statement);
+        }
+        self.comments_before(close - 1);
+        self.indent -= 1;
+    }
+
+    fn statement(&mut self, statement: &ParsedStatement) {
+        let range = statement.range();
+        match statement.kind() {
+            ParsedStatementKind::Let {
+                name,
+                type_name,
+                initial_value,
+                mutable,
+            } => {
+                let text = format!(
+                    "let {}{} {} = {};",
+                    if *mutable { "mut " } else { "" },
+                    type_name.name(),
+                    name.name(),
+                    self.expression(initial_value, 0)
+                );
+                self.line(text, range.span.end);
+            }
+            ParsedStatementKind::DestructuringLet {
+                bindings,
+                initial_value,
+            } => {
+                let bindings = bindings
+                    .iter()
+                    .map(|binding| format!("{} {}", binding.type_name.name(), binding.name.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let text = format!(
+                    "let ({}) = {};",
+                    bindings,
+                    self.expression(initial_value, 0)
+                );
+                self.line(text, range.span.end);
+            }
+            ParsedStatementKind::VariableAssignment {
+                name,
+                value,
+                operator,
+            } => {
+                let text = format!(
+                    "{} {} {};",
+                    name.name(),
+                    assignment_operator(*operator),
+                    self.expression(value, 0)
+                );
+                self.line(text, range.span.end);
+            }
+            ParsedStatementKind::Return { value } => {
+                let text = match value {
+                    Some(value) => format!("return {};", self.expression(value, 0)),
+                    None => "return;".to_string(),
+                };
+                self.line(text, range.span.end);
+            }
+            ParsedStatementKind::Expression { expression } => {
+                let text = format!("{};", self.expression(expression, 0));
+                self.line(text, range.span.end);
+            }
+            ParsedStatementKind::Break => self.line("break;".to_string(), range.span.end),
+            ParsedStatementKind::Continue => self.line("continue;".to_string(), range.span.end),
+            ParsedStatementKind::FunctionDef(function) => self.function(function),
+            ParsedStatementKind::Defer { statement } => {
+                // The deferred statement is formatted as usual, with `defer`
+                // in front of its first line.
+                self.line_prefix = "defer ";
+                self.statement(statement);
+            }
+            ParsedStatementKind::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                let header = format!("if {}", self.condition(condition));
+                self.if_statement(header, then_body, else_body.as_deref(), range.span);
+            }
+            ParsedStatementKind::IfLet {
+                name,
+                type_name,
+                value,
+                then_body,
+                else_body,
+            } => {
+                let header = format!(
+                    "if let {} {} = {} ",
+                    type_name.name(),
+                    name.name(),
+                    self.expression(value, 0)
+                );
+                self.if_statement(header, then_body, else_body.as_deref(), range.span);
+            }
+            ParsedStatementKind::Loop { body } => {
+                let open = self.next_token_offset(range.span.start, TokenKind::BraceOpen);
+                self.line("loop {".to_string(), open);
+                self.block(body, range.span.end);
+                self.line("}".to_string(), range.span.end);
+            }
+            ParsedStatementKind::While { condition, block } => {
+                let open = self.next_token_offset(range.span.start, TokenKind::BraceOpen);
+                let header = format!("while {}{{", self.condition(condition));
+                self.line(header, open);
+                self.block(block, range.span.end);
+                self.line("}".to_string(), range.span.end);
+            }
+        }
+    }
+
+    /// Formats the bodies of an `if` or `if let` whose `header` runs up to its
+    /// opening brace.
+    fn if_statement(
+        &mut self,
+        header: String,
+        then_body: &[ParsedStatement],
+        else_body: Option<&[ParsedStatement]>,
+        span: Span,
+    ) {
+        let open = self.next_token_offset(span.start, TokenKind::BraceOpen);
+        let then_close = self.matching_brace(open);
+        self.line(format!("{}{{", header), open);
+        self.block(then_body, then_close);
+        match else_body {
+            Some(else_body) => {
+                let else_open = self.next_token_offset(then_close, TokenKind::BraceOpen);
+                self.line("} else {".to_string(), else_open);
+                self.block(else_body, span.end);
+                self.line("}".to_string(), span.end);
+            }
+            None => self.line("}".to_string(), then_close),
+        }
+    }
+
+    fn condition(&mut self, condition: &Option<ParsedExpression>) -> String {
+        match condition {
+            Some(condition) => format!("{} ", self.expression(condition, 0)),
+            None => String::new(),
+        }
+    }
+
+    /// Formats an expression that the parser reads with `min_binding_power`.
+    fn expression(&mut self, expression: &ParsedExpression, min_binding_power: u8) -> String {
+        let comments = self.expression_comments(expression.range().span.start);
+        let text = match expression.kind() {
+            ParsedExpressionKind::Literal(_) => {
+                let span = expression.range().span;
+                self.source.text()[span.start..span.end].to_string()
+            }
+            ParsedExpressionKind::Variable(identifier) => identifier.name().to_string(),
+            ParsedExpressionKind::FunctionCall(call) => self.function_call(call),
+            ParsedExpressionKind::PrefixOperator {
+                operator,
+                expression,
+            } => {
+                let operand = match expression.kind() {
+                    ParsedExpressionKind::Literal(_)
+                    | ParsedExpressionKind::Variable(_)
+                    | ParsedExpressionKind::FunctionCall(_)
+                    | ParsedExpressionKind::MemberAccess { .. } => self.expression(expression, 0),
+                    _ => format!("({})", self.expression(expression, 0)),
+                };
+                format!("{}{}", prefix_operator(*operator), operand)
+            }
+            ParsedExpressionKind::InfixOperator {
+                operator,
+                left,
+                right,
+            } => {
+                let (left_power, right_power) =
+                    infix_binding_power(*operator).expect("operator should be an infix operator");
+                let left = match is_chained_comparison(*operator, left) {
+                    true => format!("({})", self.expression(left, 0)),
+                    false => self.operand(left, min_binding_power, left_power),
+                };
+                let right = match is_chained_comparison(*operator, right) {
+                    true => format!("({})", self.expression(right, 0)),
+                    false => self.operand(right, right_power, right_power),
+                };
+                format!("{} {} {}", left, operator, right)
+            }
+            ParsedExpressionKind::Ternary {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition =
+                    self.operand(condition, min_binding_power, TERNARY_BINDING_POWER + 1);
+                let then_branch = self.expression(then_branch, 0);
+                let else_branch =
+                    self.operand(else_branch, TERNARY_BINDING_POWER, TERNARY_BINDING_POWER);
+                format!("{} ? {} : {}", condition, then_branch, else_branch)
+            }
+            ParsedExpressionKind::MethodCall { expression, call } => {
+                let receiver = match expression.kind() {
+                    ParsedExpressionKind::InfixOperator { .. }
+                    | ParsedExpressionKind::PrefixOperator { .. }
+                    | ParsedExpressionKind::Ternary { .. } => {
+                        format!("({})", self.expression(expression, 0))
+                    }
+                    _ => self.expression(expression, 0),
+                };
+                format!("{}.{}", receiver, self.function_call(call))
+            }
+            ParsedExpressionKind::MemberAccess { expression, member } => {
+                let receiver = match expression.kind() {
+                    ParsedExpressionKind::InfixOperator { .. }
+                    | ParsedExpressionKind::PrefixOperator { .. }
+                    | ParsedExpressionKind::Ternary { .. } => {
+                        format!("({})", self.expression(expression, 0))
+                    }
+                    _ => self.expression(expression, 0),
+                };
+                format!("{}.{}", receiver, member.name())
+            }
+            ParsedExpressionKind::Struct { name, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{}: {}",
+                            field.name.name(),
+                            self.expression(&field.value, 0)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} {{ {} }}", name.name(), fields)
+            }
+            ParsedExpressionKind::Tuple(elements) => format!("({})", self.arguments(elements)),
+            ParsedExpressionKind::Match { value, arms } => {
+                let value = self.expression(value, 0);
+                let arms = arms
+                    .iter()
+                    .map(|arm| {
+                        let pattern = match &arm.pattern {
+                            ParsedPattern::Binding(name) => name.name().to_string(),
+                            ParsedPattern::Value(pattern) => self.expression(pattern, 0),
+                        };
+                        let guard = match &arm.guard {
+                            Some(guard) => format!(" if {}", self.expression(guard, 0)),
+                            None => String::new(),
+                        };
+                        format!("{}{} => {}", pattern, guard, self.expression(&arm.value, 0))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("match {} {{ {} }}", value, arms)
+            }
+        };
+        format!("{}{}", comments, text)
+    }
+
+    /// Formats an infix operand that the parser reads with `min_binding_power`,
+    /// wrapping it in parentheses if it would not bind tighter than `precedence`.
+    fn operand(
+        &mut self,
+        operand: &ParsedExpression,
+        min_binding_power: u8,
+        precedence: u8,
+    ) -> String {
+        let needs_parentheses = match operand.kind() {
+            ParsedExpressionKind::InfixOperator { operator, .. } => {
+                infix_binding_power(*operator).is_some_and(|(left, _)| left < precedence)
+            }
+            // Method calls bind loosest of all, so they only parse back as
+            // an operand when nothing else is pending.
+            ParsedExpressionKind::MethodCall { .. } => min_binding_power > 0,
+            // Only `name.member` binds tightly, like `Status.NotFound`. Any
+            // other member access parses like a method call.
+            ParsedExpressionKind::MemberAccess { expression, .. } => {
+                !matches!(expression.kind(), ParsedExpressionKind::Variable(_))
+                    && min_binding_power > 0
+            }
+            ParsedExpressionKind::Ternary { .. } => TERNARY_BINDING_POWER < precedence,
+            _ => false,
+        };
+        match needs_parentheses {
+            true => format!("({})", self.expression(operand, 0)),
+            false => self.expression(operand, min_binding_power),
+        }
+    }
+
+    fn function_call(&mut self, call: &ParsedFunctionCall) -> String {
+        format!("{}({})", call.name.name(), self.arguments(&call.arguments))
+    }
+
+    fn arguments(&mut self, arguments: &[ParsedExpression]) -> String {
+        arguments
+            .iter()
+            .map(|argument| self.expression(argument, 0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Writes an indented line. `end` is the source offset of the last token
+    /// on the line, used to keep a trailing comment on the same line.
+    fn line(&mut self, text: String, end: usize) {
+        // Comments inside the line that could not keep their place go above it.
+        let comments = self.take_comments(end);
+        self.moved_comments.extend(comments);
+        for comment in std::mem::take(&mut self.moved_comments) {
+            self.write_indented(&comment);
+            self.output.push('\n');
+        }
+
+        let prefix = std::mem::take(&mut self.line_prefix);
+        self.write_indented(&format!("{}{}", prefix, text));
+        if let Some(comment) = self.comments.get(self.comment_cursor) {
+            if comment.trails == Some(end) {
+                self.output.push(' ');
+                self.output.push_str(&comment.text);
+                self.comment_cursor += 1;
+            }
+        }
+        self.output.push('\n');
+    }
+
+    /// Takes the comments in front of an expression starting at `offset`.
+    /// Block comments are returned to be kept in place, while line comments,
+    /// which would swallow the rest of the line, are moved above it.
+    fn expression_comments(&mut self, offset: usize) -> String {
+        let mut inline = String::new();
+        for comment in self.take_comments(offset) {
+            match comment.starts_with("/*") {
+                true => inline.push_str(&format!("{} ", comment)),
+                false => self.moved_comments.push(comment),
+            }
+        }
+        inline
+    }
+
+    /// Takes the text of the comments before `offset` that have not been
+    /// written yet.
+    fn take_comments(&mut self, offset: usize) -> Vec<String> {
+        let mut comments = vec![];
+        while let Some(comment) = self.comments.get(self.comment_cursor) {
+            if comment.start >= offset {
+                break;
+            }
+            comments.push(comment.text.clone());
+            self.comment_cursor += 1;
+        }
+        comments
+    }
+
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.comment_cursor) {
+            if comment.start >= offset {
+                break;
+            }
+            let start = comment.start;
+            let text = comment.text.clone();
+            self.preserve_blank_line(start);
+            self.write_indented(&text);
+            self.output.push('\n');
+            self.comment_cursor += 1;
+        }
+    }
+
+    fn write_indented(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(text);
+    }
+
+    /// Keeps a single blank line before `offset` if the source has one.
+    fn preserve_blank_line(&mut self, offset: usize) {
+        let preceding = &self.source.text()[..offset];
+        let whitespace = &preceding[preceding.trim_end().len()..];
+        if whitespace.matches('\n').count() >= 2 {
+            self.blank_line();
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty()
+            && !self.output.ends_with("\n\n")
+            && !self.output.ends_with("{\n")
+        {
+            self.output.push('\n');
+        }
+    }
+
+    fn next_token_kind(&self, offset: usize) -> Option<TokenKind> {
+        self.tokens
+            .iter()
+            .find(|token| token.range().span.start >= offset)
+            .map(|token| token.kind())
+    }
+
+    /// Returns the end offset of the first `kind` token at or after `offset`.
+    fn next_token_offset(&self, offset: usize, kind: TokenKind) -> usize {
+        self.tokens
+            .iter()
+            .find(|token| token.range().span.start >= offset && token.is(kind))
+            .map(|token| token.range().span.end)
+            .expect("parsed source should contain the token")
+    }
+
+    /// Returns the end offset of the brace closing the one that ends at `open`.
+    fn matching_brace(&self, open: usize) -> usize {
+        let mut depth = 0;
+        for token in self.tokens.iter() {
+            if token.range().span.start < open {
+                continue;
+            }
+            match token.kind() {
+                TokenKind::BraceOpen => depth += 1,
+                TokenKind::BraceClose if depth == 0 => return token.range().span.end,
+                TokenKind::BraceClose => depth -= 1,
+                _ => {}
+            }
+        }
+        panic!("parsed source should have balanced braces")
+    }
+}
+
+fn signature(
+    name: &Identifier,
+    parameters: &[ParsedFunctionParameter],
+    return_type_name: &TypeName,
+) -> String {
+    let parameters = parameters
+        .iter()
+        .map(|p| format!("{} {}", p.type_name.name(), p.name.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "fn {}({}) -> {}",
+        name.name(),
+        parameters,
+        return_type_name.name()
+    )
+}
+
+fn assignment_operator(operator: AssignmentOperator) -> &'static str {
+    match operator {
+        AssignmentOperator::Equals => "=",
+        AssignmentOperator::PlusEquals => "+=",
+        AssignmentOperator::MinusEquals => "-=",
+        AssignmentOperator::AsteriskEquals => "*=",
+        AssignmentOperator::SlashEquals => "/=",
+        AssignmentOperator::PercentEquals => "%=",
+        AssignmentOperator::AmpersandAmpersandEquals => "&&=",
+        AssignmentOperator::PipePipeEquals => "||=",
+    }
+}
+
+fn prefix_operator(operator: PrefixOperator) -> &'static str {
+    match operator {
+        PrefixOperator::Plus => "+",
+        PrefixOperator::Minus => "-",
+        PrefixOperator::ExclamationMark => "!",
+    }
+}
+
+/// Whether `operand` is a comparison used as an operand of the comparison
+/// `operator`. Such an operand keeps its parentheses, because without them the
+/// typechecker rejects it as a chained comparison.
+fn is_chained_comparison(operator: TokenKind, operand: &ParsedExpression) -> bool {
+    is_comparison_operator(operator)
+        && matches!(
+            operand.kind(),
+            ParsedExpressionKind::InfixOperator { operator, .. } if is_comparison_operator(*operator)
+        )
+}
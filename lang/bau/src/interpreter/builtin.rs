@@ -1,39 +1,48 @@
 use std::collections::HashMap;
+use std::io::Write;
 
 use lazy_static::lazy_static;
 
-use crate::typechecker::{CheckedExpression, CheckedFunctionDefinition};
+use crate::source::CodeRange;
+use crate::typechecker::{
+    CheckedExpression, CheckedExpressionKind, CheckedFunctionDefinition, Type,
+};
 
-use super::error::ExecutionResult;
+use super::error::{ExecutionError, ExecutionErrorKind, ExecutionResult};
 use super::value::Value;
 use super::Interpreter;
 
 macro_rules! type_name_to_type {
-    ($type_name:ident) => {
+    ($type_name:tt) => {
         match stringify!($type_name) {
             "void" => crate::typechecker::Type::Void,
             "string" => crate::typechecker::Type::String,
             "int" => crate::typechecker::Type::Integer,
             "float" => crate::typechecker::Type::Float,
             "bool" => crate::typechecker::Type::Boolean,
+            "[string]" => {
+                crate::typechecker::Type::Array(Box::new(crate::typechecker::Type::String))
+            }
+            "[int]" => crate::typechecker::Type::Array(Box::new(crate::typechecker::Type::Integer)),
             _ => panic!("Unknown type: `{}`", stringify!($type_name)),
         }
     };
 }
 
 macro_rules! function_definition {
-    (fn $name:ident($($arg_name:ident: $arg_type:ident),*) -> $return_type:ident) => {
+    (fn $name:ident($($arg_name:ident: $arg_type:tt),*) -> $return_type:tt) => {
         CheckedFunctionDefinition {
             name: stringify!($name).to_string(),
             parameters: vec![
                 $(
-                    crate::typechecker::CheckedFunctionParameter     {
+                    crate::typechecker::CheckedFunctionParameter {
                         name: stringify!($arg_name).to_string(),
-                        type_: type_name_to_type!($return_type),
+                        type_: type_name_to_type!($arg_type),
                     }
                 ),*
             ],
             return_type: type_name_to_type!($return_type),
+            deprecation: None,
         }
     };
 }
@@ -45,15 +54,181 @@ lazy_static! {
             "print",
             function_definition!(fn print(value: string) -> void),
         );
+        map.insert(
+            "assert",
+            function_definition!(fn assert(condition: bool) -> void),
+        );
+        map.insert(
+            "assert_eq",
+            function_definition!(fn assert_eq(left: int, right: int) -> void),
+        );
+        map.insert(
+            "panic",
+            function_definition!(fn panic(message: string) -> void),
+        );
+        map.insert(
+            "join",
+            function_definition!(fn join(parts: [string], separator: string) -> string),
+        );
+        map.insert(
+            "repeat",
+            function_definition!(fn repeat(value: string, count: int) -> string),
+        );
+        map.insert(
+            "clamp",
+            function_definition!(fn clamp(value: float, min: float, max: float) -> float),
+        );
+        map.insert(
+            "lerp",
+            function_definition!(fn lerp(from: float, to: float, t: float) -> float),
+        );
+        map.insert(
+            "map_range",
+            function_definition!(
+                fn map_range(
+                    value: float,
+                    from_min: float,
+                    from_max: float,
+                    to_min: float,
+                    to_max: float
+                ) -> float
+            ),
+        );
+        map.insert(
+            "to_string",
+            function_definition!(fn to_string(value: float, precision: int) -> string),
+        );
+        map.insert("round", function_definition!(fn round(value: float) -> int));
+        map.insert(
+            "to_hex",
+            function_definition!(fn to_hex(value: int) -> string),
+        );
+        map.insert(
+            "to_binary",
+            function_definition!(fn to_binary(value: int) -> string),
+        );
+        map.insert(
+            "random_int",
+            function_definition!(fn random_int(min: int, max: int) -> int),
+        );
+        map.insert(
+            "random_float",
+            function_definition!(fn random_float() -> float),
+        );
+        // `pow` only takes a float base. An int base goes through `ipow`, which
+        // never promotes its result to a float and therefore rejects negative
+        // exponents with an error.
+        map.insert(
+            "pow",
+            function_definition!(fn pow(base: float, exponent: int) -> float),
+        );
+        map.insert(
+            "ipow",
+            function_definition!(fn ipow(base: int, exponent: int) -> int),
+        );
         map
     };
 }
 
+lazy_static! {
+    /// Methods available on builtin types without an `extend` block.
+    pub static ref BUILTIN_METHODS: Vec<(Type, CheckedFunctionDefinition)> = vec![
+        (
+            Type::String,
+            function_definition!(fn split(separator: string) -> [string]),
+        ),
+        (
+            Type::String,
+            function_definition!(fn contains(needle: string) -> bool),
+        ),
+        (
+            Type::String,
+            function_definition!(fn index_of(needle: string) -> int),
+        ),
+        (
+            Type::String,
+            function_definition!(fn append(value: string) -> void),
+        ),
+        // There is no `char` type, so each char is a one-char string.
+        (
+            Type::String,
+            function_definition!(fn chars() -> [string]),
+        ),
+        (
+            Type::String,
+            function_definition!(fn bytes() -> [int]),
+        ),
+        (
+            Type::String,
+            function_definition!(fn lines() -> [string]),
+        ),
+        (
+            Type::Integer,
+            function_definition!(fn to_string() -> string),
+        ),
+        (
+            Type::Float,
+            function_definition!(fn to_string() -> string),
+        ),
+        (
+            Type::Boolean,
+            function_definition!(fn to_string() -> string),
+        ),
+    ];
+}
+
+/// Builtin methods that modify the variable they are called on, so their
+/// receiver has to be a variable.
+pub const MUTATING_METHODS: &[(Type, &str)] = &[(Type::String, "append")];
+
+pub fn method_mutates(type_: &Type, name: &str) -> bool {
+    MUTATING_METHODS
+        .iter()
+        .any(|(method_type, method_name)| method_type == type_ && *method_name == name)
+}
+
+pub fn method_is_builtin(type_: &Type, name: &str) -> bool {
+    if matches!(type_, Type::Enum(_)) {
+        return name == ENUM_TO_INT_METHOD;
+    }
+    BUILTIN_METHODS
+        .iter()
+        .any(|(method_type, method)| method_type == type_ && method.name == name)
+}
+
+/// Builtins that take an optional of any inner type. These can't be described
+/// by a `CheckedFunctionDefinition`, so the typechecker checks them separately.
+pub const OPTIONAL_FUNCTIONS: &[&str] = &["is_some", "is_none", "unwrap"];
+
+/// Returns the name of its argument's static type. The typechecker replaces
+/// calls to it with a string literal, so the argument is never evaluated.
+pub const TYPEOF_FUNCTION: &str = "typeof";
+
+/// Whether `name` is a builtin function, which user and native functions
+/// can't be named after.
+pub fn is_builtin_function(name: &str) -> bool {
+    BUILTIN_FUNCTIONS.contains_key(name)
+        || OPTIONAL_FUNCTIONS.contains(&name)
+        || name == TYPEOF_FUNCTION
+}
+
+/// The method that converts an enum variant to its value.
+pub const ENUM_TO_INT_METHOD: &str = "to_int";
+
+/// Called on an enum itself, as in `Status.from_int(404)`, to look up the
+/// variant with a value.
+pub const ENUM_FROM_INT_FUNCTION: &str = "from_int";
+
 pub fn evaluate_builtin_function(
     interpreter: &mut Interpreter,
     name: &str,
     arguments: &[CheckedExpression],
-) -> ExecutionResult<Option<Value>> {
+    range: CodeRange,
+) -> ExecutionResult<Value> {
+    if OPTIONAL_FUNCTIONS.contains(&name) {
+        return evaluate_optional_function(interpreter, name, arguments, range);
+    }
+
     let builtin_definition = BUILTIN_FUNCTIONS.get(name).unwrap();
 
     assert_eq!(
@@ -66,12 +241,313 @@ pub fn evaluate_builtin_function(
 
     match name {
         "print" => {
-            match interpreter.evaluate_expression(&arguments[0])? {
-                Some(value) => println!("{}", value),
-                None => println!("None"),
+            let value = interpreter.evaluate_expression(&arguments[0])?;
+            writeln!(interpreter.output, "{}", value).expect("Failed to write output");
+            Ok(Value::Void)
+        }
+        "assert" => match interpreter.evaluate_expression(&arguments[0])?.is_true() {
+            true => Ok(Value::Void),
+            false => {
+                Err(
+                    ExecutionError::new(ExecutionErrorKind::AssertionFailed { message: None })
+                        .with_range(range),
+                )
+            }
+        },
+        "assert_eq" => {
+            let left = interpreter.evaluate_expression(&arguments[0])?;
+            let right = interpreter.evaluate_expression(&arguments[1])?;
+            if left == right {
+                return Ok(Value::Void);
+            }
+            Err(ExecutionError::new(ExecutionErrorKind::AssertionFailed {
+                message: Some(format!("`{}` is not equal to `{}`", left, right)),
+            })
+            .with_range(range))
+        }
+        "panic" => {
+            let Value::String(message) = interpreter.evaluate_expression(&arguments[0])? else {
+                panic!("Typechecker should have checked argument types");
+            };
+            Err(ExecutionError::new(ExecutionErrorKind::UserPanic { message }).with_range(range))
+        }
+        "join" => {
+            let Value::Array(parts) = interpreter.evaluate_expression(&arguments[0])? else {
+                panic!("Typechecker should have checked argument types");
+            };
+            let Value::String(separator) = interpreter.evaluate_expression(&arguments[1])? else {
+                panic!("Typechecker should have checked argument types");
+            };
+            let parts = parts
+                .into_iter()
+                .map(|part| match part {
+                    Value::String(part) => part,
+                    _ => panic!("Typechecker should have checked argument types"),
+                })
+                .collect::<Vec<_>>();
+            Ok(Value::String(parts.join(&separator)))
+        }
+        "repeat" => {
+            let (Value::String(value), Value::Integer(count)) = (
+                interpreter.evaluate_expression(&arguments[0])?,
+                interpreter.evaluate_expression(&arguments[1])?,
+            ) else {
+                panic!("Typechecker should have checked argument types");
+            };
+            let Ok(count) = usize::try_from(count) else {
+                return Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!(
+                        "Cannot repeat a string a negative number of times, `{}`",
+                        count
+                    ),
+                })
+                .with_range(range));
+            };
+            if value.len().checked_mul(count).is_none() {
+                return Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!(
+                        "Cannot repeat a string `{}` times, the result is too large",
+                        count
+                    ),
+                })
+                .with_range(range));
+            }
+            Ok(Value::String(value.repeat(count)))
+        }
+        // Negative numbers are written with a `-` sign rather than in two's
+        // complement, so `to_hex(-255)` is `-ff`. There is no `0x` or `0b` prefix.
+        "to_hex" | "to_binary" => {
+            let Value::Integer(value) = interpreter.evaluate_expression(&arguments[0])? else {
+                panic!("Typechecker should have checked argument types");
+            };
+            let sign = if value < 0 { "-" } else { "" };
+            let digits = match name {
+                "to_hex" => format!("{:x}", value.unsigned_abs()),
+                _ => format!("{:b}", value.unsigned_abs()),
+            };
+            Ok(Value::String(format!("{}{}", sign, digits)))
+        }
+        "clamp" => {
+            let [value, min, max] = evaluate_float_arguments(interpreter, arguments)?;
+            if min > max {
+                return Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!(
+                        "`clamp` minimum `{}` is greater than maximum `{}`",
+                        min, max
+                    ),
+                })
+                .with_range(range));
+            }
+            Ok(Value::Float(value.clamp(min, max)))
+        }
+        "lerp" => {
+            let [from, to, t] = evaluate_float_arguments(interpreter, arguments)?;
+            Ok(Value::Float(from + (to - from) * t))
+        }
+        "map_range" => {
+            let [value, from_min, from_max, to_min, to_max] =
+                evaluate_float_arguments(interpreter, arguments)?;
+            let t = (value - from_min) / (from_max - from_min);
+            Ok(Value::Float(to_min + (to_max - to_min) * t))
+        }
+        "to_string" => {
+            let (Value::Float(value), Value::Integer(precision)) = (
+                interpreter.evaluate_expression(&arguments[0])?,
+                interpreter.evaluate_expression(&arguments[1])?,
+            ) else {
+                panic!("Typechecker should have checked argument types");
+            };
+            let Ok(precision) = usize::try_from(precision) else {
+                return Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!("Precision `{}` cannot be negative", precision),
+                })
+                .with_range(range));
+            };
+            Ok(Value::String(format!("{:.*}", precision, value)))
+        }
+        "round" => {
+            let [value] = evaluate_float_arguments(interpreter, arguments)?;
+            let rounded = value.round();
+            // `i64::MAX as f64` rounds up to 2^63, which is already out of range.
+            if !rounded.is_finite() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                return Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!("Cannot round `{}` to an int", value),
+                })
+                .with_range(range));
+            }
+            Ok(Value::Integer(rounded as i64))
+        }
+        "random_int" => {
+            let (Value::Integer(min), Value::Integer(max)) = (
+                interpreter.evaluate_expression(&arguments[0])?,
+                interpreter.evaluate_expression(&arguments[1])?,
+            ) else {
+                panic!("Typechecker should have checked argument types");
+            };
+            match interpreter.rng.int_in_range(min, max) {
+                Some(value) => Ok(Value::Integer(value)),
+                None => Err(ExecutionError::new(ExecutionErrorKind::Panic {
+                    message: format!("`random_int({}, {})` has an empty range", min, max),
+                })
+                .with_range(range)),
+            }
+        }
+        "random_float" => Ok(Value::Float(interpreter.rng.float())),
+        "pow" => {
+            let (base, exponent) = evaluate_pow_arguments(interpreter, arguments, range)?;
+            let Value::Float(base) = base else {
+                panic!("Typechecker should have checked argument types");
+            };
+            Ok(Value::Float(base.powi(exponent)))
+        }
+        "ipow" => {
+            // Integer exponentiation never promotes to a float, so a negative
+            // exponent has no meaningful result and aborts execution instead.
+            let (base, exponent) = evaluate_pow_arguments(interpreter, arguments, range)?;
+            let Value::Integer(base) = base else {
+                panic!("Typechecker should have checked argument types");
+            };
+            if exponent < 0 {
+                return Err(ExecutionError::new(ExecutionErrorKind::NegativeExponent {
+                    exponent: exponent.into(),
+                })
+                .with_range(range));
+            }
+            match base.checked_pow(exponent as u32) {
+                Some(value) => Ok(Value::Integer(value)),
+                None => Err(ExecutionError::new(ExecutionErrorKind::PowerOverflow {
+                    base,
+                    exponent: exponent.into(),
+                })
+                .with_range(range)),
             }
-            Ok(None)
         }
         _ => panic!("Unknown builtin function `{}`", name),
     }
 }
+
+pub fn evaluate_builtin_method(
+    interpreter: &mut Interpreter,
+    receiver: &CheckedExpression,
+    name: &str,
+    arguments: &[CheckedExpression],
+) -> ExecutionResult<Value> {
+    let receiver_value = interpreter.evaluate_expression(receiver)?;
+    if name == "to_string" {
+        return Ok(Value::String(receiver_value.to_string()));
+    }
+    // Enum variants are already represented by their values.
+    if name == ENUM_TO_INT_METHOD {
+        return Ok(receiver_value);
+    }
+
+    let Value::String(receiver_value) = receiver_value else {
+        panic!("Unknown builtin method `{}` on `{}`", name, receiver_value);
+    };
+    match name {
+        "chars" => {
+            return Ok(Value::Array(
+                receiver_value
+                    .chars()
+                    .map(|char| Value::String(char.to_string()))
+                    .collect(),
+            ))
+        }
+        "bytes" => {
+            return Ok(Value::Array(
+                receiver_value
+                    .bytes()
+                    .map(|byte| Value::Integer(byte as i64))
+                    .collect(),
+            ))
+        }
+        "lines" => {
+            return Ok(Value::Array(
+                receiver_value
+                    .split('\n')
+                    .map(|line| Value::String(line.to_string()))
+                    .collect(),
+            ))
+        }
+        _ => {}
+    }
+
+    let Value::String(argument) = interpreter.evaluate_expression(&arguments[0])? else {
+        panic!("Typechecker should have checked argument types");
+    };
+
+    match name {
+        "append" => {
+            let CheckedExpressionKind::Variable(variable) = receiver.kind() else {
+                panic!("Typechecker should have checked that `append` is called on a variable");
+            };
+            interpreter.set_variable(&variable.name, Value::String(receiver_value + &argument));
+            Ok(Value::Void)
+        }
+        "split" => Ok(Value::Array(
+            receiver_value
+                .split(argument.as_str())
+                .map(|part| Value::String(part.to_string()))
+                .collect(),
+        )),
+        "contains" => Ok(Value::Boolean(receiver_value.contains(argument.as_str()))),
+        "index_of" => {
+            // Counted in chars rather than bytes, and `-1` if there is no match.
+            let index = receiver_value
+                .find(argument.as_str())
+                .map_or(-1, |index| receiver_value[..index].chars().count() as i64);
+            Ok(Value::Integer(index))
+        }
+        _ => panic!("Unknown builtin method `{}` on `string`", name),
+    }
+}
+
+fn evaluate_optional_function(
+    interpreter: &mut Interpreter,
+    name: &str,
+    arguments: &[CheckedExpression],
+    range: CodeRange,
+) -> ExecutionResult<Value> {
+    let value = interpreter.evaluate_expression(&arguments[0])?;
+    match name {
+        "is_some" => Ok(Value::Boolean(!value.is_none())),
+        "is_none" => Ok(Value::Boolean(value.is_none())),
+        "unwrap" => match value {
+            Value::None => {
+                Err(ExecutionError::new(ExecutionErrorKind::UnwrapNone).with_range(range))
+            }
+            value => Ok(value),
+        },
+        _ => panic!("Unknown optional function `{}`", name),
+    }
+}
+
+fn evaluate_float_arguments<const N: usize>(
+    interpreter: &mut Interpreter,
+    arguments: &[CheckedExpression],
+) -> ExecutionResult<[f64; N]> {
+    let mut values = [0.0; N];
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = match interpreter.evaluate_expression(argument)? {
+            Value::Float(float) => float,
+            _ => panic!("Typechecker should have checked argument types"),
+        };
+    }
+    Ok(values)
+}
+
+fn evaluate_pow_arguments(
+    interpreter: &mut Interpreter,
+    arguments: &[CheckedExpression],
+    range: CodeRange,
+) -> ExecutionResult<(Value, i32)> {
+    let base = interpreter.evaluate_expression(&arguments[0])?;
+    let exponent = match interpreter.evaluate_expression(&arguments[1])? {
+        Value::Integer(exponent) => exponent,
+        _ => panic!("Typechecker should have checked argument types"),
+    };
+    let exponent = i32::try_from(exponent).map_err(|_| {
+        ExecutionError::new(ExecutionErrorKind::ExponentOutOfRange { exponent }).with_range(range)
+    })?;
+    Ok((base, exponent))
+}
@@ -1,23 +1,181 @@
 use crate::error::print_error;
-use crate::source::Source;
+use crate::source::{CodeRange, Source};
+use crate::tokenizer::token::TokenKind;
+use crate::typechecker::Type;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExecutionErrorKind {
-    Panic { message: String },
+    Panic {
+        message: String,
+    },
+    IntegerOverflow {
+        left: i64,
+        operator: TokenKind,
+        right: i64,
+    },
+    /// Raised by `-` on `i64::MIN`, whose negation doesn't fit in an `int`.
+    NegationOverflow {
+        value: i64,
+    },
+    /// Raised by `ipow` when the power doesn't fit in an `int`.
+    PowerOverflow {
+        base: i64,
+        exponent: i64,
+    },
+    /// Raised by `ipow`, whose result can't be a fraction.
+    NegativeExponent {
+        exponent: i64,
+    },
+    /// An exponent of `pow` or `ipow` that doesn't fit in 32 bits.
+    ExponentOutOfRange {
+        exponent: i64,
+    },
+    /// Integer `/` or `%` with a right side of zero.
+    DivisionByZero {
+        left: i64,
+        operator: TokenKind,
+    },
+    UnwrapNone,
+    /// Raised by the `panic` builtin.
+    UserPanic {
+        message: String,
+    },
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    StackOverflow {
+        depth: usize,
+    },
+    /// The configured call depth limit needs more host stack than the
+    /// interpreter is willing to reserve.
+    CallDepthLimitTooLarge {
+        max_call_depth: usize,
+        limit: usize,
+    },
+    /// More statements and loop iterations ran than the step limit allows.
+    StepLimitExceeded {
+        max_steps: u64,
+    },
+    /// Raised by the `assert` and `assert_eq` builtins.
+    AssertionFailed {
+        message: Option<String>,
+    },
+    /// The operands of an infix operator have different types.
+    MismatchedOperandTypes {
+        left: &'static str,
+        right: &'static str,
+    },
+    /// An infix operator was used on a type it does not support.
+    UnsupportedOperator {
+        operator: TokenKind,
+        type_: &'static str,
+    },
+    /// A native function returned a value that doesn't match its signature.
+    /// The error's range is the call, which names the function.
+    InvalidNativeReturnValue {
+        /// The return type of the native function's signature, boxed to
+        /// keep errors small.
+        expected: Box<Type>,
+        found: &'static str,
+    },
+}
+
+/// Deep recursion would otherwise print one line per call.
+const MAX_PRINTED_FRAMES: usize = 20;
+
+/// A function that was being evaluated when an error occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    pub function: String,
+    /// Where the function was called from, or `None` for `main`.
+    pub call_site: Option<CodeRange>,
+}
+
+impl std::fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.call_site {
+            Some(range) => write!(
+                f,
+                "{} (called at {}:{})",
+                self.function,
+                range.coords.line + 1,
+                range.coords.column + 1
+            ),
+            None => write!(f, "{}", self.function),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExecutionError {
     kind: ExecutionErrorKind,
+    range: Option<CodeRange>,
+    scope_dump: Option<String>,
+    /// The call stack at the point of the error, innermost frame first. Boxed
+    /// as a slice to keep `ExecutionResult` small.
+    backtrace: Box<[StackFrame]>,
 }
 
 impl ExecutionError {
     pub fn new(kind: ExecutionErrorKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            range: None,
+            scope_dump: None,
+            backtrace: Box::new([]),
+        }
+    }
+
+    pub fn with_range(mut self, range: CodeRange) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn with_scope_dump(mut self, scope_dump: String) -> Self {
+        self.scope_dump = Some(scope_dump);
+        self
+    }
+
+    pub fn with_backtrace(mut self, backtrace: Vec<StackFrame>) -> Self {
+        self.backtrace = backtrace.into_boxed_slice();
+        self
+    }
+
+    pub fn kind(&self) -> &ExecutionErrorKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> Option<&CodeRange> {
+        self.range.as_ref()
+    }
+
+    pub fn scope_dump(&self) -> Option<&str> {
+        self.scope_dump.as_deref()
+    }
+
+    pub fn backtrace(&self) -> &[StackFrame] {
+        &self.backtrace
     }
 
     pub fn print(&self, source: &Source) {
-        print_error(source, None, &self.to_string());
+        print_error(source, self.range.as_ref(), &self.to_string());
+        if !self.backtrace.is_empty() {
+            eprintln!("backtrace:");
+            for (index, frame) in self.backtrace.iter().take(MAX_PRINTED_FRAMES).enumerate() {
+                eprintln!("  {}: {}", index, frame);
+            }
+            if self.backtrace.len() > MAX_PRINTED_FRAMES {
+                eprintln!(
+                    "  ... {} more frames",
+                    self.backtrace.len() - MAX_PRINTED_FRAMES
+                );
+            }
+        }
+        if let Some(scope_dump) = &self.scope_dump {
+            eprint!("{}", scope_dump);
+        }
     }
 }
 
@@ -27,6 +185,69 @@ impl std::fmt::Display for ExecutionError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let str = match &self.kind {
             ExecutionErrorKind::Panic { message } => format!("Panic: {}", message),
+            ExecutionErrorKind::IntegerOverflow {
+                left,
+                operator,
+                right,
+            } => format!("Integer overflow in `{} {} {}`", left, operator, right),
+            ExecutionErrorKind::NegationOverflow { value } => {
+                format!("Integer overflow in `-({})`", value)
+            }
+            ExecutionErrorKind::PowerOverflow { base, exponent } => {
+                format!("Integer overflow in `ipow({}, {})`", base, exponent)
+            }
+            ExecutionErrorKind::NegativeExponent { exponent } => format!(
+                "Cannot raise an int to the negative power `{}`, use `pow` with a float base instead",
+                exponent
+            ),
+            ExecutionErrorKind::ExponentOutOfRange { exponent } => {
+                format!("Exponent `{}` is out of range", exponent)
+            }
+            ExecutionErrorKind::DivisionByZero { left, operator } => {
+                format!("Division by zero in `{} {} 0`", left, operator)
+            }
+            ExecutionErrorKind::UnwrapNone => "Called `unwrap` on a `none` value".to_string(),
+            ExecutionErrorKind::UserPanic { message } => format!("Script panicked: {}", message),
+            ExecutionErrorKind::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => format!(
+                "Function `{}` expects {} arguments but got {}",
+                function, expected, found
+            ),
+            ExecutionErrorKind::StackOverflow { depth } => {
+                format!("Stack overflow: calls are nested more than {} deep", depth)
+            }
+            ExecutionErrorKind::CallDepthLimitTooLarge {
+                max_call_depth,
+                limit,
+            } => format!(
+                "The call depth limit {} is larger than the supported maximum of {}",
+                max_call_depth, limit
+            ),
+            ExecutionErrorKind::StepLimitExceeded { max_steps } => format!(
+                "Step limit exceeded: execution took more than {} steps",
+                max_steps
+            ),
+            ExecutionErrorKind::AssertionFailed { message } => match message {
+                Some(message) => format!("Assertion failed: {}", message),
+                None => "Assertion failed".to_string(),
+            },
+            ExecutionErrorKind::MismatchedOperandTypes { left, right } => format!(
+                "The operands have different types: `{}` and `{}`",
+                left, right
+            ),
+            ExecutionErrorKind::UnsupportedOperator { operator, type_ } => {
+                format!(
+                    "The `{}` operator is not supported for `{}`",
+                    operator, type_
+                )
+            }
+            ExecutionErrorKind::InvalidNativeReturnValue { expected, found } => format!(
+                "The native function should return `{}`, but returned `{}`",
+                expected, found
+            ),
         };
 
         write!(f, "{}", str)
@@ -1,20 +1,25 @@
 use std::collections::HashMap;
+use std::io::Write;
 
 use crate::parser::{AssignmentOperator, PrefixOperator};
+use crate::source::CodeRange;
 use crate::tokenizer::token::TokenKind;
 use crate::typechecker::{
-    CheckedExpression, CheckedExpressionKind, CheckedFunctionItem, CheckedItem, CheckedItemKind,
-    CheckedStatement, CheckedStatementKind, CheckedVariable, Type,
+    CheckedExpression, CheckedExpressionKind, CheckedFunctionDefinition, CheckedFunctionItem,
+    CheckedItem, CheckedItemKind, CheckedMatchArm, CheckedPattern, CheckedStatement,
+    CheckedStatementKind, CheckedVariable, Type,
 };
 
 pub mod builtin;
 pub mod error;
+pub mod random;
 pub mod value;
 
+use random::Rng;
 use value::Value;
 
 pub use error::ExecutionError;
-use error::ExecutionResult;
+use error::{ExecutionErrorKind, ExecutionResult, StackFrame};
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Scope {
@@ -46,26 +51,161 @@ impl Scope {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ControlFlowMode {
-    Return(Option<Value>),
+    Return(Value),
+    Break,
+    Continue,
 }
 
+/// A function implemented by the host that Bau programs can call. It returns
+/// `None` for `void`.
+pub type NativeFunction = dyn Fn(&[Value]) -> ExecutionResult<Option<Value>> + Send + Sync;
+
+/// The statements deferred by a call, which run when it returns.
 #[derive(Debug, Clone, PartialEq, Default)]
+struct DeferredStatements {
+    /// Where the scopes of the call start on the scope stack.
+    scope_base: usize,
+    /// Each statement with the call's scopes as they were when it was deferred.
+    statements: Vec<(CheckedStatement, Vec<Scope>)>,
+}
+
 pub struct Interpreter {
     functions: HashMap<String, CheckedFunctionItem>,
+    /// The host implementations of the native functions in `functions`.
+    natives: HashMap<String, Box<NativeFunction>>,
     methods: HashMap<Type, HashMap<String, CheckedFunctionItem>>,
     scope_stack: Vec<Scope>,
+    /// Holds the values of constants, consulted when no local scope has a variable.
+    global_scope: Scope,
+    wrapping: bool,
+    arguments: Vec<String>,
+    /// The functions currently being evaluated, outermost first.
+    call_stack: Vec<StackFrame>,
+    /// The deferred statements of each call on the call stack.
+    deferred: Vec<DeferredStatements>,
+    max_call_depth: usize,
+    /// The number of statements and loop iterations after which execution
+    /// fails, if any.
+    max_steps: Option<u64>,
+    /// The number of statements and loop iterations executed so far.
+    steps: u64,
+    rng: Rng,
+    /// Where `print` writes to.
+    output: Box<dyn Write + Send>,
 }
 
 impl Interpreter {
+    /// The default limit on how deeply calls may nest.
+    pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+    /// A generous estimate of the host stack used by a single call.
+    const STACK_BYTES_PER_CALL: usize = 32 * 1024;
+    /// Host stack for everything outside of the calls themselves.
+    const BASE_STACK_BYTES: usize = 1024 * 1024;
+    /// The largest call depth limit, which keeps the interpreter thread's
+    /// stack within 1 GiB.
+    pub const MAX_CALL_DEPTH: usize =
+        (1024 * 1024 * 1024 - Self::BASE_STACK_BYTES) / Self::STACK_BYTES_PER_CALL;
+
     pub fn new() -> Self {
         Self {
             functions: HashMap::new(),
+            natives: HashMap::new(),
             methods: HashMap::new(),
             scope_stack: vec![],
+            global_scope: Scope::new(),
+            wrapping: false,
+            arguments: vec![],
+            call_stack: vec![],
+            deferred: vec![],
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
+            max_steps: None,
+            steps: 0,
+            rng: Rng::from_time(),
+            output: Box::new(std::io::stdout()),
         }
     }
 
-    pub fn run(&mut self, checked_items: &[CheckedItem]) -> ExecutionResult<Option<Value>> {
+    /// Send the output of `print` to `output` instead of stdout.
+    pub fn with_output(mut self, output: Box<dyn Write + Send>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Limit how deeply calls may nest before failing with
+    /// [`ExecutionErrorKind::StackOverflow`] instead of overflowing the host stack.
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Fail with [`ExecutionErrorKind::StepLimitExceeded`] after executing
+    /// `max_steps` statements and loop iterations, so that every run ends.
+    pub fn with_max_steps(mut self, max_steps: Option<u64>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Seed the generator behind the `random_*` builtins, so runs are
+    /// reproducible. Without a seed, the current time is used.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// The command-line arguments passed to `main` if it takes a `[string]`.
+    pub fn with_arguments(mut self, arguments: Vec<String>) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// Wrap integer arithmetic on overflow instead of failing with
+    /// [`ExecutionErrorKind::IntegerOverflow`].
+    pub fn with_wrapping(mut self, wrapping: bool) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+
+    /// Makes `function` callable as `name`, with the function type `signature`.
+    /// The typechecker has to know about it too, through
+    /// [`Typechecker::register_native`](crate::typechecker::Typechecker::register_native).
+    pub fn register_native(&mut self, name: &str, signature: Type, function: Box<NativeFunction>) {
+        let function_item = CheckedFunctionItem {
+            definition: CheckedFunctionDefinition::from_signature(name, signature),
+            body: vec![],
+        };
+        self.register_function(&function_item);
+        self.natives.insert(name.to_string(), function);
+    }
+
+    pub fn run(&mut self, checked_items: &[CheckedItem]) -> ExecutionResult<Value> {
+        // Every call takes several kilobytes of host stack, especially in debug
+        // builds, so the program runs on a thread with room for the deepest
+        // nesting of calls that is allowed.
+        if self.max_call_depth > Self::MAX_CALL_DEPTH {
+            return Err(ExecutionError::new(
+                ExecutionErrorKind::CallDepthLimitTooLarge {
+                    max_call_depth: self.max_call_depth,
+                    limit: Self::MAX_CALL_DEPTH,
+                },
+            ));
+        }
+        let stack_size = Self::STACK_BYTES_PER_CALL * self.max_call_depth + Self::BASE_STACK_BYTES;
+        std::thread::scope(|scope| {
+            let thread = std::thread::Builder::new()
+                .stack_size(stack_size)
+                .spawn_scoped(scope, || self.run_on_current_thread(checked_items))
+                .map_err(|error| {
+                    ExecutionError::new(ExecutionErrorKind::Panic {
+                        message: format!("Failed to spawn the interpreter thread: {}", error),
+                    })
+                })?;
+            thread
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    fn run_on_current_thread(&mut self, checked_items: &[CheckedItem]) -> ExecutionResult<Value> {
         for builtin_function_definition in builtin::BUILTIN_FUNCTIONS.values() {
             let function = CheckedFunctionItem {
                 definition: builtin_function_definition.clone(),
@@ -74,50 +214,137 @@ impl Interpreter {
             self.register_function(&function);
         }
         self.register_items(checked_items);
+        self.evaluate_constants(checked_items)?;
 
         let main_function = self.main_function().clone();
-        self.evaluate_function(&main_function, &[])
+        let arguments = match main_function.definition.parameters.is_empty() {
+            true => vec![],
+            false => {
+                let arguments = self.arguments.iter().cloned().map(Value::String).collect();
+                vec![Value::Array(arguments)]
+            }
+        };
+        self.call_function(&main_function, None, arguments, None)
     }
 
     pub fn evaluate_function(
         &mut self,
         function: &CheckedFunctionItem,
         arguments: &[CheckedExpression],
-    ) -> ExecutionResult<Option<Value>> {
+        range: CodeRange,
+    ) -> ExecutionResult<Value> {
+        let values = self.evaluate_arguments(function, arguments, range)?;
+        self.call_function(function, None, values, Some(range))
+    }
+
+    fn evaluate_arguments(
+        &mut self,
+        function: &CheckedFunctionItem,
+        arguments: &[CheckedExpression],
+        range: CodeRange,
+    ) -> ExecutionResult<Vec<Value>> {
+        if function.definition.parameters.len() != arguments.len() {
+            return Err(ExecutionError::new(ExecutionErrorKind::WrongArgumentCount {
+                function: function.definition.name.clone(),
+                expected: function.definition.parameters.len(),
+                found: arguments.len(),
+            })
+            .with_range(range));
+        }
+
+        let mut values = vec![];
+        for argument in arguments.iter() {
+            values.push(self.evaluate_expression(argument)?);
+        }
+        Ok(values)
+    }
+
+    /// Calls `function`, binding `receiver` to `self` if it is a method.
+    fn call_function(
+        &mut self,
+        function: &CheckedFunctionItem,
+        receiver: Option<Value>,
+        arguments: Vec<Value>,
+        call_site: Option<CodeRange>,
+    ) -> ExecutionResult<Value> {
+        if self.call_stack.len() >= self.max_call_depth {
+            let error = ExecutionError::new(ExecutionErrorKind::StackOverflow {
+                depth: self.max_call_depth,
+            });
+            return Err(match call_site {
+                Some(range) => error.with_range(range),
+                None => error,
+            });
+        }
+
+        self.call_stack.push(StackFrame {
+            function: function.definition.name.clone(),
+            call_site,
+        });
+        let result = self.evaluate_function_body(function, receiver, arguments);
+        // The innermost call that sees the error records the whole stack.
+        let result = result.map_err(|error| match error.backtrace().is_empty() {
+            true => error.with_backtrace(self.call_stack.iter().rev().cloned().collect()),
+            false => error,
+        });
+        self.call_stack.pop();
+        result
+    }
+
+    fn evaluate_function_body(
+        &mut self,
+        function: &CheckedFunctionItem,
+        receiver: Option<Value>,
+        arguments: Vec<Value>,
+    ) -> ExecutionResult<Value> {
+        self.deferred.push(DeferredStatements {
+            scope_base: self.scope_stack.len(),
+            statements: vec![],
+        });
         self.push_scope();
 
-        assert_eq!(
-            function.definition.parameters.len(),
-            arguments.len(),
-            "Typechecker should have checked argument counts. Expected {} arguments, but found {}",
-            function.definition.parameters.len(),
-            arguments.len(),
-        );
-
-        for (i, argument) in arguments.iter().enumerate() {
-            let value = self
-                .evaluate_expression(argument)?
-                .expect("Typechecker should have checked for void expressions in function call");
+        if let Some(receiver) = receiver {
+            self.current_scope_mut().set_variable("self", receiver);
+        }
+
+        for (parameter, value) in function.definition.parameters.iter().zip(arguments) {
             self.current_scope_mut()
-                .set_variable(&function.definition.parameters[i].name, value);
+                .set_variable(&parameter.name, value);
         }
 
-        match self.evaluate_block(&function.body)? {
-            Some(ControlFlowMode::Return(return_value)) => {
-                self.pop_scope();
-                Ok(return_value)
-            }
-            None => {
-                self.pop_scope();
-                Ok(None)
+        let return_value = match self.evaluate_block(&function.body)? {
+            Some(ControlFlowMode::Return(return_value)) => return_value,
+            None => Value::Void,
+            Some(ControlFlowMode::Break | ControlFlowMode::Continue) => {
+                panic!("Typechecker should have checked that loop control is inside a loop")
             }
+        };
+        self.pop_scope();
+        self.evaluate_deferred_statements()?;
+        Ok(return_value)
+    }
+
+    /// Runs the statements deferred by the current call, the last one first.
+    fn evaluate_deferred_statements(&mut self) -> ExecutionResult<()> {
+        let deferred = self
+            .deferred
+            .pop()
+            .expect("every call should have deferred statements");
+        for (statement, scopes) in deferred.statements.into_iter().rev() {
+            self.scope_stack.extend(scopes);
+            let result = self.evaluate_statement(&statement);
+            self.scope_stack.truncate(deferred.scope_base);
+            result?;
         }
+        Ok(())
     }
 
     pub fn evaluate_statement(
         &mut self,
         statement: &CheckedStatement,
     ) -> ExecutionResult<Option<ControlFlowMode>> {
+        self.count_step(*statement.range())?;
+
         match statement.kind() {
             CheckedStatementKind::Return { value } => return self.evaluate_return_statement(value),
             CheckedStatementKind::Let {
@@ -125,6 +352,10 @@ impl Interpreter {
                 initial_value,
                 ..
             } => self.evaluate_let_statement(name, initial_value)?,
+            CheckedStatementKind::DestructuringLet {
+                variables,
+                initial_value,
+            } => self.evaluate_destructuring_let_statement(variables, initial_value)?,
             CheckedStatementKind::VariableAssignment {
                 name,
                 value,
@@ -138,9 +369,32 @@ impl Interpreter {
                 then_body,
                 else_body,
             } => return self.evaluate_if_statement(condition, then_body, else_body.as_deref()),
-            CheckedStatementKind::Loop { block } => return self.evaluate_loop_statement(block),
+            CheckedStatementKind::IfLet {
+                name,
+                value,
+                then_body,
+                else_body,
+                ..
+            } => {
+                return self.evaluate_if_let_statement(name, value, then_body, else_body.as_deref())
+            }
+            CheckedStatementKind::Loop { block } => {
+                return self.evaluate_loop_statement(block, *statement.range())
+            }
             CheckedStatementKind::While { condition, block } => {
-                return self.evaluate_while_statement(condition, block)
+                return self.evaluate_while_statement(condition, block, *statement.range())
+            }
+            CheckedStatementKind::Break => return Ok(Some(ControlFlowMode::Break)),
+            CheckedStatementKind::Continue => return Ok(Some(ControlFlowMode::Continue)),
+            // Nested functions are registered up front by `register_items`.
+            CheckedStatementKind::FunctionDef(_) => {}
+            CheckedStatementKind::Defer { statement } => {
+                let deferred = self
+                    .deferred
+                    .last_mut()
+                    .expect("statements should only run inside a call");
+                let scopes = self.scope_stack[deferred.scope_base..].to_vec();
+                deferred.statements.push((*statement.clone(), scopes));
             }
         };
         Ok(None)
@@ -167,7 +421,7 @@ impl Interpreter {
                 let return_value = self.evaluate_expression(value_expression)?;
                 Ok(Some(ControlFlowMode::Return(return_value)))
             }
-            None => Ok(Some(ControlFlowMode::Return(None))),
+            None => Ok(Some(ControlFlowMode::Return(Value::Void))),
         }
     }
 
@@ -176,31 +430,67 @@ impl Interpreter {
         name: &str,
         initial_value: &CheckedExpression,
     ) -> ExecutionResult<()> {
-        let value = self
-            .evaluate_expression(initial_value)?
-            .expect("Typechecker should have checked for void expressions in variable assignment");
+        let value = self.evaluate_expression(initial_value)?;
         self.current_scope_mut().set_variable(name, value);
         Ok(())
     }
 
+    pub fn evaluate_destructuring_let_statement(
+        &mut self,
+        variables: &[Option<CheckedVariable>],
+        initial_value: &CheckedExpression,
+    ) -> ExecutionResult<()> {
+        let Value::Tuple(values) = self.evaluate_expression(initial_value)? else {
+            panic!("Typechecker should have checked that only tuples are destructured");
+        };
+        for (variable, value) in variables.iter().zip(values) {
+            if let Some(variable) = variable {
+                self.current_scope_mut().set_variable(&variable.name, value);
+            }
+        }
+        Ok(())
+    }
+
     pub fn evaluate_variable_assignment(
         &mut self,
         name: &str,
         value: &CheckedExpression,
         operator: &AssignmentOperator,
     ) -> ExecutionResult<()> {
-        let value = self
-            .evaluate_expression(value)?
-            .expect("Typechecker should have checked for void expressions in variable assignment");
+        let current_value = self.get_variable(name).clone();
 
-        let mut new_value = self.get_variable(name).clone();
-        match operator {
-            AssignmentOperator::Equals => new_value = value,
-            AssignmentOperator::PlusEquals => new_value.add(value),
-            AssignmentOperator::MinusEquals => new_value.subtract(value),
-            AssignmentOperator::AsteriskEquals => new_value.multiply(value),
-            AssignmentOperator::SlashEquals => new_value.divide(value),
-            AssignmentOperator::PercentEquals => new_value.modulo(value),
+        // `&&=` and `||=` short-circuit, so the value is only evaluated when
+        // it can still change the variable.
+        let short_circuits = match operator {
+            AssignmentOperator::AmpersandAmpersandEquals => current_value.is_false(),
+            AssignmentOperator::PipePipeEquals => current_value.is_true(),
+            _ => false,
+        };
+        if short_circuits {
+            return Ok(());
+        }
+
+        let value = self.evaluate_expression(value)?;
+        let new_value = match operator {
+            AssignmentOperator::Equals => value,
+            AssignmentOperator::PlusEquals => {
+                self.evaluate_arithmetic(current_value, TokenKind::Plus, value)?
+            }
+            AssignmentOperator::MinusEquals => {
+                self.evaluate_arithmetic(current_value, TokenKind::Minus, value)?
+            }
+            AssignmentOperator::AsteriskEquals => {
+                self.evaluate_arithmetic(current_value, TokenKind::Asterisk, value)?
+            }
+            AssignmentOperator::SlashEquals => {
+                self.evaluate_arithmetic(current_value, TokenKind::Slash, value)?
+            }
+            AssignmentOperator::PercentEquals => {
+                self.evaluate_arithmetic(current_value, TokenKind::Percent, value)?
+            }
+            AssignmentOperator::AmpersandAmpersandEquals | AssignmentOperator::PipePipeEquals => {
+                value
+            }
         };
 
         self.set_variable(name, new_value);
@@ -210,50 +500,164 @@ impl Interpreter {
     pub fn evaluate_expression(
         &mut self,
         expression: &CheckedExpression,
-    ) -> ExecutionResult<Option<Value>> {
+    ) -> ExecutionResult<Value> {
         match expression.kind() {
-            CheckedExpressionKind::Literal(literal) => Ok(Some(literal.clone())),
+            CheckedExpressionKind::Literal(literal) => Ok(literal.clone()),
             CheckedExpressionKind::Variable(variable) => self.evaluate_variable(variable),
             CheckedExpressionKind::FunctionCall { name, arguments } => {
-                self.evaluate_function_call(name, arguments)
+                self.evaluate_function_call(name, arguments, *expression.range())
             }
             CheckedExpressionKind::PrefixOperator {
                 operator,
                 expression,
-            } => self
-                .evaluate_prefix_operator(operator, expression)
-                .map(Some),
+            } => self.evaluate_prefix_operator(operator, expression),
             CheckedExpressionKind::InfixOperator {
                 operator,
                 left,
                 right,
-            } => self
-                .evaluate_infix_operator(*operator, left, right)
-                .map(Some),
+            } => self.evaluate_infix_operator(*operator, left, right),
             CheckedExpressionKind::MethodCall {
+                receiver,
                 type_,
                 method_name,
                 arguments,
-            } => self.evaluate_method_call(type_, method_name, arguments),
+            } => self.evaluate_method_call(
+                receiver,
+                type_,
+                method_name,
+                arguments,
+                *expression.range(),
+            ),
+            CheckedExpressionKind::Ternary {
+                condition,
+                then_branch,
+                else_branch,
+            } => match self.evaluate_expression(condition)?.is_true() {
+                true => self.evaluate_expression(then_branch),
+                false => self.evaluate_expression(else_branch),
+            },
+            CheckedExpressionKind::EnumVariant { value, .. } => Ok(Value::Integer(*value)),
+            CheckedExpressionKind::EnumFromInt {
+                discriminants,
+                value,
+                ..
+            } => match self.evaluate_expression(value)? {
+                Value::Integer(value) if discriminants.contains(&value) => {
+                    Ok(Value::Integer(value))
+                }
+                _ => Ok(Value::None),
+            },
+            CheckedExpressionKind::Match { value, arms } => self.evaluate_match(value, arms),
+            CheckedExpressionKind::Struct { fields, .. } => Ok(Value::Struct(
+                fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), self.evaluate_expression(value)?)))
+                    .collect::<ExecutionResult<_>>()?,
+            )),
+            CheckedExpressionKind::FieldAccess { expression, field } => {
+                let Value::Struct(mut fields) = self.evaluate_expression(expression)? else {
+                    panic!("Typechecker should have checked that only structs have fields");
+                };
+                Ok(fields
+                    .remove(field)
+                    .expect("Typechecker should have checked that the field exists"))
+            }
+            CheckedExpressionKind::Tuple(elements) => Ok(Value::Tuple(
+                elements
+                    .iter()
+                    .map(|element| self.evaluate_expression(element))
+                    .collect::<ExecutionResult<_>>()?,
+            )),
+            CheckedExpressionKind::VariableCall {
+                variable,
+                arguments,
+            } => {
+                let Value::Function(name) = self.evaluate_variable(variable)? else {
+                    panic!("Typechecker should have checked that only functions are called");
+                };
+                self.evaluate_function_call(&name, arguments, *expression.range())
+            }
+        }
+    }
+
+    fn evaluate_match(
+        &mut self,
+        value: &CheckedExpression,
+        arms: &[CheckedMatchArm],
+    ) -> ExecutionResult<Value> {
+        let value = self.evaluate_expression(value)?;
+        for arm in arms {
+            self.push_scope();
+            let result = self.evaluate_match_arm(&value, arm);
+            self.pop_scope();
+            if let Some(result) = result? {
+                return Ok(result);
+            }
+        }
+        panic!("Typechecker should have checked that every value matches an arm")
+    }
+
+    /// Evaluates `arm` if `value` matches its pattern and guard.
+    fn evaluate_match_arm(
+        &mut self,
+        value: &Value,
+        arm: &CheckedMatchArm,
+    ) -> ExecutionResult<Option<Value>> {
+        let matches = match &arm.pattern {
+            CheckedPattern::Wildcard => true,
+            CheckedPattern::Binding(variable) => {
+                self.current_scope_mut()
+                    .set_variable(&variable.name, value.clone());
+                true
+            }
+            CheckedPattern::Value(pattern) => self.evaluate_expression(pattern)? == *value,
+        };
+        if !matches {
+            return Ok(None);
         }
+
+        if let Some(guard) = &arm.guard {
+            if !self.evaluate_expression(guard)?.is_true() {
+                return Ok(None);
+            }
+        }
+        self.evaluate_expression(&arm.value).map(Some)
     }
 
-    pub fn evaluate_variable(&self, variable: &CheckedVariable) -> ExecutionResult<Option<Value>> {
+    pub fn evaluate_variable(&self, variable: &CheckedVariable) -> ExecutionResult<Value> {
         let value = self.get_variable(&variable.name);
-        Ok(Some(value.clone()))
+        Ok(value.clone())
     }
 
     pub fn evaluate_function_call(
         &mut self,
         name: &str,
         arguments: &[CheckedExpression],
-    ) -> ExecutionResult<Option<Value>> {
+        range: CodeRange,
+    ) -> ExecutionResult<Value> {
         if self.function_is_builtin(name) {
-            return builtin::evaluate_builtin_function(self, name, arguments);
+            return builtin::evaluate_builtin_function(self, name, arguments, range);
         }
 
         let function = self.get_function(name).clone();
-        self.evaluate_function(&function, arguments.clone())
+        if self.natives.contains_key(name) {
+            let values = self.evaluate_arguments(&function, arguments, range)?;
+            let value = (self.natives[name])(&values)
+                .map_err(|error| error.with_range(range))?
+                .unwrap_or(Value::Void);
+            // The host isn't typechecked, so its result is checked here.
+            if !value_has_type(&value, &function.definition.return_type) {
+                return Err(
+                    ExecutionError::new(ExecutionErrorKind::InvalidNativeReturnValue {
+                        expected: Box::new(function.definition.return_type.clone()),
+                        found: value.type_name(),
+                    })
+                    .with_range(range),
+                );
+            }
+            return Ok(value);
+        }
+        self.evaluate_function(&function, arguments, range)
     }
 
     pub fn evaluate_prefix_operator(
@@ -261,13 +665,11 @@ impl Interpreter {
         operator: &PrefixOperator,
         expression: &CheckedExpression,
     ) -> ExecutionResult<Value> {
-        let value = self
-            .evaluate_expression(expression)?
-            .expect("Typechecker should have checked for void expressions");
+        let value = self.evaluate_expression(expression)?;
 
         match operator {
             PrefixOperator::Minus => match value {
-                Value::Integer(value) => Ok(Value::Integer(-value)),
+                Value::Integer(value) => self.evaluate_integer_negation(value),
                 Value::Float(value) => Ok(Value::Float(-value)),
                 _ => panic!("Typechecker should have checked for invalid prefix operands"),
             },
@@ -289,20 +691,29 @@ impl Interpreter {
         left: &CheckedExpression,
         right: &CheckedExpression,
     ) -> ExecutionResult<Value> {
-        let lhs = self
-            .evaluate_expression(left)?
-            .expect("Typechecker should have checked for void expressions");
-        let rhs = self
-            .evaluate_expression(right)?
-            .expect("Typechecker should have checked for void expressions");
+        let lhs = self.evaluate_expression(left)?;
+
+        // `&&` and `||` only evaluate their right side when it can still
+        // change the result.
+        match operator {
+            TokenKind::AmpersandAmpersand if lhs.is_false() => return Ok(lhs),
+            TokenKind::PipePipe if lhs.is_true() => return Ok(lhs),
+            TokenKind::AmpersandAmpersand | TokenKind::PipePipe => {
+                return self.evaluate_expression(right)
+            }
+            _ => {}
+        }
+
+        let rhs = self.evaluate_expression(right)?;
+        check_operand_types(&lhs, operator, &rhs)?;
         let mut value = lhs;
 
         match operator {
-            TokenKind::Plus => value.add(rhs),
-            TokenKind::Minus => value.subtract(rhs),
-            TokenKind::Asterisk => value.multiply(rhs),
-            TokenKind::Slash => value.divide(rhs),
-            TokenKind::Percent => value.modulo(rhs),
+            TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Asterisk
+            | TokenKind::Slash
+            | TokenKind::Percent => return self.evaluate_arithmetic(value, operator, rhs),
 
             TokenKind::EqualsEquals => value.equals(rhs),
             TokenKind::ExclamationMarkEquals => value.not_equals(rhs),
@@ -316,23 +727,101 @@ impl Interpreter {
         Ok(value)
     }
 
+    fn evaluate_arithmetic(
+        &self,
+        lhs: Value,
+        operator: TokenKind,
+        rhs: Value,
+    ) -> ExecutionResult<Value> {
+        if let (Value::Integer(left), Value::Integer(right)) = (&lhs, &rhs) {
+            if let Some(result) = self.evaluate_integer_arithmetic(*left, operator, *right) {
+                return result;
+            }
+        }
+
+        let mut value = lhs;
+        match operator {
+            TokenKind::Plus => value.add(rhs),
+            TokenKind::Minus => value.subtract(rhs),
+            TokenKind::Asterisk => value.multiply(rhs),
+            TokenKind::Slash => value.divide(rhs),
+            TokenKind::Percent => value.modulo(rhs),
+            _ => panic!("Invalid arithmetic operator: {:?}", operator),
+        }
+        Ok(value)
+    }
+
+    /// Evaluates the integer operators that can overflow or divide by zero,
+    /// returning `None` for any other operator.
+    fn evaluate_integer_arithmetic(
+        &self,
+        left: i64,
+        operator: TokenKind,
+        right: i64,
+    ) -> Option<ExecutionResult<Value>> {
+        if matches!(operator, TokenKind::Slash | TokenKind::Percent) && right == 0 {
+            return Some(Err(ExecutionError::new(
+                ExecutionErrorKind::DivisionByZero { left, operator },
+            )));
+        }
+
+        let result = match operator {
+            TokenKind::Plus if self.wrapping => Some(left.wrapping_add(right)),
+            TokenKind::Plus => left.checked_add(right),
+            TokenKind::Minus if self.wrapping => Some(left.wrapping_sub(right)),
+            TokenKind::Minus => left.checked_sub(right),
+            TokenKind::Asterisk if self.wrapping => Some(left.wrapping_mul(right)),
+            TokenKind::Asterisk => left.checked_mul(right),
+            TokenKind::Slash if self.wrapping => Some(left.wrapping_div(right)),
+            TokenKind::Slash => left.checked_div(right),
+            TokenKind::Percent if self.wrapping => Some(left.wrapping_rem(right)),
+            TokenKind::Percent => left.checked_rem(right),
+            _ => return None,
+        };
+        Some(result.map(Value::Integer).ok_or_else(|| {
+            ExecutionError::new(ExecutionErrorKind::IntegerOverflow {
+                left,
+                operator,
+                right,
+            })
+        }))
+    }
+
+    /// Negates an integer, which overflows for `i64::MIN`.
+    fn evaluate_integer_negation(&self, value: i64) -> ExecutionResult<Value> {
+        let result = match self.wrapping {
+            true => Some(value.wrapping_neg()),
+            false => value.checked_neg(),
+        };
+        result
+            .map(Value::Integer)
+            .ok_or_else(|| ExecutionError::new(ExecutionErrorKind::NegationOverflow { value }))
+    }
+
     fn evaluate_method_call(
         &mut self,
+        receiver: &CheckedExpression,
         type_: &Type,
         method_name: &str,
         arguments: &[CheckedExpression],
-    ) -> ExecutionResult<Option<Value>> {
+        range: CodeRange,
+    ) -> ExecutionResult<Value> {
         let method = self
             .methods
             .get(type_)
-            .expect("Typechecker should have checked if type has methods")
-            .get(method_name)
-            .expect("Typechecker should have checked if method exists")
-            .clone();
-
-        // FIXME: Add `self` as first argument
+            .and_then(|methods| methods.get(method_name))
+            .cloned();
+        let Some(method) = method else {
+            assert!(
+                builtin::method_is_builtin(type_, method_name),
+                "Typechecker should have checked if method exists"
+            );
+            return builtin::evaluate_builtin_method(self, receiver, method_name, arguments);
+        };
 
-        self.evaluate_function(&method, arguments)
+        let receiver = self.evaluate_expression(receiver)?;
+        let values = self.evaluate_arguments(&method, arguments, range)?;
+        self.call_function(&method, Some(receiver), values, Some(range))
     }
 
     fn evaluate_if_statement(
@@ -341,7 +830,7 @@ impl Interpreter {
         then_body: &[CheckedStatement],
         else_body: Option<&[CheckedStatement]>,
     ) -> ExecutionResult<Option<ControlFlowMode>> {
-        let condition = self.evaluate_expression(condition)?.unwrap();
+        let condition = self.evaluate_expression(condition)?;
         if condition.is_true() {
             self.push_scope();
             if let Some(mode) = self.evaluate_block(then_body)? {
@@ -361,17 +850,46 @@ impl Interpreter {
         Ok(None)
     }
 
+    fn evaluate_if_let_statement(
+        &mut self,
+        name: &str,
+        value: &CheckedExpression,
+        then_body: &[CheckedStatement],
+        else_body: Option<&[CheckedStatement]>,
+    ) -> ExecutionResult<Option<ControlFlowMode>> {
+        let value = self.evaluate_expression(value)?;
+        self.push_scope();
+        let mode = match value {
+            Value::None => match else_body {
+                Some(else_body) => self.evaluate_block(else_body),
+                None => Ok(None),
+            },
+            value => {
+                self.current_scope_mut().set_variable(name, value);
+                self.evaluate_block(then_body)
+            }
+        };
+        self.pop_scope();
+        mode
+    }
+
     fn evaluate_loop_statement(
         &mut self,
         block: &[CheckedStatement],
+        range: CodeRange,
     ) -> ExecutionResult<Option<ControlFlowMode>> {
         loop {
+            self.count_step(range)?;
             self.push_scope();
-            if let Some(mode) = self.evaluate_block(block)? {
-                self.pop_scope();
-                return Ok(Some(mode));
-            }
+            let mode = self.evaluate_block(block)?;
             self.pop_scope();
+            match mode {
+                Some(ControlFlowMode::Return(value)) => {
+                    return Ok(Some(ControlFlowMode::Return(value)))
+                }
+                Some(ControlFlowMode::Break) => return Ok(None),
+                Some(ControlFlowMode::Continue) | None => {}
+            }
         }
     }
 
@@ -379,39 +897,109 @@ impl Interpreter {
         &mut self,
         condition: &CheckedExpression,
         block: &[CheckedStatement],
+        range: CodeRange,
     ) -> ExecutionResult<Option<ControlFlowMode>> {
         loop {
-            let condition = self.evaluate_expression(condition)?.unwrap();
+            self.count_step(range)?;
+            let condition = self.evaluate_expression(condition)?;
             if condition.is_false() {
                 break;
             }
 
             self.push_scope();
-            if let Some(mode) = self.evaluate_block(block)? {
-                self.pop_scope();
-                return Ok(Some(mode));
-            }
+            let mode = self.evaluate_block(block)?;
             self.pop_scope();
+            match mode {
+                Some(ControlFlowMode::Return(value)) => {
+                    return Ok(Some(ControlFlowMode::Return(value)))
+                }
+                Some(ControlFlowMode::Break) => break,
+                // The condition is checked again before the next iteration.
+                Some(ControlFlowMode::Continue) | None => {}
+            }
         }
 
         Ok(None)
     }
 
+    /// Counts a statement or loop iteration at `range` towards `max_steps`.
+    fn count_step(&mut self, range: CodeRange) -> ExecutionResult<()> {
+        self.steps += 1;
+        match self.max_steps {
+            Some(max_steps) if self.steps > max_steps => Err(ExecutionError::new(
+                ExecutionErrorKind::StepLimitExceeded { max_steps },
+            )
+            .with_range(range)),
+            _ => Ok(()),
+        }
+    }
+
     fn register_items(&mut self, checked_items: &[CheckedItem]) {
         for item in checked_items {
             match item.kind() {
                 CheckedItemKind::Function(function) => {
                     self.register_function(function);
+                    self.register_nested_functions(&function.body);
                 }
                 CheckedItemKind::Extend(extend) => {
                     for function in &extend.methods {
-                        self.register_method(extend.type_, function);
+                        self.register_method(extend.type_.clone(), function);
+                        self.register_nested_functions(&function.body);
+                    }
+                }
+                CheckedItemKind::Const(_) => {}
+            }
+        }
+    }
+
+    /// Registers the functions nested in `block`, which the typechecker has
+    /// given unique names.
+    fn register_nested_functions(&mut self, block: &[CheckedStatement]) {
+        for statement in block {
+            match statement.kind() {
+                CheckedStatementKind::FunctionDef(function) => {
+                    self.register_function(function);
+                    self.register_nested_functions(&function.body);
+                }
+                CheckedStatementKind::If {
+                    then_body,
+                    else_body,
+                    ..
+                }
+                | CheckedStatementKind::IfLet {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    self.register_nested_functions(then_body);
+                    if let Some(else_body) = else_body {
+                        self.register_nested_functions(else_body);
                     }
                 }
+                CheckedStatementKind::Loop { block }
+                | CheckedStatementKind::While { block, .. } => {
+                    self.register_nested_functions(block)
+                }
+                CheckedStatementKind::Defer { statement } => {
+                    self.register_nested_functions(std::slice::from_ref(statement))
+                }
+                _ => {}
             }
         }
     }
 
+    /// Evaluates every constant once, before `main` runs. The typechecker
+    /// orders constants after the constants they refer to.
+    fn evaluate_constants(&mut self, checked_items: &[CheckedItem]) -> ExecutionResult<()> {
+        for item in checked_items {
+            if let CheckedItemKind::Const(constant) = item.kind() {
+                let value = self.evaluate_expression(&constant.value)?;
+                self.global_scope.set_variable(&constant.name, value);
+            }
+        }
+        Ok(())
+    }
+
     fn register_function(&mut self, function: &CheckedFunctionItem) {
         self.functions
             .insert(function.definition.name.to_string(), function.clone());
@@ -420,7 +1008,7 @@ impl Interpreter {
     fn register_method(&mut self, type_: Type, function: &CheckedFunctionItem) {
         self.methods
             .entry(type_)
-            .or_insert_with(HashMap::new)
+            .or_default()
             .insert(function.definition.name.to_string(), function.clone());
     }
 
@@ -430,6 +1018,21 @@ impl Interpreter {
             .expect("Typechecker should have checked for main function")
     }
 
+    /// Returns a human-readable listing of the variables in every scope on the
+    /// scope stack, from the outermost to the innermost scope.
+    pub fn dump_scopes(&self) -> String {
+        let mut dump = String::new();
+        for (depth, scope) in self.scope_stack.iter().enumerate() {
+            dump.push_str(&format!("scope {}:\n", depth));
+            let mut names = scope.variables.keys().collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                dump.push_str(&format!("    {} = {}\n", name, scope.variables[name]));
+            }
+        }
+        dump
+    }
+
     fn push_scope(&mut self) {
         self.scope_stack.push(Scope::new());
     }
@@ -444,7 +1047,7 @@ impl Interpreter {
                 return scope.get_variable(name);
             }
         }
-        panic!("Variable with name `{}` not found", name);
+        self.global_scope.get_variable(name)
     }
 
     fn set_variable(&mut self, name: &str, value: Value) {
@@ -467,6 +1070,83 @@ impl Interpreter {
     }
 
     fn function_is_builtin(&self, name: &str) -> bool {
-        builtin::BUILTIN_FUNCTIONS.contains_key(name)
+        builtin::BUILTIN_FUNCTIONS.contains_key(name) || builtin::OPTIONAL_FUNCTIONS.contains(&name)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `value` is a value of `type_`. Enums are stored as their integer
+/// discriminant, and struct values are not checked against their declaration.
+fn value_has_type(value: &Value, type_: &Type) -> bool {
+    match (type_, value) {
+        (Type::Optional(_) | Type::None, Value::None) => true,
+        (Type::Optional(inner), value) => value_has_type(value, inner),
+        (Type::Array(element), Value::Array(values)) => {
+            values.iter().all(|value| value_has_type(value, element))
+        }
+        (Type::Tuple(types), Value::Tuple(values)) => {
+            types.len() == values.len()
+                && types
+                    .iter()
+                    .zip(values)
+                    .all(|(type_, value)| value_has_type(value, type_))
+        }
+        (Type::Void, Value::Void)
+        | (Type::Integer | Type::Enum(_), Value::Integer(_))
+        | (Type::Float, Value::Float(_))
+        | (Type::String, Value::String(_))
+        | (Type::Boolean, Value::Boolean(_))
+        | (Type::Function { .. }, Value::Function(_))
+        | (Type::Struct(_), Value::Struct(_)) => true,
+        _ => false,
+    }
+}
+
+/// The typechecker already rules out invalid operands, but embedders can call
+/// [`Interpreter::evaluate_infix_operator`] directly, so this keeps the
+/// [`Value`] operations from panicking.
+fn check_operand_types(lhs: &Value, operator: TokenKind, rhs: &Value) -> ExecutionResult<()> {
+    let is_equality = matches!(
+        operator,
+        TokenKind::EqualsEquals | TokenKind::ExclamationMarkEquals
+    );
+    // An optional is either `none` or holds its value directly.
+    if is_equality && (lhs.is_none() || rhs.is_none()) {
+        return Ok(());
+    }
+
+    if std::mem::discriminant(lhs) != std::mem::discriminant(rhs) {
+        return Err(ExecutionError::new(
+            ExecutionErrorKind::MismatchedOperandTypes {
+                left: lhs.type_name(),
+                right: rhs.type_name(),
+            },
+        ));
+    }
+
+    let supported = match operator {
+        TokenKind::Plus => matches!(
+            lhs,
+            Value::Integer(_) | Value::Float(_) | Value::String(_) | Value::Array(_)
+        ),
+        TokenKind::EqualsEquals | TokenKind::ExclamationMarkEquals => matches!(
+            lhs,
+            Value::Integer(_) | Value::Float(_) | Value::String(_) | Value::Boolean(_)
+        ),
+        _ => matches!(lhs, Value::Integer(_) | Value::Float(_)),
+    };
+    if !supported {
+        return Err(ExecutionError::new(
+            ExecutionErrorKind::UnsupportedOperator {
+                operator,
+                type_: lhs.type_name(),
+            },
+        ));
     }
+    Ok(())
 }
@@ -0,0 +1,46 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small SplitMix64 generator behind the `random_*` builtins. It's not
+/// suitable for cryptography, but the same seed always gives the same sequence.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeds the generator from the current time.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns an integer in `min..max`, or `None` if the range is empty.
+    pub fn int_in_range(&mut self, min: i64, max: i64) -> Option<i64> {
+        if min >= max {
+            return None;
+        }
+        let span = (max as i128 - min as i128) as u128;
+        let offset = (self.next_u64() as u128 % span) as i128;
+        Some((min as i128 + offset) as i64)
+    }
+
+    /// Returns a float in `0.0..1.0`.
+    pub fn float(&mut self) -> f64 {
+        // The top 53 bits fill the mantissa of an `f64` exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -1,9 +1,20 @@
+use std::collections::HashMap;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Integer(i64),
     Float(f64),
     Boolean(bool),
     String(String),
+    Void,
+    /// The empty value of an optional type.
+    None,
+    Array(Vec<Value>),
+    /// A function used as a value, referring to it by name.
+    Function(String),
+    Tuple(Vec<Value>),
+    /// The fields of a struct value by name.
+    Struct(HashMap<String, Value>),
 }
 impl Value {
     pub fn is_integer(&self) -> bool {
@@ -30,11 +41,95 @@ impl Value {
         matches!(self, Value::String(_))
     }
 
+    pub fn is_void(&self) -> bool {
+        matches!(self, Value::Void)
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The name of the value's type, as it is written in Bau.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "int",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "bool",
+            Value::String(_) => "string",
+            Value::Void => "void",
+            Value::None => "none",
+            Value::Array(_) => "array",
+            Value::Function(_) => "function",
+            Value::Tuple(_) => "tuple",
+            Value::Struct(_) => "struct",
+        }
+    }
+
+    /// Serializes the value as JSON. Values without a JSON counterpart, like
+    /// `void`, `none`, functions and non-finite floats, become `null`.
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) if value.is_finite() => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            Value::String(value) => json_string(value),
+            Value::Array(values) | Value::Tuple(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|value| value.to_json())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Value::Struct(fields) => format!(
+                "{{{}}}",
+                sorted_fields(fields)
+                    .map(|(name, value)| format!("{}:{}", json_string(name), value.to_json()))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            Value::Float(_) | Value::Void | Value::None | Value::Function(_) => "null".to_string(),
+        }
+    }
+
     pub fn add(&mut self, other: Value) {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Integer(this + other),
             (Value::Float(this), Value::Float(other)) => Value::Float(this + other),
-            _ => panic!("Typechhecker should have checked these"),
+            (Value::String(this), Value::String(other)) => Value::String(this + &other),
+            (Value::Array(mut this), Value::Array(other)) => {
+                this.extend(other);
+                Value::Array(this)
+            }
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -43,7 +138,7 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Integer(this - other),
             (Value::Float(this), Value::Float(other)) => Value::Float(this - other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -52,7 +147,7 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Integer(this * other),
             (Value::Float(this), Value::Float(other)) => Value::Float(this * other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -61,16 +156,18 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Integer(this / other),
             (Value::Float(this), Value::Float(other)) => Value::Float(this / other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
 
+    /// The remainder of truncating division, so the result has the sign of
+    /// the left side, like Rust's `%` for both integers and floats.
     pub fn modulo(&mut self, other: Value) {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Integer(this % other),
             (Value::Float(this), Value::Float(other)) => Value::Float(this % other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -81,7 +178,10 @@ impl Value {
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this == other),
             (Value::String(this), Value::String(other)) => Value::Boolean(this == other),
             (Value::Boolean(this), Value::Boolean(other)) => Value::Boolean(this == other),
-            _ => panic!("Typechhecker should have checked these"),
+            // An optional is either `none` or holds its value directly.
+            (Value::None, Value::None) => Value::Boolean(true),
+            (Value::None, _) | (_, Value::None) => Value::Boolean(false),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -92,7 +192,9 @@ impl Value {
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this != other),
             (Value::String(this), Value::String(other)) => Value::Boolean(this != other),
             (Value::Boolean(this), Value::Boolean(other)) => Value::Boolean(this != other),
-            _ => panic!("Typechhecker should have checked these"),
+            (Value::None, Value::None) => Value::Boolean(false),
+            (Value::None, _) | (_, Value::None) => Value::Boolean(true),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -101,7 +203,7 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Boolean(this < other),
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this < other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -110,7 +212,7 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Boolean(this <= other),
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this <= other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -119,7 +221,7 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Boolean(this > other),
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this > other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
@@ -128,12 +230,47 @@ impl Value {
         let value = match (self.clone(), other) {
             (Value::Integer(this), Value::Integer(other)) => Value::Boolean(this >= other),
             (Value::Float(this), Value::Float(other)) => Value::Boolean(this >= other),
-            _ => panic!("Typechhecker should have checked these"),
+            _ => unchecked_operands(),
         };
         *self = value;
     }
 }
 
+/// The fields of a struct value ordered by name, so they are always written
+/// in the same order.
+fn sorted_fields(fields: &HashMap<String, Value>) -> impl Iterator<Item = (&String, &Value)> {
+    let mut fields = fields.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|(name, _)| *name);
+    fields.into_iter()
+}
+
+/// The fallback of the operator methods on [`Value`], whose operand types the
+/// typechecker and `check_operand_types` have already checked.
+fn unchecked_operands() -> ! {
+    unreachable!("operand types are checked by the typechecker and `check_operand_types`")
+}
+
+/// Quotes `value` as a JSON string, escaping the characters JSON requires.
+fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+    for char in value.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Composite values are printed by recursing into their elements. A value
+/// owns its contents, so it forms a finite tree that can't contain itself,
+/// and printing always terminates without tracking visited values.
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let str = match self {
@@ -141,7 +278,87 @@ impl std::fmt::Display for Value {
             Value::Float(value) => value.to_string(),
             Value::Boolean(value) => value.to_string(),
             Value::String(value) => value.to_string(),
+            Value::Void => "void".to_string(),
+            Value::None => "none".to_string(),
+            Value::Function(name) => format!("fn {}", name),
+            Value::Array(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Struct(fields) => format!(
+                "{{ {} }}",
+                sorted_fields(fields)
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Tuple(values) => format!(
+                "({})",
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         };
         write!(f, "{}", str)
     }
 }
+
+/// A [`Value`] that could not be converted into a Rust type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl std::error::Error for ValueConversionError {}
+
+impl std::fmt::Display for ValueConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Expected a value of type `{}`, but found `{}` instead",
+            self.expected, self.found
+        )
+    }
+}
+
+macro_rules! impl_value_conversions {
+    ($rust_type:ty, $variant:ident, $type_name:literal) => {
+        impl From<$rust_type> for Value {
+            fn from(value: $rust_type) -> Self {
+                Value::$variant(value)
+            }
+        }
+
+        impl TryFrom<Value> for $rust_type {
+            type Error = ValueConversionError;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$variant(value) => Ok(value),
+                    other => Err(ValueConversionError {
+                        expected: $type_name,
+                        found: other.type_name(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+impl_value_conversions!(i64, Integer, "int");
+impl_value_conversions!(f64, Float, "float");
+impl_value_conversions!(bool, Boolean, "bool");
+impl_value_conversions!(String, String, "string");
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
@@ -1,50 +1,382 @@
-use error::BauError;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use error::{BauError, BauWarning};
 use interpreter::value::Value;
-use parser::Parser;
-use source::Source;
+use interpreter::NativeFunction;
+use parser::{ParsedItem, Parser};
+use source::{Source, SourceOrigin};
+use typechecker::Type;
 
 pub mod error;
+pub mod formatter;
 pub mod interpreter;
 pub mod parser;
 pub mod source;
 pub mod tokenizer;
-mod typechecker;
+pub mod typechecker;
 
-#[derive(Debug, Clone, PartialEq, Default)]
-pub struct Bau {}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bau {
+    dump_scopes_on_error: bool,
+    wrapping: bool,
+    arguments: Vec<String>,
+    max_call_args: usize,
+    seed: Option<u64>,
+    max_call_depth: usize,
+    max_steps: Option<u64>,
+    prelude: Option<String>,
+    max_source_size: usize,
+    natives: NativeFunctions,
+}
 
 impl Bau {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            dump_scopes_on_error: false,
+            wrapping: false,
+            arguments: vec![],
+            max_call_args: Parser::DEFAULT_MAX_ARGUMENTS,
+            seed: None,
+            max_call_depth: interpreter::Interpreter::DEFAULT_MAX_CALL_DEPTH,
+            max_steps: None,
+            prelude: None,
+            max_source_size: Self::DEFAULT_MAX_SOURCE_SIZE,
+            natives: NativeFunctions::default(),
+        }
+    }
+
+    /// The default maximum size of a source, in bytes.
+    pub const DEFAULT_MAX_SOURCE_SIZE: usize = 10 * 1024 * 1024;
+
+    /// Attach the interpreter's scope stack to runtime errors.
+    pub fn with_dump_scopes_on_error(mut self, dump_scopes_on_error: bool) -> Self {
+        self.dump_scopes_on_error = dump_scopes_on_error;
+        self
+    }
+
+    /// Wrap integer arithmetic on overflow instead of reporting an error.
+    pub fn with_wrapping(mut self, wrapping: bool) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+
+    /// The command-line arguments passed to `main` if it takes a `[string]`.
+    pub fn with_arguments(mut self, arguments: Vec<String>) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// Limit the number of parameters of a function and arguments in a call.
+    pub fn with_max_call_args(mut self, max_call_args: usize) -> Self {
+        self.max_call_args = max_call_args;
+        self
+    }
+
+    /// Limit how deeply calls may nest at runtime.
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Stop with an error after `max_steps` statements and loop iterations,
+    /// so that every run ends.
+    pub fn with_max_steps(mut self, max_steps: Option<u64>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Seed the `random_*` builtins so that runs are reproducible.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Source code whose items are available to the program, as if they were
+    /// written before it.
+    pub fn with_prelude(mut self, prelude: Option<String>) -> Self {
+        self.prelude = prelude;
+        self
+    }
+
+    /// Refuse to run sources larger than `max_source_size` bytes.
+    pub fn with_max_source_size(mut self, max_source_size: usize) -> Self {
+        self.max_source_size = max_source_size;
+        self
+    }
+
+    /// Lets programs call `function` as `name`, with the function type
+    /// `signature`, as in `fn(int) -> int`.
+    pub fn with_native_function(
+        mut self,
+        name: &str,
+        signature: Type,
+        function: Box<NativeFunction>,
+    ) -> Self {
+        self.natives
+            .0
+            .push((name.to_string(), signature, Arc::from(function)));
+        self
     }
 
     pub fn run(&self, input: &str) -> Result<Option<Value>, Vec<BauError>> {
+        self.run_with_warnings(input).0
+    }
+
+    pub fn run_with_warnings(
+        &self,
+        input: &str,
+    ) -> (Result<Option<Value>, Vec<BauError>>, Vec<BauWarning>) {
+        self.run_with_output(input, Box::new(std::io::stdout()))
+    }
+
+    /// Runs `input`, returning what it printed instead of writing it to stdout.
+    pub fn run_with_stdout_capture(
+        &self,
+        input: &str,
+    ) -> (Result<Option<Value>, Vec<BauError>>, String) {
+        let buffer = SharedBuffer::default();
+        let (result, _) = self.run_with_output(input, Box::new(buffer.clone()));
+        let output = String::from_utf8_lossy(&buffer.0.lock().unwrap()).into_owned();
+        (result, output)
+    }
+
+    fn run_with_output(
+        &self,
+        input: &str,
+        output: Box<dyn Write + Send>,
+    ) -> (Result<Option<Value>, Vec<BauError>>, Vec<BauWarning>) {
+        if let Err(error) = self.check_source_size(input.len()) {
+            return (Err(vec![error]), vec![]);
+        }
+        if let Err(errors) = self.check_natives() {
+            return (Err(errors), vec![]);
+        }
+
+        let prelude = match self.check_prelude() {
+            Ok(prelude) => prelude,
+            Err(errors) => return (Err(errors), vec![]),
+        };
+
         let source = Source::new(input);
-        match Parser::new(&source).parse_top_level() {
+        match Parser::new(&source)
+            .with_max_arguments(self.max_call_args)
+            .parse_top_level()
+        {
             Ok(items) => {
-                let mut typechecker = typechecker::Typechecker::new();
+                let items = [prelude, items].concat();
+                let mut typechecker = self.typechecker();
                 let checked_items = typechecker.check_items(&items);
+                let warnings = typechecker
+                    .warnings()
+                    .iter()
+                    .map(|warning| locate_warning(warning.clone().into()))
+                    .collect();
                 if !typechecker.errors().is_empty() {
-                    let errors = typechecker
+                    let mut errors: Vec<_> = typechecker
                         .errors()
                         .iter()
-                        .map(|err| BauError::from(err.clone()))
+                        .map(|err| locate_error(err.clone().into()))
                         .collect();
-                    Err(errors)
+                    error::sort_errors(&mut errors);
+                    (Err(errors), warnings)
                 } else {
-                    let mut interpreter = interpreter::Interpreter::new();
+                    let mut interpreter = interpreter::Interpreter::new()
+                        .with_wrapping(self.wrapping)
+                        .with_arguments(self.arguments.clone())
+                        .with_max_call_depth(self.max_call_depth)
+                        .with_max_steps(self.max_steps)
+                        .with_output(output);
+                    for (name, signature, function) in self.natives.0.iter() {
+                        let function = Arc::clone(function);
+                        interpreter.register_native(
+                            name,
+                            signature.clone(),
+                            Box::new(move |arguments| function(arguments)),
+                        );
+                    }
+                    if let Some(seed) = self.seed {
+                        interpreter = interpreter.with_seed(seed);
+                    }
                     match interpreter.run(&checked_items) {
-                        Ok(value) => Ok(value),
-                        Err(error) => Err(vec![BauError::from(error)]),
+                        Ok(Value::Void) => (Ok(None), warnings),
+                        Ok(value) => (Ok(Some(value)), warnings),
+                        Err(error) => {
+                            let error = match self.dump_scopes_on_error {
+                                true => error.with_scope_dump(interpreter.dump_scopes()),
+                                false => error,
+                            };
+                            (Err(vec![locate_error(error.into())]), warnings)
+                        }
                     }
                 }
             }
-            Err(error) => Err(vec![BauError::from(error)]),
+            Err(errors) => {
+                let mut errors: Vec<_> = errors.into_iter().map(BauError::from).collect();
+                error::sort_errors(&mut errors);
+                (Err(errors), vec![])
+            }
+        }
+    }
+
+    /// Parses and checks the prelude on its own, so that its errors can be
+    /// reported against its own source.
+    fn check_prelude(&self) -> Result<Vec<ParsedItem>, Vec<BauError>> {
+        let Some(prelude) = &self.prelude else {
+            return Ok(vec![]);
+        };
+
+        let source = Source::new(prelude).with_origin(SourceOrigin::Prelude);
+        let in_prelude = |error: BauError| BauError::PreludeError(Box::new(error));
+        let items = Parser::new(&source)
+            .with_max_arguments(self.max_call_args)
+            .parse_top_level()
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|error| in_prelude(error.into()))
+                    .collect::<Vec<_>>()
+            })?;
+
+        let mut typechecker = self.typechecker();
+        typechecker.check_items(&items);
+        let errors: Vec<_> = typechecker
+            .errors()
+            .iter()
+            // The prelude does not need a `main` of its own.
+            .filter(|error| {
+                error.kind() != &typechecker::error::TypecheckerErrorKind::MainFunctionNotDefined
+            })
+            .map(|error| in_prelude(error.clone().into()))
+            .collect();
+        match errors.is_empty() {
+            true => Ok(items),
+            false => Err(errors),
+        }
+    }
+
+    /// A typechecker that knows the signatures of the native functions.
+    fn typechecker(&self) -> typechecker::Typechecker {
+        let mut typechecker = typechecker::Typechecker::new();
+        for (name, signature, _) in self.natives.0.iter() {
+            typechecker.register_native(name, signature.clone());
         }
+        typechecker
     }
 
     pub fn run_file(&self, path: &str) -> Result<Option<Value>, Vec<BauError>> {
-        let file_content = std::fs::read_to_string(path).unwrap();
-        self.run(&file_content)
+        let source = self.read_source(path).map_err(|error| vec![error])?;
+        self.run(source.text())
+    }
+
+    /// Reads the source at `path`, checking its size before reading it so
+    /// huge files are rejected without loading them.
+    pub fn read_source(&self, path: &str) -> Result<Source<'static>, BauError> {
+        let io_error = |error: std::io::Error| BauError::IoError {
+            path: path.to_string(),
+            message: error.to_string(),
+        };
+        let size = std::fs::metadata(path).map_err(io_error)?.len();
+        self.check_source_size(usize::try_from(size).unwrap_or(usize::MAX))?;
+        Source::from_file(path).map_err(io_error)
+    }
+
+    /// Checks that every native function has a function type and a name of
+    /// its own, as the typechecker and interpreter rely on both.
+    fn check_natives(&self) -> Result<(), Vec<BauError>> {
+        let mut errors = vec![];
+        for (index, (name, signature, _)) in self.natives.0.iter().enumerate() {
+            if !matches!(signature, Type::Function { .. }) {
+                errors.push(BauError::InvalidNativeSignature {
+                    name: name.clone(),
+                    signature: signature.clone(),
+                });
+            }
+            let is_duplicate = self.natives.0[..index]
+                .iter()
+                .any(|(other_name, _, _)| other_name == name);
+            if interpreter::builtin::is_builtin_function(name) || is_duplicate {
+                errors.push(BauError::NativeFunctionAlreadyDefined { name: name.clone() });
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    fn check_source_size(&self, size: usize) -> Result<(), BauError> {
+        if size > self.max_source_size {
+            return Err(BauError::SourceTooLarge {
+                size,
+                limit: self.max_source_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Bau {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The prelude runs together with the program, so an error raised inside it
+/// is wrapped to be reported against the prelude's source.
+fn locate_error(error: BauError) -> BauError {
+    match error.range().map(|range| range.origin) {
+        Some(SourceOrigin::Prelude) => BauError::PreludeError(Box::new(error)),
+        _ => error,
+    }
+}
+
+/// Like [`locate_error`], for warnings.
+fn locate_warning(warning: BauWarning) -> BauWarning {
+    match warning.range().origin {
+        SourceOrigin::Prelude => BauWarning::PreludeWarning(Box::new(warning)),
+        SourceOrigin::Program => warning,
+    }
+}
+
+/// The native functions given to [`Bau`], by name and signature. They are
+/// shared so that `Bau` can be cloned, and compared by identity.
+#[derive(Clone, Default)]
+struct NativeFunctions(Vec<(String, Type, Arc<NativeFunction>)>);
+
+impl std::fmt::Debug for NativeFunctions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|(name, signature, _)| (name, signature)))
+            .finish()
+    }
+}
+
+impl PartialEq for NativeFunctions {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(other.0.iter()).all(
+                |((name, signature, function), (other_name, other_signature, other_function))| {
+                    name == other_name
+                        && signature == other_signature
+                        && Arc::ptr_eq(function, other_function)
+                },
+            )
+    }
+}
+
+/// A writer whose clones all append to the same bytes, so output can be read
+/// after the interpreter that wrote it is gone.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
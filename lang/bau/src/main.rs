@@ -1,22 +1,105 @@
+use bau::error::{BauError, BauWarning};
 use bau::source::Source;
+use bau::tokenizer::Tokenizer;
 use bau::Bau;
 use clap::Parser;
 
 #[derive(Parser)]
 struct Args {
     file: String,
+
+    /// Arguments passed to the script's `main` function
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+
+    /// Print the token stream of the file instead of running it
+    #[arg(long)]
+    tokens: bool,
+
+    /// Print the variables in scope when a runtime error occurs
+    #[arg(long)]
+    dump_scopes_on_error: bool,
+
+    /// The maximum number of parameters of a function or arguments in a call
+    #[arg(long, default_value_t = bau::parser::Parser::DEFAULT_MAX_ARGUMENTS)]
+    max_call_args: usize,
+
+    /// The maximum depth of nested calls at runtime
+    #[arg(long, default_value_t = bau::interpreter::Interpreter::DEFAULT_MAX_CALL_DEPTH)]
+    max_call_depth: usize,
+
+    /// Seed the `random_*` builtins so that runs are reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Stop after this many statements and loop iterations, so that seeded
+    /// test runs always end
+    #[arg(long)]
+    max_steps: Option<u64>,
+
+    /// A file whose items are available to the script, like a standard library
+    #[arg(long)]
+    prelude: Option<String>,
+
+    /// The maximum size of the file in bytes
+    #[arg(long, default_value_t = bau::Bau::DEFAULT_MAX_SOURCE_SIZE)]
+    max_source_size: usize,
 }
 
 fn main() {
     let args = Args::parse();
-    let src = std::fs::read_to_string(&args.file)
-        .unwrap_or_else(|_| panic!("Failed to read file: `{}`", args.file));
-    match Bau::new().run(&src) {
-        Ok(_) => {}
-        Err(errors) => {
-            let source = Source::new(&src);
-            for error in errors.iter() {
-                error.print(&source);
+    let bau = Bau::new().with_max_source_size(args.max_source_size);
+    let source = match bau.read_source(&args.file) {
+        Ok(source) => source,
+        Err(error) => {
+            error.print(&Source::new(""));
+            return;
+        }
+    };
+    let src = source.text();
+
+    if args.tokens {
+        for token in Tokenizer::new(src).tokenize() {
+            println!("{}", token);
+        }
+        return;
+    }
+
+    let prelude = match args.prelude.as_ref().map(|path| bau.read_source(path)) {
+        Some(Ok(prelude)) => Some(prelude),
+        Some(Err(error)) => {
+            error.print(&Source::new(""));
+            return;
+        }
+        None => None,
+    };
+
+    let (result, warnings) = bau
+        .with_dump_scopes_on_error(args.dump_scopes_on_error)
+        .with_arguments(args.args)
+        .with_max_call_args(args.max_call_args)
+        .with_max_call_depth(args.max_call_depth)
+        .with_seed(args.seed)
+        .with_max_steps(args.max_steps)
+        .with_prelude(prelude.as_ref().map(|prelude| prelude.text().to_string()))
+        .run_with_warnings(src);
+    for warning in warnings.iter() {
+        match (warning, &prelude) {
+            (BauWarning::PreludeWarning(_), Some(prelude)) => {
+                eprintln!("In prelude `{}`:", args.prelude.as_ref().unwrap());
+                warning.print(prelude);
+            }
+            _ => warning.print(&source),
+        }
+    }
+    if let Err(errors) = result {
+        for error in errors.iter() {
+            match (error, &prelude) {
+                (BauError::PreludeError(_), Some(prelude)) => {
+                    eprintln!("In prelude `{}`:", args.prelude.as_ref().unwrap());
+                    error.print(prelude);
+                }
+                _ => error.print(&source),
             }
         }
     }
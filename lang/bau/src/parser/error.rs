@@ -24,6 +24,13 @@ pub enum ParserErrorKind {
     InvalidPrefixOperator {
         found: TokenKind,
     },
+    TooManyArguments {
+        max: usize,
+    },
+    UnclosedCall {
+        name: String,
+    },
+    AssignmentInCondition,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -37,6 +44,14 @@ impl ParserError {
         Self { kind, range }
     }
 
+    pub fn kind(&self) -> &ParserErrorKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> &CodeRange {
+        &self.range
+    }
+
     pub fn print(&self, source: &Source) {
         print_error(source, Some(&self.range), &self.to_string());
     }
@@ -53,12 +68,12 @@ impl std::fmt::Display for ParserError {
                     expected, found
                 )
             }
-            ParserErrorKind::UnexpectedEndOfFile { .. } => {
+            ParserErrorKind::UnexpectedEndOfFile => {
                 "Expected a token, but found end of file instead".to_string()
             }
             ParserErrorKind::ExpectedItem { found } => {
                 format!(
-                    "Expected an item (`fn` or `extend`), but found `{}` instead",
+                    "Expected an item (`fn`, `extend`, `trait`, `enum`, `struct`, `const` or an `@` attribute), but found `{}` instead",
                     found
                 )
             }
@@ -74,6 +89,17 @@ impl std::fmt::Display for ParserError {
             ParserErrorKind::InvalidPrefixOperator { found } => {
                 format!("Invalid prefix operator `{}`", found)
             }
+            ParserErrorKind::TooManyArguments { max } => {
+                format!("Functions cannot take more than {} arguments", max)
+            }
+            ParserErrorKind::UnclosedCall { name } => format!(
+                "Unclosed `(` in call to `{}` opened at line {}",
+                name,
+                self.range.coords.line + 1
+            ),
+            ParserErrorKind::AssignmentInCondition => {
+                "Cannot assign in a condition, did you mean `==`?".to_string()
+            }
         };
 
         write!(f, "{}", str)
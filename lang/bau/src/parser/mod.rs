@@ -17,6 +17,8 @@ pub enum AssignmentOperator {
     AsteriskEquals,
     SlashEquals,
     PercentEquals,
+    AmpersandAmpersandEquals,
+    PipePipeEquals,
 }
 
 impl TryFrom<TokenKind> for AssignmentOperator {
@@ -30,11 +32,29 @@ impl TryFrom<TokenKind> for AssignmentOperator {
             TokenKind::AsteriskEquals => Ok(Self::AsteriskEquals),
             TokenKind::SlashEquals => Ok(Self::SlashEquals),
             TokenKind::PercentEquals => Ok(Self::PercentEquals),
+            TokenKind::AmpersandAmpersandEquals => Ok(Self::AmpersandAmpersandEquals),
+            TokenKind::PipePipeEquals => Ok(Self::PipePipeEquals),
             _ => Err(()),
         }
     }
 }
 
+impl AssignmentOperator {
+    /// The infix operator a compound assignment applies, or `None` for `=`.
+    pub fn infix_operator(&self) -> Option<TokenKind> {
+        match self {
+            Self::Equals => None,
+            Self::PlusEquals => Some(TokenKind::Plus),
+            Self::MinusEquals => Some(TokenKind::Minus),
+            Self::AsteriskEquals => Some(TokenKind::Asterisk),
+            Self::SlashEquals => Some(TokenKind::Slash),
+            Self::PercentEquals => Some(TokenKind::Percent),
+            Self::AmpersandAmpersandEquals => Some(TokenKind::AmpersandAmpersand),
+            Self::PipePipeEquals => Some(TokenKind::PipePipe),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PrefixOperator {
     Plus,
@@ -75,6 +95,10 @@ impl TypeName {
 pub enum ParsedItemKind {
     Function(ParsedFunctionItem),
     Extend(ParsedExtendItem),
+    Const(ParsedConstItem),
+    Trait(ParsedTraitItem),
+    Enum(ParsedEnumItem),
+    Struct(ParsedStructItem),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -97,8 +121,16 @@ impl ParsedItem {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedAttribute {
+    pub name: Identifier,
+    pub arguments: Vec<ParsedExpression>,
+    pub range: CodeRange,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsedFunctionItem {
+    pub attributes: Vec<ParsedAttribute>,
     pub name: Identifier,
     pub parameters: Vec<ParsedFunctionParameter>,
     pub return_type_name: TypeName,
@@ -112,18 +144,84 @@ pub struct ParsedFunctionParameter {
     pub type_name: TypeName,
 }
 
+/// A name and its type in a destructuring `let`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedBinding {
+    pub name: Identifier,
+    pub type_name: TypeName,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsedExtendItem {
     pub type_name: TypeName,
+    /// The trait this block implements, as in `extend int: Describe { ... }`.
+    pub trait_name: Option<Identifier>,
     pub functions: Vec<ParsedFunctionItem>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTraitItem {
+    pub name: Identifier,
+    pub methods: Vec<ParsedTraitMethod>,
+}
+
+/// A method signature required by a trait, such as `fn draw() -> string;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTraitMethod {
+    pub name: Identifier,
+    pub parameters: Vec<ParsedFunctionParameter>,
+    pub return_type_name: TypeName,
+    pub range: CodeRange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedEnumItem {
+    pub name: Identifier,
+    pub variants: Vec<ParsedEnumVariant>,
+}
+
+/// A variant such as `NotFound = 404`. Without a value, a variant is one
+/// more than the variant before it, or `0` if it is the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedEnumVariant {
+    pub name: Identifier,
+    pub value: Option<ParsedExpression>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedStructItem {
+    pub name: Identifier,
+    pub fields: Vec<ParsedStructField>,
+}
+
+/// A field declaration such as `int x;`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedStructField {
+    pub name: Identifier,
+    pub type_name: TypeName,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedConstItem {
+    pub name: Identifier,
+    pub type_name: TypeName,
+    pub value: ParsedExpression,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParsedStatementKind {
     Let {
         name: Identifier,
         type_name: TypeName,
         initial_value: ParsedExpression,
+        /// Whether the variable was declared with `let mut`, which allows
+        /// assigning to it.
+        mutable: bool,
+    },
+    /// `let (T a, U b) = value;`, which binds each element of a tuple.
+    DestructuringLet {
+        bindings: Vec<ParsedBinding>,
+        initial_value: ParsedExpression,
     },
     VariableAssignment {
         name: Identifier,
@@ -141,6 +239,15 @@ pub enum ParsedStatementKind {
         then_body: Vec<ParsedStatement>,
         else_body: Option<Vec<ParsedStatement>>,
     },
+    /// `if let T name = value { ... }`, which runs `then_body` with the inner
+    /// value bound to `name` if the optional `value` is not `none`.
+    IfLet {
+        name: Identifier,
+        type_name: TypeName,
+        value: ParsedExpression,
+        then_body: Vec<ParsedStatement>,
+        else_body: Option<Vec<ParsedStatement>>,
+    },
     Loop {
         body: Vec<ParsedStatement>,
     },
@@ -148,6 +255,14 @@ pub enum ParsedStatementKind {
         condition: Option<ParsedExpression>,
         block: Vec<ParsedStatement>,
     },
+    Break,
+    Continue,
+    /// A function nested in a function body, visible only in its block.
+    FunctionDef(ParsedFunctionItem),
+    /// `defer statement;`, which runs `statement` when the function returns.
+    Defer {
+        statement: Box<ParsedStatement>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -188,17 +303,73 @@ pub enum ParsedExpressionKind {
         expression: Box<ParsedExpression>,
         call: ParsedFunctionCall,
     },
+    /// `expression.member`, as in `Status.NotFound`.
+    MemberAccess {
+        expression: Box<ParsedExpression>,
+        member: Identifier,
+    },
+    /// `condition ? then_branch : else_branch`
+    Ternary {
+        condition: Box<ParsedExpression>,
+        then_branch: Box<ParsedExpression>,
+        else_branch: Box<ParsedExpression>,
+    },
+    /// `match value { pattern if guard => result, ... }`
+    Match {
+        value: Box<ParsedExpression>,
+        arms: Vec<ParsedMatchArm>,
+    },
+    /// `(a, b)`, with at least two elements.
+    Tuple(Vec<ParsedExpression>),
+    /// `Point { x: 1, y: 2 }`
+    Struct {
+        name: Identifier,
+        fields: Vec<ParsedFieldValue>,
+    },
+}
+
+/// A field and its value in a struct expression, as in `x: 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedFieldValue {
+    pub name: Identifier,
+    pub value: ParsedExpression,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMatchArm {
+    pub pattern: ParsedPattern,
+    /// A condition that has to hold as well, checked after the pattern matched.
+    pub guard: Option<ParsedExpression>,
+    pub value: ParsedExpression,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedPattern {
+    /// A name, which matches any value and binds it, or `_` to ignore it.
+    Binding(Identifier),
+    /// A literal or enum variant, which matches values equal to it.
+    Value(ParsedExpression),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsedExpression {
     kind: ParsedExpressionKind,
     range: CodeRange,
+    parenthesized: bool,
 }
 
 impl ParsedExpression {
     pub fn new(kind: ParsedExpressionKind, range: CodeRange) -> Self {
-        Self { kind, range }
+        Self {
+            kind,
+            range,
+            parenthesized: false,
+        }
+    }
+
+    /// Whether the expression was written in parentheses, as in `(a < b)`.
+    pub fn is_parenthesized(&self) -> bool {
+        self.parenthesized
     }
 
     pub fn kind(&self) -> &ParsedExpressionKind {
@@ -241,63 +412,137 @@ pub struct Parser<'source> {
     source: &'source Source<'source>,
     tokens: Vec<Token>,
     cursor: usize,
+    max_arguments: usize,
 }
 
 impl<'source> Parser<'source> {
+    /// The default limit on the number of parameters of a function and the
+    /// number of arguments in a call.
+    pub const DEFAULT_MAX_ARGUMENTS: usize = 255;
+
     pub fn new(source: &'source Source) -> Self {
-        let mut tokens = Tokenizer::new(source.text()).tokenize();
+        let mut tokens = Tokenizer::new(source.text())
+            .with_origin(source.origin())
+            .tokenize();
         preprocess_tokens(&mut tokens);
         Self {
             source,
             tokens,
             cursor: 0,
+            max_arguments: Self::DEFAULT_MAX_ARGUMENTS,
         }
     }
 
-    pub fn parse_top_level(&mut self) -> ParserResult<Vec<ParsedItem>> {
+    pub fn with_max_arguments(mut self, max_arguments: usize) -> Self {
+        self.max_arguments = max_arguments;
+        self
+    }
+
+    /// Parses all items in the source. A malformed item does not stop
+    /// parsing: its error is recorded and parsing resumes at the next item.
+    pub fn parse_top_level(&mut self) -> Result<Vec<ParsedItem>, Vec<ParserError>> {
         let mut items = vec![];
+        let mut errors = vec![];
         while !self.done() {
-            match self.parse_item()? {
-                Some(item) => items.push(item),
-                _ => {
-                    return Err(ParserError::new(
+            let item_start = self.cursor;
+            match self.parse_item() {
+                Ok(Some(item)) => items.push(item),
+                Ok(None) => {
+                    let token = self.peek().expect("parser should not be done");
+                    errors.push(ParserError::new(
                         ParserErrorKind::ExpectedItem {
-                            found: self.peek_kind()?,
+                            found: token.kind(),
                         },
-                        self.peek()?.range(),
-                    ))
+                        token.range(),
+                    ));
+                    self.skip_to_next_item(item_start);
+                }
+                Err(error) => {
+                    errors.push(error);
+                    self.skip_to_next_item(item_start);
                 }
             }
         }
-        Ok(items)
+
+        match errors.is_empty() {
+            true => Ok(items),
+            false => Err(errors),
+        }
+    }
+
+    /// Skips past the item that started at `item_start`, up to the next
+    /// token that can start an item.
+    fn skip_to_next_item(&mut self, item_start: usize) {
+        let end_of_file = self.tokens.len() - 1;
+        self.cursor = self.cursor.max(item_start + 1).min(end_of_file);
+        while !matches!(
+            self.peek_kind(),
+            Ok(TokenKind::Fn
+                | TokenKind::Extend
+                | TokenKind::Trait
+                | TokenKind::Enum
+                | TokenKind::Struct
+                | TokenKind::Const
+                | TokenKind::At
+                | TokenKind::EndOfFile)
+        ) {
+            self.cursor += 1;
+        }
     }
 
     fn parse_item(&mut self) -> ParserResult<Option<ParsedItem>> {
         let start = self.current_token_range()?;
         match self.peek_kind()? {
-            TokenKind::Fn => {
-                let end = self.current_token_range()?;
-
-                self.parse_function_item().map(|f| {
-                    f.map(|f| {
-                        ParsedItem::new(
-                            ParsedItemKind::Function(f),
-                            CodeRange::from_ranges(start, end),
-                        )
-                    })
-                })
+            TokenKind::Fn | TokenKind::At => {
+                let function = self.parse_function_item()?;
+                let end = self.previous_token_range()?;
+                Ok(function.map(|f| {
+                    ParsedItem::new(
+                        ParsedItemKind::Function(f),
+                        CodeRange::from_ranges(start, end),
+                    )
+                }))
             }
             TokenKind::Extend => {
-                let end = self.current_token_range()?;
-
-                self.parse_extend_item().map(|e| {
-                    e.map(|e| {
-                        ParsedItem::new(
-                            ParsedItemKind::Extend(e),
-                            CodeRange::from_ranges(start, end),
-                        )
-                    })
-                })
+                let extend = self.parse_extend_item()?;
+                let end = self.previous_token_range()?;
+                Ok(extend.map(|e| {
+                    ParsedItem::new(
+                        ParsedItemKind::Extend(e),
+                        CodeRange::from_ranges(start, end),
+                    )
+                }))
+            }
+            TokenKind::Trait => {
+                let trait_item = self.parse_trait_item()?;
+                let end = self.previous_token_range()?;
+                Ok(trait_item.map(|t| {
+                    ParsedItem::new(ParsedItemKind::Trait(t), CodeRange::from_ranges(start, end))
+                }))
+            }
+            TokenKind::Enum => {
+                let enum_item = self.parse_enum_item()?;
+                let end = self.previous_token_range()?;
+                Ok(enum_item.map(|e| {
+                    ParsedItem::new(ParsedItemKind::Enum(e), CodeRange::from_ranges(start, end))
+                }))
+            }
+            TokenKind::Struct => {
+                let struct_item = self.parse_struct_item()?;
+                let end = self.previous_token_range()?;
+                Ok(struct_item.map(|s| {
+                    ParsedItem::new(
+                        ParsedItemKind::Struct(s),
+                        CodeRange::from_ranges(start, end),
+                    )
+                }))
+            }
+            TokenKind::Const => {
+                let constant = self.parse_const_item()?;
+                let end = self.previous_token_range()?;
+                Ok(constant.map(|c| {
+                    ParsedItem::new(ParsedItemKind::Const(c), CodeRange::from_ranges(start, end))
+                }))
             }
             _ => Ok(None),
         }
@@ -305,6 +550,7 @@ impl<'source> Parser<'source> {
 
     fn parse_function_item(&mut self) -> ParserResult<Option<ParsedFunctionItem>> {
         let start = self.current_token_range()?;
+        let attributes = self.parse_attributes()?;
         self.consume_specific(TokenKind::Fn)?;
 
         let name = self.parse_identifier()?;
@@ -324,6 +570,7 @@ impl<'source> Parser<'source> {
         self.consume_specific(TokenKind::BraceClose)?;
 
         Ok(Some(ParsedFunctionItem {
+            attributes,
             name,
             parameters,
             return_type_name,
@@ -332,27 +579,43 @@ impl<'source> Parser<'source> {
         }))
     }
 
-    fn parse_function_parameters(&mut self) -> ParserResult<Vec<ParsedFunctionParameter>> {
-        let mut parameters = vec![];
-        self.parse_next_function_parameter(&mut parameters)?;
-        Ok(parameters)
-    }
-
-    fn parse_next_function_parameter(
-        &mut self,
-        parameters: &mut Vec<ParsedFunctionParameter>,
-    ) -> ParserResult<()> {
-        if self.peek_kind() == Ok(TokenKind::ParenClose) {
-            return Ok(());
+    fn parse_attributes(&mut self) -> ParserResult<Vec<ParsedAttribute>> {
+        let mut attributes = vec![];
+        while self.peek_kind()? == TokenKind::At {
+            let start = self.current_token_range()?;
+            self.consume_specific(TokenKind::At)?;
+            let name = self.parse_identifier()?;
+            let arguments = if self.consume_if(TokenKind::ParenOpen) {
+                let arguments = self.parse_function_arguments()?;
+                self.consume_specific(TokenKind::ParenClose)?;
+                arguments
+            } else {
+                vec![]
+            };
+            let end = self.previous_token_range()?;
+            attributes.push(ParsedAttribute {
+                name,
+                arguments,
+                range: CodeRange::from_ranges(start, end),
+            });
         }
+        Ok(attributes)
+    }
 
-        if let Some(parameter) = self.parse_function_parameter()? {
+    fn parse_function_parameters(&mut self) -> ParserResult<Vec<ParsedFunctionParameter>> {
+        let mut parameters = vec![];
+        // Checking for `)` before each item also accepts a trailing comma.
+        while self.peek_kind() != Ok(TokenKind::ParenClose) {
+            let Some(parameter) = self.parse_function_parameter()? else {
+                break;
+            };
+            self.check_argument_count(parameters.len(), parameter.name.token().range())?;
             parameters.push(parameter);
-            if self.consume_if(TokenKind::Comma) {
-                self.parse_next_function_parameter(parameters)?;
+            if !self.consume_if(TokenKind::Comma) {
+                break;
             }
         }
-        Ok(())
+        Ok(parameters)
     }
 
     fn parse_function_parameter(&mut self) -> ParserResult<Option<ParsedFunctionParameter>> {
@@ -365,23 +628,34 @@ impl<'source> Parser<'source> {
 
     fn parse_function_arguments(&mut self) -> ParserResult<Vec<ParsedExpression>> {
         let mut arguments = vec![];
-        self.parse_next_function_argument(&mut arguments)?;
+        // Checking for `)` before each item also accepts a trailing comma.
+        // The caller reports a missing `)` at the end of the file.
+        while !matches!(
+            self.peek_kind(),
+            Ok(TokenKind::ParenClose | TokenKind::EndOfFile) | Err(_)
+        ) {
+            let Some(argument) = self.parse_expression()? else {
+                break;
+            };
+            self.check_argument_count(arguments.len(), *argument.range())?;
+            arguments.push(argument);
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
         Ok(arguments)
     }
 
-    fn parse_next_function_argument(
-        &mut self,
-        arguments: &mut Vec<ParsedExpression>,
-    ) -> ParserResult<()> {
-        if self.peek_kind() == Ok(TokenKind::ParenClose) {
-            return Ok(());
-        }
-
-        if let Some(argument) = self.parse_expression()? {
-            arguments.push(argument);
-            if self.consume_if(TokenKind::Comma) {
-                self.parse_next_function_argument(arguments)?;
-            }
+    /// Fails if another parameter or argument would exceed the limit, where
+    /// `count` is the number already parsed and `range` that of the next one.
+    fn check_argument_count(&self, count: usize, range: CodeRange) -> ParserResult<()> {
+        if count >= self.max_arguments {
+            return Err(ParserError::new(
+                ParserErrorKind::TooManyArguments {
+                    max: self.max_arguments,
+                },
+                range,
+            ));
         }
         Ok(())
     }
@@ -391,6 +665,11 @@ impl<'source> Parser<'source> {
 
         let type_name = self.parse_type_name()?;
 
+        let trait_name = match self.consume_if(TokenKind::Colon) {
+            true => Some(self.parse_identifier()?),
+            false => None,
+        };
+
         self.consume_specific(TokenKind::BraceOpen)?;
         let mut functions = vec![];
         while self.peek_kind() != Ok(TokenKind::BraceClose) {
@@ -404,10 +683,124 @@ impl<'source> Parser<'source> {
 
         Ok(Some(ParsedExtendItem {
             type_name,
+            trait_name,
             functions,
         }))
     }
 
+    fn parse_trait_item(&mut self) -> ParserResult<Option<ParsedTraitItem>> {
+        self.consume_specific(TokenKind::Trait)?;
+
+        let name = self.parse_identifier()?;
+
+        self.consume_specific(TokenKind::BraceOpen)?;
+        let mut methods = vec![];
+        while self.peek_kind()? == TokenKind::Fn {
+            let start = self.current_token_range()?;
+            self.consume_specific(TokenKind::Fn)?;
+
+            let name = self.parse_identifier()?;
+
+            self.consume_specific(TokenKind::ParenOpen)?;
+            let parameters = self.parse_function_parameters()?;
+            self.consume_specific(TokenKind::ParenClose)?;
+
+            self.consume_specific(TokenKind::Arrow)?;
+
+            let return_type_name = self.parse_type_name()?;
+
+            let end = self.current_token_range()?;
+            self.consume_specific(TokenKind::Semicolon)?;
+
+            methods.push(ParsedTraitMethod {
+                name,
+                parameters,
+                return_type_name,
+                range: CodeRange::from_ranges(start, end),
+            });
+        }
+        self.consume_specific(TokenKind::BraceClose)?;
+
+        Ok(Some(ParsedTraitItem { name, methods }))
+    }
+
+    fn parse_enum_item(&mut self) -> ParserResult<Option<ParsedEnumItem>> {
+        self.consume_specific(TokenKind::Enum)?;
+
+        let name = self.parse_identifier()?;
+
+        self.consume_specific(TokenKind::BraceOpen)?;
+        let mut variants = vec![];
+        // Checking for `}` before each item also accepts a trailing comma.
+        while self.peek_kind()? != TokenKind::BraceClose {
+            let name = self.parse_identifier()?;
+            let mut value = None;
+            if self.consume_if(TokenKind::Equals) {
+                let Some(expression) = self.parse_expression()? else {
+                    return Err(ParserError::new(
+                        ParserErrorKind::ExpectedExpression {
+                            found: self.peek_kind()?,
+                        },
+                        self.peek()?.range(),
+                    ));
+                };
+                value = Some(expression);
+            }
+            variants.push(ParsedEnumVariant { name, value });
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume_specific(TokenKind::BraceClose)?;
+
+        Ok(Some(ParsedEnumItem { name, variants }))
+    }
+
+    fn parse_struct_item(&mut self) -> ParserResult<Option<ParsedStructItem>> {
+        self.consume_specific(TokenKind::Struct)?;
+
+        let name = self.parse_identifier()?;
+
+        self.consume_specific(TokenKind::BraceOpen)?;
+        let mut fields = vec![];
+        while self.peek_kind()? != TokenKind::BraceClose {
+            let type_name = self.parse_type_name()?;
+            let name = self.parse_identifier()?;
+            self.consume_specific(TokenKind::Semicolon)?;
+            fields.push(ParsedStructField { name, type_name });
+        }
+        self.consume_specific(TokenKind::BraceClose)?;
+
+        Ok(Some(ParsedStructItem { name, fields }))
+    }
+
+    fn parse_const_item(&mut self) -> ParserResult<Option<ParsedConstItem>> {
+        self.consume_specific(TokenKind::Const)?;
+
+        let type_name = self.parse_type_name()?;
+
+        let name = self.parse_identifier()?;
+
+        self.consume_specific(TokenKind::Equals)?;
+
+        let Some(value) = self.parse_expression()? else {
+            return Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            ));
+        };
+
+        self.consume_specific(TokenKind::Semicolon)?;
+
+        Ok(Some(ParsedConstItem {
+            name,
+            type_name,
+            value,
+        }))
+    }
+
     fn parse_statement_list(&mut self) -> ParserResult<Vec<ParsedStatement>> {
         let mut statements = vec![];
         while self.peek_kind() != Ok(TokenKind::BraceClose) {
@@ -427,23 +820,97 @@ impl<'source> Parser<'source> {
             TokenKind::If => self.parse_if_statement(),
             TokenKind::Loop => self.parse_loop_statement(),
             TokenKind::While => self.parse_while_statement(),
+            TokenKind::Break => self.parse_loop_control_statement(ParsedStatementKind::Break),
+            TokenKind::Continue => self.parse_loop_control_statement(ParsedStatementKind::Continue),
+            TokenKind::Fn | TokenKind::At => self.parse_function_def_statement(),
+            TokenKind::Defer => self.parse_defer_statement(),
             TokenKind::Identifier => match self.peek_kind_at(1)? {
                 TokenKind::Equals
                 | TokenKind::PlusEquals
                 | TokenKind::MinusEquals
                 | TokenKind::AsteriskEquals
                 | TokenKind::SlashEquals
-                | TokenKind::PercentEquals => self.parse_variable_assignment_statement(),
+                | TokenKind::PercentEquals
+                | TokenKind::AmpersandAmpersandEquals
+                | TokenKind::PipePipeEquals => self.parse_variable_assignment_statement(),
                 _ => self.parse_expression_statement(),
             },
             _ => self.parse_expression_statement(),
         }
     }
 
+    /// Whether the next tokens are `(T name`, which starts the bindings of a
+    /// destructuring `let` rather than a tuple type.
+    fn at_destructuring_bindings(&mut self) -> bool {
+        if self.peek_kind() != Ok(TokenKind::ParenOpen) {
+            return false;
+        }
+        let cursor = self.cursor;
+        self.cursor += 1;
+        let is_binding =
+            self.parse_type_name().is_ok() && self.peek_kind() == Ok(TokenKind::Identifier);
+        self.cursor = cursor;
+        is_binding
+    }
+
+    fn parse_destructuring_let_statement(
+        &mut self,
+        start: CodeRange,
+    ) -> ParserResult<Option<ParsedStatement>> {
+        self.consume_specific(TokenKind::ParenOpen)?;
+        let mut bindings = vec![];
+        loop {
+            let type_name = self.parse_type_name()?;
+            let name = self.parse_identifier()?;
+            bindings.push(ParsedBinding { name, type_name });
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume_specific(TokenKind::ParenClose)?;
+        self.consume_specific(TokenKind::Equals)?;
+
+        let Some(initial_value) = self.parse_expression()? else {
+            return Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            ));
+        };
+
+        let end = self.current_token_range()?;
+        self.consume_specific(TokenKind::Semicolon)?;
+
+        Ok(Some(ParsedStatement::new(
+            ParsedStatementKind::DestructuringLet {
+                bindings,
+                initial_value,
+            },
+            CodeRange::from_ranges(start, end),
+        )))
+    }
+
+    fn parse_function_def_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
+        let Some(function) = self.parse_function_item()? else {
+            return Ok(None);
+        };
+        let range = function.range;
+        Ok(Some(ParsedStatement::new(
+            ParsedStatementKind::FunctionDef(function),
+            range,
+        )))
+    }
+
     fn parse_let_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
         let start = self.current_token_range()?;
         self.consume_specific(TokenKind::Let)?;
 
+        if self.at_destructuring_bindings() {
+            return self.parse_destructuring_let_statement(start);
+        }
+
+        let mutable = self.consume_if(TokenKind::Mut);
         let type_name = self.parse_type_name()?;
 
         let name = self.parse_identifier()?;
@@ -461,6 +928,7 @@ impl<'source> Parser<'source> {
             ));
         }
 
+        let end = self.current_token_range()?;
         self.consume_specific(TokenKind::Semicolon)?;
 
         Ok(Some(ParsedStatement::new(
@@ -468,16 +936,17 @@ impl<'source> Parser<'source> {
                 name,
                 type_name,
                 initial_value: initial_value.unwrap(),
+                mutable,
             },
-            CodeRange::from_ranges(start, self.current_token_range()?),
+            CodeRange::from_ranges(start, end),
         )))
     }
 
     fn parse_return_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
         let start = self.current_token_range()?;
-        let mut end = start;
         self.consume_specific(TokenKind::Return)?;
         if self.consume_if(TokenKind::Semicolon) {
+            let end = self.previous_token_range()?;
             return Ok(Some(ParsedStatement::new(
                 ParsedStatementKind::Return { value: None },
                 CodeRange::from_ranges(start, end),
@@ -485,7 +954,7 @@ impl<'source> Parser<'source> {
         }
 
         let expr = self.parse_expression()?;
-        end = self.previous_token_range()?;
+        let end = self.current_token_range()?;
         self.consume_specific(TokenKind::Semicolon)?;
         Ok(Some(ParsedStatement::new(
             ParsedStatementKind::Return { value: expr },
@@ -496,27 +965,57 @@ impl<'source> Parser<'source> {
     fn parse_if_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
         let start = self.current_token_range()?;
         self.consume_specific(TokenKind::If)?;
+        let binding = match self.consume_if(TokenKind::Let) {
+            true => {
+                let type_name = self.parse_type_name()?;
+                let name = self.parse_identifier()?;
+                self.consume_specific(TokenKind::Equals)?;
+                Some((type_name, name))
+            }
+            false => None,
+        };
         let condition = self.parse_expression()?;
-        let mut end = self.current_token_range()?;
+        if binding.is_none() {
+            self.check_assignment_in_condition()?;
+        }
+        if binding.is_some() && condition.is_none() {
+            return Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            ));
+        }
         self.consume_specific(TokenKind::BraceOpen)?;
         let then_body = self.parse_statement_list()?;
+        let mut end = self.current_token_range()?;
         self.consume_specific(TokenKind::BraceClose)?;
         let else_body = if self.consume_if(TokenKind::Else) {
             self.consume_specific(TokenKind::BraceOpen)?;
             let else_body = self.parse_statement_list()?;
-            self.consume_specific(TokenKind::BraceClose)?;
             end = self.current_token_range()?;
+            self.consume_specific(TokenKind::BraceClose)?;
             Some(else_body)
         } else {
             None
         };
 
-        Ok(Some(ParsedStatement::new(
-            ParsedStatementKind::If {
+        let kind = match binding {
+            Some((type_name, name)) => ParsedStatementKind::IfLet {
+                name,
+                type_name,
+                value: condition.expect("Condition should have been checked"),
+                then_body,
+                else_body,
+            },
+            None => ParsedStatementKind::If {
                 condition,
                 then_body,
                 else_body,
             },
+        };
+        Ok(Some(ParsedStatement::new(
+            kind,
             CodeRange::from_ranges(start, end),
         )))
     }
@@ -526,8 +1025,8 @@ impl<'source> Parser<'source> {
         self.consume_specific(TokenKind::Loop)?;
         self.consume_specific(TokenKind::BraceOpen)?;
         let body = self.parse_statement_list()?;
-        self.consume_specific(TokenKind::BraceClose)?;
         let end = self.current_token_range()?;
+        self.consume_specific(TokenKind::BraceClose)?;
         Ok(Some(ParsedStatement::new(
             ParsedStatementKind::Loop { body },
             CodeRange::from_ranges(start?, end),
@@ -538,10 +1037,11 @@ impl<'source> Parser<'source> {
         let start = self.current_token_range()?;
         self.consume_specific(TokenKind::While)?;
         let condition = self.parse_expression()?;
+        self.check_assignment_in_condition()?;
         self.consume_specific(TokenKind::BraceOpen)?;
         let body = self.parse_statement_list()?;
-        self.consume_specific(TokenKind::BraceClose)?;
         let end = self.current_token_range()?;
+        self.consume_specific(TokenKind::BraceClose)?;
         Ok(Some(ParsedStatement::new(
             ParsedStatementKind::While {
                 condition,
@@ -551,6 +1051,53 @@ impl<'source> Parser<'source> {
         )))
     }
 
+    /// A `=` after a condition, as in `if x = 5 {}`, was almost certainly
+    /// meant to be `==`.
+    fn check_assignment_in_condition(&self) -> ParserResult<()> {
+        if self.peek_kind()? == TokenKind::Equals {
+            return Err(ParserError::new(
+                ParserErrorKind::AssignmentInCondition,
+                self.peek()?.range(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses `break;` or `continue;`, whichever `kind` is.
+    fn parse_loop_control_statement(
+        &mut self,
+        kind: ParsedStatementKind,
+    ) -> ParserResult<Option<ParsedStatement>> {
+        let start = self.current_token_range()?;
+        self.consume()?;
+        self.consume_specific(TokenKind::Semicolon)?;
+        let end = self.previous_token_range()?;
+        Ok(Some(ParsedStatement::new(
+            kind,
+            CodeRange::from_ranges(start, end),
+        )))
+    }
+
+    fn parse_defer_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
+        let start = self.current_token_range()?;
+        self.consume_specific(TokenKind::Defer)?;
+        let Some(statement) = self.parse_statement()? else {
+            return Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            ));
+        };
+        let end = self.previous_token_range()?;
+        Ok(Some(ParsedStatement::new(
+            ParsedStatementKind::Defer {
+                statement: Box::new(statement),
+            },
+            CodeRange::from_ranges(start, end),
+        )))
+    }
+
     fn parse_variable_assignment_statement(&mut self) -> ParserResult<Option<ParsedStatement>> {
         let start = self.current_token_range()?;
         let name = self.parse_identifier()?;
@@ -617,13 +1164,69 @@ impl<'source> Parser<'source> {
         | TokenKind::GreaterThan
         | TokenKind::GreaterThanEquals
         | TokenKind::AmpersandAmpersand
-        | TokenKind::PipePipe) = self.peek_kind()?
+        | TokenKind::PipePipe
+        | TokenKind::QuestionMark) = self.peek_kind()?
         {
+            if op == TokenKind::QuestionMark {
+                if TERNARY_BINDING_POWER < min_binding_power {
+                    break;
+                }
+
+                self.consume_specific(op)?;
+                let Some(then_branch) = self.parse_expression()? else {
+                    return Err(ParserError::new(
+                        ParserErrorKind::ExpectedExpression {
+                            found: self.peek_kind()?,
+                        },
+                        self.peek()?.range(),
+                    ));
+                };
+                self.consume_specific(TokenKind::Colon)?;
+                // Parsing the else branch at the same binding power makes
+                // `a ? b : c ? d : e` group as `a ? b : (c ? d : e)`.
+                let Some(else_branch) = self.parse_pratt_expression(TERNARY_BINDING_POWER)? else {
+                    return Err(ParserError::new(
+                        ParserErrorKind::ExpectedExpression {
+                            found: self.peek_kind()?,
+                        },
+                        self.peek()?.range(),
+                    ));
+                };
+                let end = *else_branch.range();
+                lhs = Some(ParsedExpression::new(
+                    ParsedExpressionKind::Ternary {
+                        condition: Box::new(lhs.unwrap()),
+                        then_branch: Box::new(then_branch),
+                        else_branch: Box::new(else_branch),
+                    },
+                    CodeRange::from_ranges(start, end),
+                ));
+                continue;
+            }
+
             if let Some((left_binding_power, right_binding_power)) = infix_binding_power(op) {
                 if left_binding_power < min_binding_power {
                     break;
                 }
 
+                // `.name` without a call reads a field of the value.
+                if op == TokenKind::Period
+                    && self.peek_kind_at(1)? == TokenKind::Identifier
+                    && self.peek_kind_at(2)? != TokenKind::ParenOpen
+                {
+                    self.consume_specific(op)?;
+                    let member = self.parse_identifier()?;
+                    let end = member.token().range();
+                    lhs = Some(ParsedExpression::new(
+                        ParsedExpressionKind::MemberAccess {
+                            expression: Box::new(lhs.unwrap()),
+                            member,
+                        },
+                        CodeRange::from_ranges(start, end),
+                    ));
+                    continue;
+                }
+
                 if op == TokenKind::Period {
                     self.consume_specific(op)?;
                     let end = self.current_token_range()?;
@@ -664,7 +1267,8 @@ impl<'source> Parser<'source> {
             TokenKind::IntLiteral
             | TokenKind::FloatLiteral
             | TokenKind::StringLiteral
-            | TokenKind::BoolLiteral => self.parse_literal_expression(),
+            | TokenKind::BoolLiteral
+            | TokenKind::NoneLiteral => self.parse_literal_expression(),
             TokenKind::Identifier => match self.peek_kind_at(1) {
                 Ok(TokenKind::ParenOpen) => self.parse_function_call_expression(),
                 Err(_) => Ok(None),
@@ -673,11 +1277,40 @@ impl<'source> Parser<'source> {
             TokenKind::Plus | TokenKind::Minus | TokenKind::ExclamationMark => {
                 self.parse_prefix_operator_expression()
             }
+            TokenKind::Match => self.parse_match_expression(),
             TokenKind::ParenOpen => {
                 self.consume_specific(TokenKind::ParenOpen)?;
-                let expr = self.parse_pratt_expression(0);
-                self.consume_specific(TokenKind::ParenClose)?;
-                expr
+                let expr = self.parse_pratt_expression(0)?;
+                if !self.consume_if(TokenKind::Comma) {
+                    self.consume_specific(TokenKind::ParenClose)?;
+                    return Ok(expr.map(|expr| ParsedExpression {
+                        parenthesized: true,
+                        ..expr
+                    }));
+                }
+
+                // A comma after the first element makes it a tuple.
+                let mut elements = expr.into_iter().collect::<Vec<_>>();
+                while !self.consume_if(TokenKind::ParenClose) {
+                    let Some(element) = self.parse_expression()? else {
+                        return Err(ParserError::new(
+                            ParserErrorKind::ExpectedExpression {
+                                found: self.peek_kind()?,
+                            },
+                            self.peek()?.range(),
+                        ));
+                    };
+                    elements.push(element);
+                    if !self.consume_if(TokenKind::Comma) {
+                        self.consume_specific(TokenKind::ParenClose)?;
+                        break;
+                    }
+                }
+                let end = self.previous_token_range()?;
+                Ok(Some(ParsedExpression::new(
+                    ParsedExpressionKind::Tuple(elements),
+                    CodeRange::from_ranges(range, end),
+                )))
             }
             invalid_kind => Err(ParserError::new(
                 ParserErrorKind::InvalidExpressionStart {
@@ -688,6 +1321,83 @@ impl<'source> Parser<'source> {
         }
     }
 
+    fn parse_match_expression(&mut self) -> ParserResult<Option<ParsedExpression>> {
+        let start = self.current_token_range()?;
+        self.consume_specific(TokenKind::Match)?;
+        let Some(value) = self.parse_expression()? else {
+            return Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            ));
+        };
+
+        self.consume_specific(TokenKind::BraceOpen)?;
+        let mut arms = vec![];
+        // Checking for `}` before each item also accepts a trailing comma.
+        while self.peek_kind()? != TokenKind::BraceClose {
+            let pattern = self.parse_pattern()?;
+            let mut guard = None;
+            if self.consume_if(TokenKind::If) {
+                let Some(condition) = self.parse_expression()? else {
+                    return Err(ParserError::new(
+                        ParserErrorKind::ExpectedExpression {
+                            found: self.peek_kind()?,
+                        },
+                        self.peek()?.range(),
+                    ));
+                };
+                guard = Some(condition);
+            }
+            self.consume_specific(TokenKind::FatArrow)?;
+            let Some(arm_value) = self.parse_expression()? else {
+                return Err(ParserError::new(
+                    ParserErrorKind::ExpectedExpression {
+                        found: self.peek_kind()?,
+                    },
+                    self.peek()?.range(),
+                ));
+            };
+            arms.push(ParsedMatchArm {
+                pattern,
+                guard,
+                value: arm_value,
+            });
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+        let end = self.current_token_range()?;
+        self.consume_specific(TokenKind::BraceClose)?;
+
+        Ok(Some(ParsedExpression::new(
+            ParsedExpressionKind::Match {
+                value: Box::new(value),
+                arms,
+            },
+            CodeRange::from_ranges(start, end),
+        )))
+    }
+
+    fn parse_pattern(&mut self) -> ParserResult<ParsedPattern> {
+        // A name on its own binds the value, while `Status.Ok` is a variant.
+        if self.peek_kind()? == TokenKind::Identifier && self.peek_kind_at(1)? != TokenKind::Period
+        {
+            return Ok(ParsedPattern::Binding(self.parse_identifier()?));
+        }
+
+        match self.parse_primary_expression()? {
+            Some(value) => Ok(ParsedPattern::Value(value)),
+            None => Err(ParserError::new(
+                ParserErrorKind::ExpectedExpression {
+                    found: self.peek_kind()?,
+                },
+                self.peek()?.range(),
+            )),
+        }
+    }
+
     fn parse_prefix_operator_expression(&mut self) -> ParserResult<Option<ParsedExpression>> {
         let token = self.consume()?;
         match token.kind() {
@@ -752,6 +1462,10 @@ impl<'source> Parser<'source> {
                 let value = string_value_text.parse::<bool>().unwrap();
                 Value::Boolean(value)
             }
+            TokenKind::NoneLiteral => {
+                self.consume_specific(TokenKind::NoneLiteral)?;
+                Value::None
+            }
             _ => return Ok(None),
         };
 
@@ -773,8 +1487,18 @@ impl<'source> Parser<'source> {
 
     fn parse_function_call(&mut self) -> ParserResult<ParsedFunctionCall> {
         let name = self.parse_identifier()?;
-        self.consume_specific(TokenKind::ParenOpen)?;
+        let paren_open = self.consume_specific(TokenKind::ParenOpen)?;
         let arguments = self.parse_function_arguments()?;
+        // Running out of input means the `(` was never closed, which is
+        // clearer to point out than the end of the file.
+        if matches!(self.peek_kind(), Ok(TokenKind::EndOfFile) | Err(_)) {
+            return Err(ParserError::new(
+                ParserErrorKind::UnclosedCall {
+                    name: name.name().to_string(),
+                },
+                paren_open.range(),
+            ));
+        }
         self.consume_specific(TokenKind::ParenClose)?;
         Ok(ParsedFunctionCall { name, arguments })
     }
@@ -782,15 +1506,77 @@ impl<'source> Parser<'source> {
     fn parse_identifier_expression(&mut self) -> ParserResult<Option<ParsedExpression>> {
         let ident = self.parse_identifier()?;
         let range = ident.token().range();
+        let variable = ParsedExpression::new(ParsedExpressionKind::Variable(ident), range);
         match self.peek_kind()? {
             TokenKind::ParenOpen => self.parse_function_call_expression(),
-            _ => Ok(Some(ParsedExpression::new(
-                ParsedExpressionKind::Variable(ident),
-                range,
-            ))),
+            // Unlike a method call, `Status.NotFound` binds tighter than any
+            // operator, so that it can be used as an operand.
+            TokenKind::Period
+                if self.peek_kind_at(1)? == TokenKind::Identifier
+                    && self.peek_kind_at(2)? != TokenKind::ParenOpen =>
+            {
+                self.consume_specific(TokenKind::Period)?;
+                let member = self.parse_identifier()?;
+                let end = member.token().range();
+                Ok(Some(ParsedExpression::new(
+                    ParsedExpressionKind::MemberAccess {
+                        expression: Box::new(variable),
+                        member,
+                    },
+                    CodeRange::from_ranges(range, end),
+                )))
+            }
+            // Requiring `name:` after the `{` tells a struct apart from a
+            // block, as in `if done { ... }`.
+            TokenKind::BraceOpen
+                if self.peek_kind_at(1)? == TokenKind::Identifier
+                    && self.peek_kind_at(2)? == TokenKind::Colon =>
+            {
+                let ParsedExpressionKind::Variable(name) = variable.kind else {
+                    unreachable!()
+                };
+                self.parse_struct_expression(name)
+            }
+            _ => Ok(Some(variable)),
         }
     }
 
+    fn parse_struct_expression(
+        &mut self,
+        name: Identifier,
+    ) -> ParserResult<Option<ParsedExpression>> {
+        let start = name.token().range();
+        self.consume_specific(TokenKind::BraceOpen)?;
+        let mut fields = vec![];
+        // Checking for `}` before each item also accepts a trailing comma.
+        while self.peek_kind()? != TokenKind::BraceClose {
+            let field_name = self.parse_identifier()?;
+            self.consume_specific(TokenKind::Colon)?;
+            let Some(value) = self.parse_expression()? else {
+                return Err(ParserError::new(
+                    ParserErrorKind::ExpectedExpression {
+                        found: self.peek_kind()?,
+                    },
+                    self.peek()?.range(),
+                ));
+            };
+            fields.push(ParsedFieldValue {
+                name: field_name,
+                value,
+            });
+            if !self.consume_if(TokenKind::Comma) {
+                break;
+            }
+        }
+        let end = self.current_token_range()?;
+        self.consume_specific(TokenKind::BraceClose)?;
+
+        Ok(Some(ParsedExpression::new(
+            ParsedExpressionKind::Struct { name, fields },
+            CodeRange::from_ranges(start, end),
+        )))
+    }
+
     fn parse_identifier(&mut self) -> ParserResult<Identifier> {
         let ident = self.consume_specific(TokenKind::Identifier)?;
         let name = self.text(&ident);
@@ -798,12 +1584,58 @@ impl<'source> Parser<'source> {
     }
 
     fn parse_type_name(&mut self) -> ParserResult<TypeName> {
-        let type_ident = self.consume_specific(TokenKind::Identifier)?;
-        let name = self.text(&type_ident);
-        Ok(TypeName {
-            name,
-            token: type_ident,
-        })
+        // Array, optional, tuple and function types keep their full spelling
+        // in the name, as in `[string]`, `int?`, `(int, int)` or
+        // `fn(int, int) -> bool`.
+        if self.peek_kind()? == TokenKind::Fn {
+            return self.parse_function_type_name();
+        }
+
+        let (mut name, token) = match self.peek_kind()? {
+            TokenKind::SquareOpen => {
+                self.consume_specific(TokenKind::SquareOpen)?;
+                let element = self.parse_type_name()?;
+                self.consume_specific(TokenKind::SquareClose)?;
+                (format!("[{}]", element.name), element.token)
+            }
+            TokenKind::ParenOpen => {
+                let token = self.consume_specific(TokenKind::ParenOpen)?;
+                let mut elements = vec![];
+                loop {
+                    elements.push(self.parse_type_name()?.name);
+                    if !self.consume_if(TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.consume_specific(TokenKind::ParenClose)?;
+                (format!("({})", elements.join(", ")), token)
+            }
+            _ => {
+                let type_ident = self.consume_specific(TokenKind::Identifier)?;
+                (self.text(&type_ident), type_ident)
+            }
+        };
+        if self.consume_if(TokenKind::QuestionMark) {
+            name.push('?');
+        }
+        Ok(TypeName { name, token })
+    }
+
+    fn parse_function_type_name(&mut self) -> ParserResult<TypeName> {
+        let token = self.consume_specific(TokenKind::Fn)?;
+        self.consume_specific(TokenKind::ParenOpen)?;
+        let mut parameters = vec![];
+        while !self.consume_if(TokenKind::ParenClose) {
+            parameters.push(self.parse_type_name()?.name);
+            if !self.consume_if(TokenKind::Comma) {
+                self.consume_specific(TokenKind::ParenClose)?;
+                break;
+            }
+        }
+        self.consume_specific(TokenKind::Arrow)?;
+        let return_type = self.parse_type_name()?;
+        let name = format!("fn({}) -> {}", parameters.join(", "), return_type.name);
+        Ok(TypeName { name, token })
     }
 
     fn current_token_range(&self) -> ParserResult<CodeRange> {
@@ -893,7 +1725,22 @@ pub fn preprocess_tokens(tokens: &mut Vec<Token>) {
     tokens.retain(|token| !token.is(TokenKind::Whitespace) && !token.is(TokenKind::Comment));
 }
 
-fn infix_binding_power(op: TokenKind) -> Option<(u8, u8)> {
+/// Binds looser than every infix operator except `.`.
+pub(crate) const TERNARY_BINDING_POWER: u8 = 1;
+
+pub(crate) fn is_comparison_operator(operator: TokenKind) -> bool {
+    matches!(
+        operator,
+        TokenKind::EqualsEquals
+            | TokenKind::ExclamationMarkEquals
+            | TokenKind::LessThan
+            | TokenKind::LessThanEquals
+            | TokenKind::GreaterThan
+            | TokenKind::GreaterThanEquals
+    )
+}
+
+pub(crate) fn infix_binding_power(op: TokenKind) -> Option<(u8, u8)> {
     match op {
         TokenKind::Period => Some((0, 1)),
         TokenKind::PipePipe => Some((2, 3)),
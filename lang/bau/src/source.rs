@@ -1,39 +1,112 @@
+use std::borrow::Cow;
+
+use crate::tokenizer::{Token, Tokenizer};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Source<'text> {
-    text: &'text str,
-    lines: Vec<&'text str>,
+    text: Cow<'text, str>,
+    origin: SourceOrigin,
 }
 
 impl<'text> Source<'text> {
     pub fn new(text: &'text str) -> Self {
-        let lines = text.lines().collect();
-        Self { text, lines }
+        Self {
+            text: Cow::Borrowed(text),
+            origin: SourceOrigin::Program,
+        }
+    }
+
+    /// Marks the ranges parsed from this source as coming from `origin`.
+    pub fn with_origin(mut self, origin: SourceOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Reads the source from the file at `path`.
+    pub fn from_file(path: &str) -> std::io::Result<Source<'static>> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Source {
+            text: Cow::Owned(text),
+            origin: SourceOrigin::Program,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
-    pub fn text(&self) -> &'text str {
-        self.text
+    pub fn origin(&self) -> SourceOrigin {
+        self.origin
     }
 
-    pub fn lines(&self) -> &[&'text str] {
-        &self.lines
+    pub fn lines(&self) -> Vec<&str> {
+        self.text.lines().collect()
     }
+
+    /// Tokenizes the source, pairing every token with the text it covers.
+    pub fn tokens(&self) -> Vec<(Token, &str)> {
+        Tokenizer::new(self.text())
+            .with_origin(self.origin)
+            .tokenize()
+            .into_iter()
+            .map(|token| {
+                let span = token.range().span;
+                let text = self.text.get(span.start..span.end).unwrap_or_default();
+                (token, text)
+            })
+            .collect()
+    }
+
+    /// Returns the range between the byte offsets `start` and `end`, with its
+    /// coordinates counted the same way the tokenizer counts them.
+    ///
+    /// Panics if `start` is not a char boundary within the text.
+    pub fn range_for(&self, start: usize, end: usize) -> CodeRange {
+        let before = &self.text[..start];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let column = before[line_start..].chars().count();
+        CodeRange::new(Span::new(start, end), SourceCoords::new(line, column))
+            .with_origin(self.origin)
+    }
+}
+
+/// The source a range points into. The prelude is run together with the
+/// program, so its ranges have to be told apart to be shown against the
+/// right text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SourceOrigin {
+    #[default]
+    Program,
+    Prelude,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct CodeRange {
     pub span: Span,
     pub coords: SourceCoords,
+    pub origin: SourceOrigin,
 }
 
 impl CodeRange {
     pub fn new(span: Span, coords: SourceCoords) -> Self {
-        Self { span, coords }
+        Self {
+            span,
+            coords,
+            origin: SourceOrigin::Program,
+        }
+    }
+
+    pub fn with_origin(mut self, origin: SourceOrigin) -> Self {
+        self.origin = origin;
+        self
     }
 
     pub fn from_ranges(start: CodeRange, end: CodeRange) -> Self {
         Self {
             span: Span::new(start.span.start, end.span.end),
             coords: start.coords,
+            origin: start.origin,
         }
     }
 }
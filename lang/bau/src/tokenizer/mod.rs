@@ -1,13 +1,13 @@
 pub use token::Token;
 
-use crate::source::{CodeRange, SourceCoords, Span};
+use crate::source::{CodeRange, SourceCoords, SourceOrigin, Span};
 
 use self::token::TokenKind;
 
 mod rule;
 pub mod token;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Tokenizer<'input> {
     input: &'input str,
     cursor: usize,
@@ -15,6 +15,7 @@ pub struct Tokenizer<'input> {
     column: usize,
     eof: bool,
     rules: Vec<rule::Rule>,
+    origin: SourceOrigin,
 }
 
 impl<'input> Tokenizer<'input> {
@@ -26,72 +27,88 @@ impl<'input> Tokenizer<'input> {
             column: 0,
             eof: false,
             rules: rule::get_rules(),
+            origin: SourceOrigin::Program,
         }
     }
 
+    /// Marks the ranges of the tokens as coming from `origin`.
+    pub fn with_origin(mut self, origin: SourceOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
     pub fn tokenize(&mut self) -> Vec<Token> {
         self.collect()
     }
 
     fn next_token(&mut self, input: &str) -> Token {
-        self.consume_token(input)
-            .unwrap_or_else(|| self.invalid_token(input))
+        match self.match_token(input) {
+            Some((kind, len)) => self.token(kind, len),
+            None => self.invalid_token(input),
+        }
     }
 
-    fn consume_token(&mut self, input: &str) -> Option<Token> {
+    /// Returns the kind and byte length of the token at the start of `input`.
+    /// A rule that matches nothing is treated as not matching at all, so every
+    /// token advances the cursor.
+    fn match_token(&self, input: &str) -> Option<(TokenKind, usize)> {
+        let (kind, len) = self.match_rules(input)?;
+        debug_assert!(len > 0, "Rule for {:?} matched zero characters", kind);
+        (len > 0).then_some((kind, len))
+    }
+
+    fn match_rules(&self, input: &str) -> Option<(TokenKind, usize)> {
         if input.starts_with("//") {
-            let len = input
-                .char_indices()
-                .take_while(|(_, c)| *c != '\n')
-                .last()
-                .expect("At least one char should exist")
-                .0
-                + 1;
-            return Some(self.token(TokenKind::Comment, len));
+            let line = &input[..input.find('\n').unwrap_or(input.len())];
+            // Leave the `\r` of a `\r\n` line ending to the whitespace token.
+            let len = line.strip_suffix('\r').unwrap_or(line).len();
+            return Some((TokenKind::Comment, len));
+        }
+
+        if input.starts_with("/*") {
+            // An unterminated block comment swallows the rest of the input.
+            return Some(match block_comment_len(input) {
+                Some(len) => (TokenKind::Comment, len),
+                None => (TokenKind::Invalid, input.len()),
+            });
         }
 
         let next = input.chars().next().unwrap();
         match next {
             char if char.is_whitespace() => {
                 let len = input
-                    .char_indices()
-                    .take_while(|(_, c)| c.is_whitespace())
-                    .last()
-                    .expect("At least one whitespace char should exist")
-                    .0
-                    + 1;
-                Some(self.token(TokenKind::Whitespace, len))
+                    .find(|c: char| !c.is_whitespace())
+                    .unwrap_or(input.len());
+                Some((TokenKind::Whitespace, len))
             }
             char => {
                 if let Some(kind) = rule::get_unambiguous_token(char) {
-                    return Some(self.token(kind, 1));
+                    return Some((kind, 1));
                 }
 
-                let (len, kind) = self
-                    .rules
+                self.rules
                     .iter()
                     // `max_by_key` returns the last element if multiple
                     // rules match, but we want earlier rules to "win"
                     // against later ones
                     .rev()
                     .filter_map(|rule| Some(((rule.matches)(input)?, rule.kind)))
-                    .max_by_key(|&(len, _)| len)?;
-
-                Some(self.token(kind, len))
+                    .max_by_key(|&(len, _)| len)
+                    .map(|(len, kind)| (kind, len))
             }
         }
     }
 
     fn invalid_token(&mut self, input: &str) -> Token {
-        let start = self.cursor;
+        // The first char never matches, so the token is at least one char long.
         let len = input
             .char_indices()
-            .find(|(pos, _)| self.consume_token(&input[*pos..]).is_some())
+            .skip(1)
+            .find(|(pos, _)| self.match_token(&input[*pos..]).is_some())
             .map(|(pos, _)| pos)
             .unwrap_or_else(|| input.len());
-        debug_assert!(len <= input.len());
+        debug_assert!(len > 0 && len <= input.len());
 
-        self.cursor = start + len;
         self.token(TokenKind::Invalid, len)
     }
 
@@ -101,7 +118,8 @@ impl<'input> Tokenizer<'input> {
             CodeRange::new(
                 Span::new(self.cursor, self.cursor + len),
                 SourceCoords::new(self.line, self.column),
-            ),
+            )
+            .with_origin(self.origin),
         );
         assert!(
             self.cursor + len <= self.input.len(),
@@ -120,6 +138,29 @@ impl<'input> Tokenizer<'input> {
     }
 }
 
+/// Returns the length of the block comment at the start of `input`, including
+/// any nested block comments, or `None` if it is never closed.
+fn block_comment_len(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut cursor = 0;
+    while cursor < input.len() {
+        let rest = &input[cursor..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            cursor += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            cursor += 2;
+            if depth == 0 {
+                return Some(cursor);
+            }
+        } else {
+            cursor += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    None
+}
+
 impl<'input> Iterator for Tokenizer<'input> {
     type Item = Token;
 
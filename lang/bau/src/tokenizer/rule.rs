@@ -2,7 +2,7 @@ use crate::tokenizer::token::TokenKind;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct Rule {
     pub kind: TokenKind,
     pub matches: fn(&str) -> Option<usize>,
@@ -38,6 +38,7 @@ lazy_static! {
     static ref FLOAT_REGEX: Regex =
         Regex::new(r"^((\d+(\.\d+)?)|(\.\d+))([Ee](\+|-)?\d+)?").unwrap();
     static ref BOOL_REGEX: Regex = Regex::new(r"^\b(?:true|false)\b").unwrap();
+    static ref NONE_REGEX: Regex = Regex::new(r"^\bnone\b").unwrap();
     static ref IDENTIFIER_REGEX: Regex = Regex::new(r"^([A-Za-z]|_)([A-Za-z]|_|\d)*").unwrap();
 }
 
@@ -71,6 +72,15 @@ pub(crate) fn get_rules() -> Vec<Rule> {
         };
     }
 
+    macro_rules! three_chars {
+        ($token:expr) => {
+            Rule {
+                kind: $token,
+                matches: |input| input.starts_with($token.to_string().as_str()).then_some(3),
+            }
+        };
+    }
+
     macro_rules! keyword {
         ($token:expr) => {
             Rule {
@@ -107,14 +117,22 @@ pub(crate) fn get_rules() -> Vec<Rule> {
         two_chars!(TokenKind::LessThanEquals),
         two_chars!(TokenKind::GreaterThanEquals),
         two_chars!(TokenKind::Arrow),
+        two_chars!(TokenKind::FatArrow),
         two_chars!(TokenKind::PlusEquals),
         two_chars!(TokenKind::MinusEquals),
         two_chars!(TokenKind::AsteriskEquals),
         two_chars!(TokenKind::SlashEquals),
         two_chars!(TokenKind::PercentEquals),
+        three_chars!(TokenKind::AmpersandAmpersandEquals),
+        three_chars!(TokenKind::PipePipeEquals),
         keyword!(TokenKind::Extend),
+        keyword!(TokenKind::Trait),
+        keyword!(TokenKind::Enum),
+        keyword!(TokenKind::Struct),
         keyword!(TokenKind::Fn),
         keyword!(TokenKind::Let),
+        keyword!(TokenKind::Mut),
+        keyword!(TokenKind::Const),
         keyword!(TokenKind::If),
         keyword!(TokenKind::Else),
         keyword!(TokenKind::Loop),
@@ -122,10 +140,13 @@ pub(crate) fn get_rules() -> Vec<Rule> {
         keyword!(TokenKind::Return),
         keyword!(TokenKind::Continue),
         keyword!(TokenKind::Break),
+        keyword!(TokenKind::Defer),
+        keyword!(TokenKind::Match),
         regex!(TokenKind::StringLiteral, &STRING_REGEX),
         regex!(TokenKind::IntLiteral, &INT_REGEX),
         regex!(TokenKind::FloatLiteral, &FLOAT_REGEX),
         regex!(TokenKind::BoolLiteral, &BOOL_REGEX),
+        regex!(TokenKind::NoneLiteral, &NONE_REGEX),
         regex!(TokenKind::Identifier, &IDENTIFIER_REGEX),
     ]
 }
@@ -141,6 +162,9 @@ pub(crate) fn get_unambiguous_token(char: char) -> Option<TokenKind> {
         ';' => Some(TokenKind::Semicolon),
         '.' => Some(TokenKind::Period),
         ',' => Some(TokenKind::Comma),
+        ':' => Some(TokenKind::Colon),
+        '?' => Some(TokenKind::QuestionMark),
+        '@' => Some(TokenKind::At),
         _ => None,
     }
 }
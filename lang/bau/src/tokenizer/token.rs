@@ -5,7 +5,12 @@ pub enum TokenKind {
     // Keywords
     Fn,
     Extend,
+    Trait,
+    Enum,
+    Struct,
     Let,
+    Mut,
+    Const,
     If,
     Else,
     Loop,
@@ -13,12 +18,15 @@ pub enum TokenKind {
     Return,
     Continue,
     Break,
+    Defer,
+    Match,
 
     // Literals
     StringLiteral,
     IntLiteral,
     FloatLiteral,
     BoolLiteral,
+    NoneLiteral,
 
     // Identifiers
     Identifier,
@@ -39,6 +47,8 @@ pub enum TokenKind {
     AsteriskEquals,
     SlashEquals,
     PercentEquals,
+    AmpersandAmpersandEquals,
+    PipePipeEquals,
 
     EqualsEquals,
     ExclamationMarkEquals,
@@ -50,6 +60,7 @@ pub enum TokenKind {
     // Punctuation
     Equals,
     Arrow,
+    FatArrow,
     ParenOpen,
     ParenClose,
     BraceOpen,
@@ -59,6 +70,9 @@ pub enum TokenKind {
     Semicolon,
     Period,
     Comma,
+    Colon,
+    QuestionMark,
+    At,
 
     // Misc
     Comment,
@@ -72,7 +86,12 @@ impl std::fmt::Display for TokenKind {
         let str = match self {
             Self::Fn => "fn".to_string(),
             Self::Extend => "extend".to_string(),
+            Self::Trait => "trait".to_string(),
+            Self::Enum => "enum".to_string(),
+            Self::Struct => "struct".to_string(),
             Self::Let => "let".to_string(),
+            Self::Mut => "mut".to_string(),
+            Self::Const => "const".to_string(),
             Self::If => "if".to_string(),
             Self::Else => "else".to_string(),
             Self::Loop => "loop".to_string(),
@@ -80,11 +99,14 @@ impl std::fmt::Display for TokenKind {
             Self::Return => "return".to_string(),
             Self::Continue => "continue".to_string(),
             Self::Break => "break".to_string(),
+            Self::Defer => "defer".to_string(),
+            Self::Match => "match".to_string(),
 
             Self::StringLiteral => "string literal".to_string(),
             Self::IntLiteral => "integer literal".to_string(),
             Self::FloatLiteral => "float literal".to_string(),
             Self::BoolLiteral => "bool literal".to_string(),
+            Self::NoneLiteral => "none".to_string(),
 
             Self::Identifier => "identifier".to_string(),
 
@@ -102,6 +124,8 @@ impl std::fmt::Display for TokenKind {
             Self::AsteriskEquals => "*=".to_string(),
             Self::SlashEquals => "/=".to_string(),
             Self::PercentEquals => "%=".to_string(),
+            Self::AmpersandAmpersandEquals => "&&=".to_string(),
+            Self::PipePipeEquals => "||=".to_string(),
 
             Self::EqualsEquals => "==".to_string(),
             Self::ExclamationMarkEquals => "!=".to_string(),
@@ -112,6 +136,7 @@ impl std::fmt::Display for TokenKind {
 
             Self::Equals => "=".to_string(),
             Self::Arrow => "->".to_string(),
+            Self::FatArrow => "=>".to_string(),
             Self::ParenOpen => "(".to_string(),
             Self::ParenClose => ")".to_string(),
             Self::BraceOpen => "{".to_string(),
@@ -121,6 +146,9 @@ impl std::fmt::Display for TokenKind {
             Self::Semicolon => ";".to_string(),
             Self::Period => ".".to_string(),
             Self::Comma => ",".to_string(),
+            Self::Colon => ":".to_string(),
+            Self::QuestionMark => "?".to_string(),
+            Self::At => "@".to_string(),
 
             Self::Comment => "comment".to_string(),
             Self::Whitespace => "whitespace".to_string(),
@@ -163,3 +191,17 @@ impl Token {
         self.kind == kind
     }
 }
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} {}..{} {}:{}",
+            self.kind,
+            self.range.span.start,
+            self.range.span.end,
+            self.range.coords.line,
+            self.range.coords.column
+        )
+    }
+}
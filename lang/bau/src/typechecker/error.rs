@@ -19,6 +19,17 @@ pub enum TypecheckerErrorKind {
     VariableNotDefined {
         name: String,
     },
+    DiscardRead,
+    ConstantAssignment {
+        name: String,
+    },
+    AssignmentToImmutable {
+        name: String,
+    },
+    NonConstantExpression,
+    ConstantCycle {
+        name: String,
+    },
     FunctionNotDefined {
         name: String,
     },
@@ -29,27 +40,118 @@ pub enum TypecheckerErrorKind {
         operator: TokenKind,
         right: Type,
     },
+    UnsupportedInfixOperator {
+        operator: TokenKind,
+        type_: Type,
+    },
+    ChainedComparison {
+        first: TokenKind,
+        second: TokenKind,
+    },
     InvalidVoidExpression,
     MainFunctionNotDefined,
+    InvalidMainSignature,
+    InvalidMainReturnType {
+        found: Type,
+    },
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    InvalidDeferredStatement,
+    NonExhaustiveMatch,
+    InvalidPattern,
+    UnknownAttribute {
+        name: String,
+    },
+    InvalidAttributeArguments {
+        name: String,
+    },
     MethodNotDefined {
         type_: Type,
         method_name: String,
     },
-    ArgumentCountMismatch {
+    WrongArgumentCount {
         expected: usize,
-        actual: usize,
+        found: usize,
+    },
+    DuplicateParameter {
+        name: String,
+    },
+    TraitNotDefined {
+        name: String,
+    },
+    MissingTraitMethod {
+        trait_name: String,
+        method_name: String,
+    },
+    TraitMethodMismatch {
+        trait_name: String,
+        method_name: String,
+        /// The signature the trait declares for the method.
+        expected: String,
+    },
+    ExpectedOptional {
+        actual: Type,
+    },
+    NotCallable {
+        type_: Type,
+    },
+    TemporaryReceiver {
+        method_name: String,
+    },
+    TypeAlreadyDefined {
+        name: String,
+    },
+    FunctionAlreadyDefined {
+        name: String,
+    },
+    VariantAlreadyDefined {
+        name: String,
+    },
+    VariantNotDefined {
+        enum_name: String,
+        variant: String,
+    },
+    DuplicateDiscriminant {
+        variant: String,
+        /// The earlier variant with the same value.
+        other: String,
+        value: i64,
+    },
+    InvalidDiscriminant,
+    MemberNotDefined {
+        type_: Type,
+        member: String,
+    },
+    FieldAlreadyDefined {
+        name: String,
+    },
+    MissingField {
+        struct_name: String,
+        field: String,
     },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypecheckerError {
-    kind: TypecheckerErrorKind,
+    /// Boxed to keep errors, and the results that carry them, small.
+    kind: Box<TypecheckerErrorKind>,
     range: CodeRange,
 }
 
 impl TypecheckerError {
     pub fn new(kind: TypecheckerErrorKind, range: CodeRange) -> Self {
-        Self { kind, range }
+        Self {
+            kind: Box::new(kind),
+            range,
+        }
+    }
+
+    pub fn kind(&self) -> &TypecheckerErrorKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> &CodeRange {
+        &self.range
     }
 
     pub fn print(&self, source: &Source) {
@@ -61,7 +163,7 @@ impl std::error::Error for TypecheckerError {}
 
 impl std::fmt::Display for TypecheckerError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let str = match &self.kind {
+        let str = match self.kind.as_ref() {
             TypecheckerErrorKind::UnknownType { type_name } => {
                 format!("Unknown type `{}`", type_name)
             }
@@ -77,6 +179,22 @@ impl std::fmt::Display for TypecheckerError {
             TypecheckerErrorKind::VariableNotDefined { name } => {
                 format!("Variable `{}` is not defined", name)
             }
+            TypecheckerErrorKind::DiscardRead => "`_` is a discard and cannot be read".to_string(),
+            TypecheckerErrorKind::ConstantAssignment { name } => {
+                format!("Cannot assign to constant `{}`", name)
+            }
+            TypecheckerErrorKind::AssignmentToImmutable { name } => {
+                format!(
+                    "Cannot assign to immutable variable `{}`, declare it with `let mut` instead",
+                    name
+                )
+            }
+            TypecheckerErrorKind::NonConstantExpression => {
+                "Constant values can only contain literals and other constants".to_string()
+            }
+            TypecheckerErrorKind::ConstantCycle { name } => {
+                format!("The value of constant `{}` depends on itself", name)
+            }
             TypecheckerErrorKind::FunctionNotDefined { name } => {
                 format!("Function `{}` is not defined", name)
             }
@@ -88,43 +206,149 @@ impl std::fmt::Display for TypecheckerError {
                 left,
                 operator,
                 right,
-            } => match operator {
-                TokenKind::Plus | TokenKind::Minus | TokenKind::Asterisk | TokenKind::Slash => {
-                    format!("The `{}` operator can only be used between two floats or two ints, but found `{}` and `{}`", operator, left, right)
-                }
-                TokenKind::EqualsEquals
-                | TokenKind::ExclamationMarkEquals
-                | TokenKind::LessThan
-                | TokenKind::GreaterThan
-                | TokenKind::LessThanEquals
-                | TokenKind::GreaterThanEquals => {
-                    format!("The `{}` operator can only be used between two floats or two ints, but found `{}` and `{}`", operator, left, right)
-                }
-                _ => {
+            } => match (left, right) {
+                // Ints are never promoted to floats, or the other way around.
+                (Type::Integer, Type::Float) | (Type::Float, Type::Integer) => format!(
+                    "The `{}` operator cannot mix `{}` and `{}`, as ints and floats are not converted implicitly",
+                    operator, left, right
+                ),
+                (Type::Optional(_) | Type::None, _) | (_, Type::Optional(_) | Type::None) => {
                     format!(
-                        "Invalid types for `{}` operator: `{}` and `{}`",
+                        "The `{}` operator cannot be used on optionals, but found `{}` and `{}`",
                         operator, left, right
                     )
                 }
+                _ => format!(
+                    "Both sides of the `{}` operator must have the same type, but found `{}` and `{}`",
+                    operator, left, right
+                ),
             },
+            TypecheckerErrorKind::UnsupportedInfixOperator { operator, type_ } => {
+                format!("The `{}` operator cannot be used on `{}`", operator, type_)
+            }
+            TypecheckerErrorKind::ChainedComparison { first, second } => {
+                format!(
+                    "Comparison operators cannot be chained: write `a {} b && b {} c` instead",
+                    first, second
+                )
+            }
             TypecheckerErrorKind::InvalidVoidExpression => {
-                "Cannot use void expression in this context".to_string()
+                "Cannot use a `void` expression as a value".to_string()
             }
             TypecheckerErrorKind::MainFunctionNotDefined => {
                 "Main function is not defined".to_string()
             }
+            TypecheckerErrorKind::InvalidMainSignature => {
+                "Main function must take no parameters or a single `[string]` parameter".to_string()
+            }
+            TypecheckerErrorKind::InvalidMainReturnType { found } => {
+                format!(
+                    "Main function must return `int` or `void`, but returns `{}`",
+                    found
+                )
+            }
+            TypecheckerErrorKind::BreakOutsideLoop => {
+                "`break` can only be used inside a loop".to_string()
+            }
+            TypecheckerErrorKind::ContinueOutsideLoop => {
+                "`continue` can only be used inside a loop".to_string()
+            }
+            TypecheckerErrorKind::InvalidDeferredStatement => {
+                "A deferred statement cannot `return` or `defer` again".to_string()
+            }
+            TypecheckerErrorKind::NonExhaustiveMatch => {
+                "A `match` needs an arm without a guard that matches any value, like `_`"
+                    .to_string()
+            }
+            TypecheckerErrorKind::InvalidPattern => {
+                "Patterns can only be names, literals or enum variants".to_string()
+            }
+            TypecheckerErrorKind::UnknownAttribute { name } => {
+                format!("Unknown attribute `@{}`", name)
+            }
+            TypecheckerErrorKind::InvalidAttributeArguments { name } => {
+                format!("Invalid arguments for attribute `@{}`", name)
+            }
             TypecheckerErrorKind::MethodNotDefined { type_, method_name } => {
                 format!(
                     "Method `{}` is not defined for type `{}`",
                     method_name, type_
                 )
             }
-            TypecheckerErrorKind::ArgumentCountMismatch { expected, actual } => {
+            TypecheckerErrorKind::WrongArgumentCount { expected, found } => {
                 format!(
                     "Expected {} arguments, but found {} instead",
-                    expected, actual
+                    expected, found
+                )
+            }
+            TypecheckerErrorKind::DuplicateParameter { name } => {
+                format!("Parameter `{}` is already defined", name)
+            }
+            TypecheckerErrorKind::TraitNotDefined { name } => {
+                format!("Trait `{}` is not defined", name)
+            }
+            TypecheckerErrorKind::MissingTraitMethod {
+                trait_name,
+                method_name,
+            } => {
+                format!(
+                    "Method `{}` required by trait `{}` is not implemented",
+                    method_name, trait_name
+                )
+            }
+            TypecheckerErrorKind::TraitMethodMismatch {
+                trait_name,
+                method_name,
+                expected,
+            } => {
+                format!(
+                    "Method `{}` does not match trait `{}`, expected `{}`",
+                    method_name, trait_name, expected
                 )
             }
+            TypecheckerErrorKind::TemporaryReceiver { method_name } => format!(
+                "Method `{}` modifies its receiver, so it can only be called on a variable",
+                method_name
+            ),
+            TypecheckerErrorKind::NotCallable { type_ } => {
+                format!("A value of type `{}` cannot be called", type_)
+            }
+            TypecheckerErrorKind::ExpectedOptional { actual } => {
+                format!("Expected an optional type, but found `{}` instead", actual)
+            }
+            TypecheckerErrorKind::TypeAlreadyDefined { name } => {
+                format!("Type `{}` is already defined", name)
+            }
+            TypecheckerErrorKind::FunctionAlreadyDefined { name } => {
+                format!("Function `{}` is already defined", name)
+            }
+            TypecheckerErrorKind::VariantAlreadyDefined { name } => {
+                format!("Variant `{}` is already defined", name)
+            }
+            TypecheckerErrorKind::VariantNotDefined { enum_name, variant } => {
+                format!("Enum `{}` has no variant `{}`", enum_name, variant)
+            }
+            TypecheckerErrorKind::DuplicateDiscriminant {
+                variant,
+                other,
+                value,
+            } => format!(
+                "Variant `{}` has the value `{}`, which `{}` already has",
+                variant, value, other
+            ),
+            TypecheckerErrorKind::InvalidDiscriminant => {
+                "The value of an enum variant must be an integer literal that fits in an `int`"
+                    .to_string()
+            }
+            TypecheckerErrorKind::MemberNotDefined { type_, member } => {
+                format!("Type `{}` has no member `{}`", type_, member)
+            }
+            TypecheckerErrorKind::FieldAlreadyDefined { name } => {
+                format!("Field `{}` is already defined", name)
+            }
+            TypecheckerErrorKind::MissingField { struct_name, field } => {
+                format!("Struct `{}` is missing field `{}`", struct_name, field)
+            }
         };
 
         write!(f, "{}", str)
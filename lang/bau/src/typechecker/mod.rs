@@ -3,23 +3,32 @@ use std::collections::HashMap;
 use crate::interpreter::builtin;
 use crate::interpreter::value::Value;
 use crate::parser::{
-    AssignmentOperator, Identifier, ParsedExpression, ParsedExpressionKind, ParsedExtendItem,
-    ParsedFunctionParameter, ParsedItem, ParsedItemKind, ParsedStatement, ParsedStatementKind,
-    PrefixOperator, TypeName,
+    is_comparison_operator, AssignmentOperator, Identifier, ParsedAttribute, ParsedBinding,
+    ParsedConstItem, ParsedEnumItem, ParsedExpression, ParsedExpressionKind, ParsedExtendItem,
+    ParsedFunctionCall, ParsedFunctionItem, ParsedFunctionParameter, ParsedItem, ParsedItemKind,
+    ParsedMatchArm, ParsedPattern, ParsedStatement, ParsedStatementKind, ParsedStructItem,
+    ParsedTraitItem, PrefixOperator, TypeName,
 };
 
 use crate::source::{CodeRange, SourceCoords, Span};
 use crate::tokenizer::token::TokenKind;
 
 pub mod error;
+pub mod warning;
+
+/// The name of the discard binding, which can be assigned to but never read.
+pub const DISCARD_NAME: &str = "_";
 
 pub use error::TypecheckerError;
 use error::{TypecheckerErrorKind, TypecheckerResult};
+pub use warning::TypecheckerWarning;
+use warning::TypecheckerWarningKind;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CheckedItemKind {
     Function(CheckedFunctionItem),
     Extend(CheckedExtendItem),
+    Const(CheckedConstItem),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,6 +65,13 @@ pub struct CheckedExtendItem {
     pub methods: Vec<CheckedFunctionItem>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedConstItem {
+    pub name: String,
+    pub type_: Type,
+    pub value: CheckedExpression,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CheckedStatementKind {
     Let {
@@ -63,6 +79,11 @@ pub enum CheckedStatementKind {
         type_: Type,
         initial_value: CheckedExpression,
     },
+    /// Binds each element of a tuple. Elements bound to `_` are `None`.
+    DestructuringLet {
+        variables: Vec<Option<CheckedVariable>>,
+        initial_value: CheckedExpression,
+    },
     VariableAssignment {
         name: String,
         value: CheckedExpression,
@@ -76,6 +97,13 @@ pub enum CheckedStatementKind {
         then_body: Vec<CheckedStatement>,
         else_body: Option<Vec<CheckedStatement>>,
     },
+    IfLet {
+        name: String,
+        type_: Type,
+        value: CheckedExpression,
+        then_body: Vec<CheckedStatement>,
+        else_body: Option<Vec<CheckedStatement>>,
+    },
     Expression {
         expression: CheckedExpression,
     },
@@ -86,6 +114,14 @@ pub enum CheckedStatementKind {
         condition: CheckedExpression,
         block: Vec<CheckedStatement>,
     },
+    Break,
+    Continue,
+    /// A nested function, whose definition is named after the functions it is
+    /// nested in, like `main.square`.
+    FunctionDef(CheckedFunctionItem),
+    Defer {
+        statement: Box<CheckedStatement>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -122,10 +158,67 @@ pub enum CheckedExpressionKind {
         right: Box<CheckedExpression>,
     },
     MethodCall {
+        receiver: Box<CheckedExpression>,
         type_: Type,
         method_name: String,
         arguments: Vec<CheckedExpression>,
     },
+    /// A call through a variable that holds a function value.
+    VariableCall {
+        variable: CheckedVariable,
+        arguments: Vec<CheckedExpression>,
+    },
+    Ternary {
+        condition: Box<CheckedExpression>,
+        then_branch: Box<CheckedExpression>,
+        else_branch: Box<CheckedExpression>,
+    },
+    /// A variant such as `Status.NotFound`, which is represented by its value.
+    EnumVariant {
+        enum_name: String,
+        variant: String,
+        value: i64,
+    },
+    /// `Status.from_int(value)`, which is `none` unless `value` is one of the
+    /// `discriminants` of the enum.
+    EnumFromInt {
+        enum_name: String,
+        discriminants: Vec<i64>,
+        value: Box<CheckedExpression>,
+    },
+    Tuple(Vec<CheckedExpression>),
+    /// `Point { x: 1, y: 2 }`, with the fields in the order they are written.
+    Struct {
+        name: String,
+        fields: Vec<(String, CheckedExpression)>,
+    },
+    /// Reads `field` of a struct value.
+    FieldAccess {
+        expression: Box<CheckedExpression>,
+        field: String,
+    },
+    /// Evaluates to the value of the first arm that `value` matches.
+    Match {
+        value: Box<CheckedExpression>,
+        arms: Vec<CheckedMatchArm>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedMatchArm {
+    pub pattern: CheckedPattern,
+    pub guard: Option<CheckedExpression>,
+    pub value: CheckedExpression,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckedPattern {
+    /// `_`, which matches any value.
+    Wildcard,
+    /// Matches any value, which is bound to the variable in the arm.
+    Binding(CheckedVariable),
+    /// Matches values equal to a literal or enum variant.
+    Value(CheckedExpression),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -152,15 +245,44 @@ impl CheckedExpression {
 pub struct CheckedVariable {
     pub name: String,
     pub type_: Type,
+    /// Whether the variable can be assigned to. Only `let mut` variables and
+    /// parameters can be.
+    pub mutable: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Void,
     Integer,
     Float,
     String,
     Boolean,
+    /// A value of the inner type, or `none`.
+    Optional(Box<Type>),
+    /// The type of the `none` literal, which fits any optional type.
+    None,
+    Array(Box<Type>),
+    Function {
+        parameters: Vec<Type>,
+        return_type: Box<Type>,
+    },
+    /// An enum declared with `enum`, by name.
+    Enum(String),
+    /// A fixed number of values of the given types, as in `(int, string)`.
+    Tuple(Vec<Type>),
+    /// A struct declared with `struct`, by name.
+    Struct(String),
+}
+
+impl Type {
+    /// Whether a value of type `other` can be stored where `self` is expected.
+    /// Besides exact matches, an optional accepts `none` and its inner type.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match self {
+            Self::Optional(inner) => self == other || other == &Self::None || **inner == *other,
+            _ => self == other,
+        }
+    }
 }
 
 impl std::fmt::Display for Type {
@@ -171,6 +293,29 @@ impl std::fmt::Display for Type {
             Self::Float => "float",
             Self::String => "string",
             Self::Boolean => "bool",
+            Self::Optional(inner) => return write!(f, "{}?", inner),
+            Self::None => "none",
+            Self::Array(element) => return write!(f, "[{}]", element),
+            Self::Function {
+                parameters,
+                return_type,
+            } => {
+                let parameters = parameters
+                    .iter()
+                    .map(|parameter| parameter.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return write!(f, "fn({}) -> {}", parameters, return_type);
+            }
+            Self::Enum(name) | Self::Struct(name) => name,
+            Self::Tuple(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return write!(f, "({})", elements);
+            }
         };
 
         write!(f, "{}", str)
@@ -182,19 +327,104 @@ pub struct CheckedFunctionDefinition {
     pub name: String,
     pub parameters: Vec<CheckedFunctionParameter>,
     pub return_type: Type,
+    /// The message of the `@deprecated` attribute, if the function has one.
+    pub deprecation: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl CheckedFunctionDefinition {
+    /// The definition of a function named `name` with the parameter and return
+    /// types of `signature`, which has to be a function type. Its parameters
+    /// are named after their position, as in `argument1`.
+    ///
+    /// Panics if `signature` is not a function type. [`Bau`](crate::Bau)
+    /// rejects such native functions before registering them.
+    pub fn from_signature(name: &str, signature: Type) -> Self {
+        let Type::Function {
+            parameters,
+            return_type,
+        } = signature
+        else {
+            panic!("The signature of `{}` should be a function type", name);
+        };
+        Self {
+            name: name.to_string(),
+            parameters: parameters
+                .into_iter()
+                .enumerate()
+                .map(|(index, type_)| CheckedFunctionParameter {
+                    name: format!("argument{}", index + 1),
+                    type_,
+                })
+                .collect(),
+            return_type: *return_type,
+            deprecation: None,
+        }
+    }
+
+    /// The type of this function when it is used as a value.
+    pub fn function_type(&self) -> Type {
+        Type::Function {
+            parameters: self
+                .parameters
+                .iter()
+                .map(|parameter| parameter.type_.clone())
+                .collect(),
+            return_type: Box::new(self.return_type.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for CheckedFunctionDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|parameter| format!("{} {}", parameter.type_, parameter.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(
+            f,
+            "fn {}({}) -> {}",
+            self.name, parameters, self.return_type
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Scope {
     variables: Vec<CheckedVariable>,
+    /// Maps the names of the nested functions declared in this scope to the
+    /// names they are registered under.
+    functions: HashMap<String, String>,
+    /// The `let` variables declared in this scope that have not been read
+    /// yet, with the ranges of their names.
+    unread: Vec<(String, CodeRange)>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Typechecker {
     errors: Vec<TypecheckerError>,
+    warnings: Vec<TypecheckerWarning>,
     scope_stack: Vec<Scope>,
+    /// Holds the constants, which are visible from every function body.
+    global_scope: Scope,
     functions: HashMap<String, CheckedFunctionDefinition>,
     methods: HashMap<Type, HashMap<String, CheckedFunctionDefinition>>,
+    /// The method signatures required by each trait.
+    traits: HashMap<String, Vec<CheckedFunctionDefinition>>,
+    /// The names and values of the variants of each enum, in declaration order.
+    enums: HashMap<String, Vec<(String, i64)>>,
+    /// The names and types of the fields of each struct, in declaration order.
+    structs: HashMap<String, Vec<(String, Type)>>,
+    /// How many loops enclose the statement being checked.
+    loop_depth: usize,
+    /// Whether the statement being checked is deferred, in which case it can't
+    /// `return` or `defer` again.
+    in_defer: bool,
+    /// The names of the functions enclosing the statement being checked,
+    /// outermost first.
+    function_path: Vec<String>,
 }
 
 impl Typechecker {
@@ -202,12 +432,24 @@ impl Typechecker {
         &self.errors
     }
 
+    pub fn warnings(&self) -> &[TypecheckerWarning] {
+        &self.warnings
+    }
+
     pub fn new() -> Self {
         Self {
             errors: vec![],
+            warnings: vec![],
             scope_stack: vec![],
+            global_scope: Scope::default(),
             functions: HashMap::new(),
             methods: HashMap::new(),
+            traits: HashMap::new(),
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            loop_depth: 0,
+            in_defer: false,
+            function_path: vec![],
         }
     }
 
@@ -216,9 +458,64 @@ impl Typechecker {
         for builtin_function in builtin::BUILTIN_FUNCTIONS.values() {
             self.register_function(builtin_function.clone());
         }
+        for (type_, builtin_method) in builtin::BUILTIN_METHODS.iter() {
+            self.register_method(type_.clone(), builtin_method.clone());
+        }
+        // Enums are registered first, so their names can be used as types
+        // anywhere, including in trait signatures.
+        for item in items.iter() {
+            if let ParsedItemKind::Enum(enum_item) = item.kind() {
+                if let Err(error) = self.check_enum_item(enum_item) {
+                    self.errors.push(error);
+                }
+            }
+        }
+        // Struct names are declared before any fields are checked, so a field
+        // can have the type of a struct that is declared after it.
+        let mut structs = vec![];
+        for item in items.iter() {
+            if let ParsedItemKind::Struct(struct_item) = item.kind() {
+                match self.declare_struct_item(struct_item) {
+                    Ok(()) => structs.push(struct_item),
+                    Err(error) => self.errors.push(error),
+                }
+            }
+        }
+        for struct_item in structs {
+            if let Err(error) = self.check_struct_item(struct_item) {
+                self.errors.push(error);
+            }
+        }
+        // Traits are registered up front, so an `extend` can implement a
+        // trait that is declared after it.
+        for item in items.iter() {
+            if let ParsedItemKind::Trait(trait_item) = item.kind() {
+                match self.check_trait_item(trait_item) {
+                    Ok(methods) => {
+                        self.traits
+                            .insert(trait_item.name.name().to_string(), methods);
+                    }
+                    Err(error) => self.errors.push(error),
+                }
+            }
+        }
+        let mut checked_items = self.check_const_items(items);
         for item in items.iter() {
             match item.kind() {
-                ParsedItemKind::Function(_) => {
+                ParsedItemKind::Function(function) => {
+                    // Builtins are dispatched before user functions, so a
+                    // function named after one would never be called, and a
+                    // second user function would replace the first.
+                    let name = function.name.name();
+                    if self.functions.contains_key(name) || builtin::is_builtin_function(name) {
+                        self.errors.push(TypecheckerError::new(
+                            TypecheckerErrorKind::FunctionAlreadyDefined {
+                                name: name.to_string(),
+                            },
+                            function.name.token().range(),
+                        ));
+                        continue;
+                    }
                     let function_definition = match self.check_function_definition(item, false) {
                         Ok(function_definition) => function_definition,
                         Err(error) => {
@@ -237,6 +534,7 @@ impl Typechecker {
                         }
                     };
 
+                    let mut method_definitions = vec![];
                     for function in extend_item.functions.iter() {
                         let function_item = ParsedItem::new(
                             ParsedItemKind::Function(function.clone()),
@@ -250,15 +548,28 @@ impl Typechecker {
                                     continue;
                                 }
                             };
-                        self.register_method(type_, function_definition);
+                        method_definitions.push(function_definition.clone());
+                        self.register_method(type_.clone(), function_definition);
+                    }
+
+                    if let Some(trait_name) = &extend_item.trait_name {
+                        let errors = self.check_trait_implementation(
+                            trait_name,
+                            extend_item,
+                            &method_definitions,
+                        );
+                        self.errors.extend(errors);
                     }
                 }
+                ParsedItemKind::Trait(_)
+                | ParsedItemKind::Enum(_)
+                | ParsedItemKind::Struct(_)
+                | ParsedItemKind::Const(_) => {}
             }
         }
 
         // FIXME: We might be able to only check function definitions once somehow.
         //        Currently we still check the function definitions here again.
-        let mut checked_items = vec![];
         for item in items.iter() {
             match item.kind() {
                 ParsedItemKind::Function(_) => {
@@ -287,15 +598,48 @@ impl Typechecker {
                         range: *item.range(),
                     });
                 }
+                ParsedItemKind::Const(_)
+                | ParsedItemKind::Trait(_)
+                | ParsedItemKind::Enum(_)
+                | ParsedItemKind::Struct(_) => {}
             }
         }
 
         // Check if main function is found
-        if self.get_function_definition_by_name("main").is_none() {
-            self.errors.push(TypecheckerError::new(
+        match self.get_function_definition_by_name("main") {
+            Some(main) => {
+                // `main` may take the command-line arguments as a `[string]`.
+                let valid = match main.parameters.as_slice() {
+                    [] => true,
+                    [args] => args.type_ == Type::Array(Box::new(Type::String)),
+                    _ => false,
+                };
+                let range = items
+                    .iter()
+                    .find(|item| {
+                        matches!(item.kind(), ParsedItemKind::Function(function) if function.name.name() == "main")
+                    })
+                    .map(|item| *item.range())
+                    .unwrap_or_else(|| CodeRange::new(Span::new(0, 0), SourceCoords::new(0, 0)));
+                if !valid {
+                    self.errors.push(TypecheckerError::new(
+                        TypecheckerErrorKind::InvalidMainSignature,
+                        range,
+                    ));
+                }
+                if !matches!(main.return_type, Type::Integer | Type::Void) {
+                    self.errors.push(TypecheckerError::new(
+                        TypecheckerErrorKind::InvalidMainReturnType {
+                            found: main.return_type.clone(),
+                        },
+                        range,
+                    ));
+                }
+            }
+            None => self.errors.push(TypecheckerError::new(
                 TypecheckerErrorKind::MainFunctionNotDefined,
                 CodeRange::new(Span::new(0, 0), SourceCoords::new(0, 0)),
-            ));
+            )),
         }
 
         checked_items
@@ -304,6 +648,20 @@ impl Typechecker {
     fn check_function_item(
         &mut self,
         function_item: &ParsedItem,
+    ) -> TypecheckerResult<CheckedFunctionItem> {
+        let ParsedItemKind::Function(function) = function_item.kind() else {
+            panic!("Expected function item");
+        };
+
+        self.function_path.push(function.name.name().to_string());
+        let checked_function = self.check_function_item_body(function_item);
+        self.function_path.pop();
+        checked_function
+    }
+
+    fn check_function_item_body(
+        &mut self,
+        function_item: &ParsedItem,
     ) -> TypecheckerResult<CheckedFunctionItem> {
         self.push_scope();
 
@@ -326,7 +684,7 @@ impl Typechecker {
                     *return_statement.range(),
                 ));
             }
-        } else if definition.return_type != Type::Void {
+        } else if definition.return_type != Type::Void && !block_terminates(&body) {
             self.pop_scope();
             return Err(TypecheckerError::new(
                 TypecheckerErrorKind::ExpectedReturnValue,
@@ -352,11 +710,23 @@ impl Typechecker {
 
         let return_type = self.check_type(&function.return_type_name)?;
 
+        let deprecation = self.check_function_attributes(&function.attributes)?;
+
         if register_parameters {
-            for parameter in parameters.iter() {
+            for (parameter, parsed_parameter) in parameters.iter().zip(&function.parameters) {
+                // Like any other variable, a parameter can't shadow a constant.
+                if self.is_constant(&parameter.name) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::VariableAlreadyDefined {
+                            name: parameter.name.clone(),
+                        },
+                        parsed_parameter.name.token().range(),
+                    ));
+                }
                 self.register_var_in_current_scope(CheckedVariable {
                     name: parameter.name.clone(),
-                    type_: parameter.type_,
+                    type_: parameter.type_.clone(),
+                    mutable: true,
                 });
             }
         }
@@ -365,15 +735,78 @@ impl Typechecker {
             name: function.name.name().to_string(),
             parameters,
             return_type,
+            deprecation,
         })
     }
 
+    /// Checks the attributes of a function and returns its deprecation message, if any.
+    fn check_function_attributes(
+        &mut self,
+        attributes: &[ParsedAttribute],
+    ) -> TypecheckerResult<Option<String>> {
+        let mut deprecation = None;
+        for attribute in attributes.iter() {
+            match attribute.name.name() {
+                "deprecated" => {
+                    let message = match attribute.arguments.as_slice() {
+                        [] => String::new(),
+                        [argument] => match argument.kind() {
+                            ParsedExpressionKind::Literal(Value::String(message)) => {
+                                message.clone()
+                            }
+                            _ => {
+                                return Err(TypecheckerError::new(
+                                    TypecheckerErrorKind::InvalidAttributeArguments {
+                                        name: attribute.name.name().to_string(),
+                                    },
+                                    *argument.range(),
+                                ))
+                            }
+                        },
+                        _ => {
+                            return Err(TypecheckerError::new(
+                                TypecheckerErrorKind::InvalidAttributeArguments {
+                                    name: attribute.name.name().to_string(),
+                                },
+                                attribute.range,
+                            ))
+                        }
+                    };
+                    deprecation = Some(message);
+                }
+                _ => {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::UnknownAttribute {
+                            name: attribute.name.name().to_string(),
+                        },
+                        attribute.name.token().range(),
+                    ))
+                }
+            }
+        }
+        Ok(deprecation)
+    }
+
     fn check_function_parameters(
         &mut self,
         parameters: &[ParsedFunctionParameter],
     ) -> TypecheckerResult<Vec<CheckedFunctionParameter>> {
-        let mut checked_parameters = vec![];
+        let mut checked_parameters: Vec<CheckedFunctionParameter> = vec![];
         for parameter in parameters.iter() {
+            let name = parameter.name.name();
+            if name != DISCARD_NAME
+                && checked_parameters
+                    .iter()
+                    .any(|checked_parameter| checked_parameter.name == name)
+            {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::DuplicateParameter {
+                        name: name.to_string(),
+                    },
+                    parameter.name.token().range(),
+                ));
+            }
+
             let type_ = self.check_type(&parameter.type_name)?;
             checked_parameters.push(CheckedFunctionParameter {
                 name: parameter.name.name().to_string(),
@@ -389,9 +822,47 @@ impl Typechecker {
         parent_function_return_type: &Type,
     ) -> TypecheckerResult<Vec<CheckedStatement>> {
         let checked_body = self.check_block(body, parent_function_return_type)?;
+        self.check_reachability(&checked_body);
         Ok(checked_body)
     }
 
+    /// Warns about statements that can never run because an earlier statement
+    /// in the same block always returns or diverges.
+    fn check_reachability(&mut self, block: &[CheckedStatement]) {
+        for (index, statement) in block.iter().enumerate() {
+            match statement.kind() {
+                CheckedStatementKind::If {
+                    then_body,
+                    else_body,
+                    ..
+                }
+                | CheckedStatementKind::IfLet {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    self.check_reachability(then_body);
+                    if let Some(else_body) = else_body {
+                        self.check_reachability(else_body);
+                    }
+                }
+                CheckedStatementKind::Loop { block }
+                | CheckedStatementKind::While { block, .. } => self.check_reachability(block),
+                _ => {}
+            }
+
+            if statement_terminates(statement) {
+                if let (Some(first), Some(last)) = (block.get(index + 1), block.last()) {
+                    self.warnings.push(TypecheckerWarning::new(
+                        TypecheckerWarningKind::UnreachableCode,
+                        CodeRange::from_ranges(*first.range(), *last.range()),
+                    ));
+                }
+                return;
+            }
+        }
+    }
+
     fn check_extend_item(
         &mut self,
         extend_item: ParsedExtendItem,
@@ -402,13 +873,334 @@ impl Typechecker {
         for function in extend_item.functions.iter() {
             let function_item =
                 ParsedItem::new(ParsedItemKind::Function(function.clone()), function.range);
-            let method = self.check_function_item(&function_item)?;
-            methods.push(method);
+            // The receiver is available in the body as `self`.
+            self.push_scope();
+            self.register_var_in_current_scope(CheckedVariable {
+                name: "self".to_string(),
+                type_: type_.clone(),
+                mutable: true,
+            });
+            let method = self.check_function_item(&function_item);
+            self.pop_scope();
+            methods.push(method?);
         }
 
         Ok(CheckedExtendItem { type_, methods })
     }
 
+    fn check_trait_item(
+        &mut self,
+        trait_item: &ParsedTraitItem,
+    ) -> TypecheckerResult<Vec<CheckedFunctionDefinition>> {
+        let mut methods = vec![];
+        for method in trait_item.methods.iter() {
+            methods.push(CheckedFunctionDefinition {
+                name: method.name.name().to_string(),
+                parameters: self.check_function_parameters(&method.parameters)?,
+                return_type: self.check_type(&method.return_type_name)?,
+                deprecation: None,
+            });
+        }
+        Ok(methods)
+    }
+
+    /// Checks that an `extend` block provides every method its trait requires,
+    /// with matching parameter and return types.
+    fn check_trait_implementation(
+        &self,
+        trait_name: &Identifier,
+        extend_item: &ParsedExtendItem,
+        method_definitions: &[CheckedFunctionDefinition],
+    ) -> Vec<TypecheckerError> {
+        let Some(required_methods) = self.traits.get(trait_name.name()) else {
+            return vec![TypecheckerError::new(
+                TypecheckerErrorKind::TraitNotDefined {
+                    name: trait_name.name().to_string(),
+                },
+                trait_name.token().range(),
+            )];
+        };
+
+        let mut errors = vec![];
+        for required in required_methods.iter() {
+            let Some(method) = method_definitions
+                .iter()
+                .find(|method| method.name == required.name)
+            else {
+                errors.push(TypecheckerError::new(
+                    TypecheckerErrorKind::MissingTraitMethod {
+                        trait_name: trait_name.name().to_string(),
+                        method_name: required.name.clone(),
+                    },
+                    trait_name.token().range(),
+                ));
+                continue;
+            };
+
+            let parameter_types_match = method.parameters.len() == required.parameters.len()
+                && method
+                    .parameters
+                    .iter()
+                    .zip(required.parameters.iter())
+                    .all(|(parameter, required)| parameter.type_ == required.type_);
+            if !parameter_types_match || method.return_type != required.return_type {
+                let range = extend_item
+                    .functions
+                    .iter()
+                    .find(|function| function.name.name() == method.name)
+                    .map(|function| function.name.token().range())
+                    .unwrap_or(trait_name.token().range());
+                errors.push(TypecheckerError::new(
+                    TypecheckerErrorKind::TraitMethodMismatch {
+                        trait_name: trait_name.name().to_string(),
+                        method_name: required.name.clone(),
+                        expected: required.to_string(),
+                    },
+                    range,
+                ));
+            }
+        }
+        errors
+    }
+
+    fn check_enum_item(&mut self, enum_item: &ParsedEnumItem) -> TypecheckerResult<()> {
+        let name = &enum_item.name;
+        if type_from_name(name.name(), &self.enums, &self.structs).is_some() {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeAlreadyDefined {
+                    name: name.name().to_string(),
+                },
+                name.token().range(),
+            ));
+        }
+
+        let mut variants: Vec<(String, i64)> = vec![];
+        let mut next_value = Some(0);
+        for variant in enum_item.variants.iter() {
+            let value = match &variant.value {
+                Some(value) => discriminant(value).ok_or_else(|| {
+                    TypecheckerError::new(TypecheckerErrorKind::InvalidDiscriminant, *value.range())
+                })?,
+                None => next_value.ok_or_else(|| {
+                    TypecheckerError::new(
+                        TypecheckerErrorKind::InvalidDiscriminant,
+                        variant.name.token().range(),
+                    )
+                })?,
+            };
+
+            if variants.iter().any(|(name, _)| name == variant.name.name()) {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::VariantAlreadyDefined {
+                        name: variant.name.name().to_string(),
+                    },
+                    variant.name.token().range(),
+                ));
+            }
+            if let Some((other, _)) = variants
+                .iter()
+                .find(|(_, other_value)| *other_value == value)
+            {
+                let range = match &variant.value {
+                    Some(value) => *value.range(),
+                    None => variant.name.token().range(),
+                };
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::DuplicateDiscriminant {
+                        variant: variant.name.name().to_string(),
+                        other: other.clone(),
+                        value,
+                    },
+                    range,
+                ));
+            }
+
+            variants.push((variant.name.name().to_string(), value));
+            next_value = value.checked_add(1);
+        }
+
+        self.register_method(
+            Type::Enum(name.name().to_string()),
+            CheckedFunctionDefinition {
+                name: builtin::ENUM_TO_INT_METHOD.to_string(),
+                parameters: vec![],
+                return_type: Type::Integer,
+                deprecation: None,
+            },
+        );
+        self.enums.insert(name.name().to_string(), variants);
+        Ok(())
+    }
+
+    fn declare_struct_item(&mut self, struct_item: &ParsedStructItem) -> TypecheckerResult<()> {
+        let name = &struct_item.name;
+        if type_from_name(name.name(), &self.enums, &self.structs).is_some() {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeAlreadyDefined {
+                    name: name.name().to_string(),
+                },
+                name.token().range(),
+            ));
+        }
+        self.structs.insert(name.name().to_string(), vec![]);
+        Ok(())
+    }
+
+    fn check_struct_item(&mut self, struct_item: &ParsedStructItem) -> TypecheckerResult<()> {
+        let mut fields: Vec<(String, Type)> = vec![];
+        for field in struct_item.fields.iter() {
+            if fields.iter().any(|(name, _)| name == field.name.name()) {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::FieldAlreadyDefined {
+                        name: field.name.name().to_string(),
+                    },
+                    field.name.token().range(),
+                ));
+            }
+            let type_ = self.check_type(&field.type_name)?;
+            fields.push((field.name.name().to_string(), type_));
+        }
+        self.structs
+            .insert(struct_item.name.name().to_string(), fields);
+        Ok(())
+    }
+
+    /// Constants can refer to each other in any order, so they are all
+    /// declared before their values are checked. They are returned in
+    /// dependency order, which lets the interpreter evaluate each of them
+    /// once, after the constants it refers to.
+    fn check_const_items(&mut self, items: &[ParsedItem]) -> Vec<CheckedItem> {
+        let mut declared = vec![];
+        for item in items.iter() {
+            if let ParsedItemKind::Const(const_item) = item.kind() {
+                match self.declare_const_item(const_item) {
+                    Ok(type_) => declared.push((item, type_)),
+                    Err(error) => self.errors.push(error),
+                }
+            }
+        }
+
+        let mut sorted = vec![];
+        for index in 0..declared.len() {
+            self.sort_const_item(index, &declared, &mut vec![], &mut sorted);
+        }
+
+        let mut checked_items = vec![];
+        for index in sorted {
+            let (item, type_) = &declared[index];
+            let ParsedItemKind::Const(const_item) = item.kind() else {
+                panic!("Expected const item");
+            };
+            match self.check_const_item(const_item, type_.clone()) {
+                Ok(constant) => checked_items.push(CheckedItem {
+                    kind: CheckedItemKind::Const(constant),
+                    range: *item.range(),
+                }),
+                Err(error) => self.errors.push(error),
+            }
+        }
+        checked_items
+    }
+
+    /// Adds the constant at `index` to `sorted` after the constants its value
+    /// refers to, reporting an error if it refers back to itself.
+    fn sort_const_item(
+        &mut self,
+        index: usize,
+        declared: &[(&ParsedItem, Type)],
+        visiting: &mut Vec<usize>,
+        sorted: &mut Vec<usize>,
+    ) {
+        if sorted.contains(&index) || visiting.contains(&index) {
+            return;
+        }
+
+        let ParsedItemKind::Const(const_item) = declared[index].0.kind() else {
+            panic!("Expected const item");
+        };
+        visiting.push(index);
+        for dependency in constant_dependencies(&const_item.value) {
+            let Some(dependency_index) = declared.iter().position(|(item, _)| {
+                matches!(item.kind(), ParsedItemKind::Const(other) if other.name.name() == dependency.name())
+            }) else {
+                continue;
+            };
+            if visiting.contains(&dependency_index) {
+                self.errors.push(TypecheckerError::new(
+                    TypecheckerErrorKind::ConstantCycle {
+                        name: dependency.name().to_string(),
+                    },
+                    dependency.token().range(),
+                ));
+                continue;
+            }
+            self.sort_const_item(dependency_index, declared, visiting, sorted);
+        }
+        visiting.pop();
+        sorted.push(index);
+    }
+
+    fn declare_const_item(&mut self, const_item: &ParsedConstItem) -> TypecheckerResult<Type> {
+        let name = &const_item.name;
+        if self.variable_exists(name.name()) {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::VariableAlreadyDefined {
+                    name: name.name().to_string(),
+                },
+                name.token().range(),
+            ));
+        }
+
+        let type_ = self.check_type(&const_item.type_name)?;
+        self.global_scope.variables.push(CheckedVariable {
+            name: name.name().to_string(),
+            type_: type_.clone(),
+            mutable: false,
+        });
+        Ok(type_)
+    }
+
+    fn check_const_item(
+        &mut self,
+        const_item: &ParsedConstItem,
+        type_: Type,
+    ) -> TypecheckerResult<CheckedConstItem> {
+        self.check_constant_expression(&const_item.value)?;
+        let value = self.check_expression(&const_item.value)?;
+
+        if !type_.accepts(&self.expression_type(&value)?) {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeMismatch {
+                    expected: type_.clone(),
+                    actual: self.expression_type(&value)?,
+                },
+                value.range,
+            ));
+        }
+
+        Ok(CheckedConstItem {
+            name: const_item.name.name().to_string(),
+            type_,
+            value,
+        })
+    }
+
+    /// Constants are evaluated before `main` runs, so their values may only
+    /// consist of literals and other constants.
+    fn check_constant_expression(&self, expression: &ParsedExpression) -> TypecheckerResult<()> {
+        match expression.kind() {
+            // Outside of a function body, only constants are in scope.
+            ParsedExpressionKind::Literal(_) | ParsedExpressionKind::Variable(_) => Ok(()),
+            ParsedExpressionKind::PrefixOperator { expression, .. } => {
+                self.check_constant_expression(expression)
+            }
+            _ => Err(TypecheckerError::new(
+                TypecheckerErrorKind::NonConstantExpression,
+                *expression.range(),
+            )),
+        }
+    }
+
     fn check_block(
         &mut self,
         block: &[ParsedStatement],
@@ -429,6 +1221,10 @@ impl Typechecker {
     ) -> TypecheckerResult<CheckedStatement> {
         match statement.kind() {
             ParsedStatementKind::Let { .. } => self.check_let_statement(statement),
+            ParsedStatementKind::DestructuringLet {
+                bindings,
+                initial_value,
+            } => self.check_destructuring_let_statement(statement, bindings, initial_value),
             ParsedStatementKind::Return { .. } => {
                 self.check_return_statement(statement, parent_function_return_type)
             }
@@ -436,6 +1232,9 @@ impl Typechecker {
             ParsedStatementKind::If { .. } => {
                 self.check_if_statement(statement, parent_function_return_type)
             }
+            ParsedStatementKind::IfLet { .. } => {
+                self.check_if_let_statement(statement, parent_function_return_type)
+            }
             ParsedStatementKind::Loop { .. } => {
                 self.check_loop_statement(statement, parent_function_return_type)
             }
@@ -445,19 +1244,171 @@ impl Typechecker {
             ParsedStatementKind::VariableAssignment { .. } => {
                 self.check_variable_assignment_statement(statement)
             }
+            ParsedStatementKind::Break | ParsedStatementKind::Continue => {
+                self.check_loop_control_statement(statement)
+            }
+            ParsedStatementKind::FunctionDef(function) => {
+                self.check_function_def_statement(statement, function)
+            }
+            ParsedStatementKind::Defer {
+                statement: deferred,
+            } => self.check_defer_statement(statement, deferred, parent_function_return_type),
         }
     }
 
-    fn check_let_statement(
+    fn check_defer_statement(
         &mut self,
         statement: &ParsedStatement,
+        deferred: &ParsedStatement,
+        parent_function_return_type: &Type,
     ) -> TypecheckerResult<CheckedStatement> {
-        match statement.kind() {
+        if self.in_defer {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::InvalidDeferredStatement,
+                *statement.range(),
+            ));
+        }
+
+        // The deferred statement runs after the rest of the function, so it
+        // can't break out of the loops around the `defer`, and the variables
+        // it declares are not visible after it.
+        let outer_loop_depth = std::mem::take(&mut self.loop_depth);
+        self.in_defer = true;
+        self.push_scope();
+        let checked_statement = self.check_statement(deferred, parent_function_return_type);
+        self.pop_scope();
+        self.in_defer = false;
+        self.loop_depth = outer_loop_depth;
+
+        Ok(CheckedStatement {
+            kind: CheckedStatementKind::Defer {
+                statement: Box::new(checked_statement?),
+            },
+            range: *statement.range(),
+        })
+    }
+
+    fn check_function_def_statement(
+        &mut self,
+        statement: &ParsedStatement,
+        function: &ParsedFunctionItem,
+    ) -> TypecheckerResult<CheckedStatement> {
+        let function_item =
+            ParsedItem::new(ParsedItemKind::Function(function.clone()), function.range);
+        let mut definition = self.check_function_definition(&function_item, false)?;
+
+        // Nested functions are registered under a name that can't clash with
+        // any other function, so the interpreter can look them up globally.
+        let mut registered_name =
+            format!("{}.{}", self.function_path.join("."), function.name.name());
+        let mut index = 1;
+        while self.functions.contains_key(&registered_name) {
+            index += 1;
+            registered_name = format!(
+                "{}.{}#{}",
+                self.function_path.join("."),
+                function.name.name(),
+                index
+            );
+        }
+        definition.name = registered_name.clone();
+        self.register_function(definition);
+        // Registered before checking the body, so the function can recurse.
+        self.scope_stack
+            .last_mut()
+            .unwrap()
+            .functions
+            .insert(function.name.name().to_string(), registered_name.clone());
+
+        // The body can't see the enclosing function's variables, only the
+        // nested functions declared around it.
+        let mut visible_functions = HashMap::new();
+        for scope in self.scope_stack.iter() {
+            visible_functions.extend(scope.functions.clone());
+        }
+        let outer_scope_stack = std::mem::replace(
+            &mut self.scope_stack,
+            vec![Scope {
+                variables: vec![],
+                functions: visible_functions,
+                unread: vec![],
+            }],
+        );
+        let outer_loop_depth = std::mem::take(&mut self.loop_depth);
+        let outer_in_defer = std::mem::take(&mut self.in_defer);
+        let checked_function = self.check_function_item(&function_item);
+        self.scope_stack = outer_scope_stack;
+        self.loop_depth = outer_loop_depth;
+        self.in_defer = outer_in_defer;
+
+        let mut checked_function = checked_function?;
+        checked_function.definition.name = registered_name;
+        Ok(CheckedStatement {
+            kind: CheckedStatementKind::FunctionDef(checked_function),
+            range: *statement.range(),
+        })
+    }
+
+    fn check_loop_control_statement(
+        &mut self,
+        statement: &ParsedStatement,
+    ) -> TypecheckerResult<CheckedStatement> {
+        let (kind, error_kind) = match statement.kind() {
+            ParsedStatementKind::Break => (
+                CheckedStatementKind::Break,
+                TypecheckerErrorKind::BreakOutsideLoop,
+            ),
+            ParsedStatementKind::Continue => (
+                CheckedStatementKind::Continue,
+                TypecheckerErrorKind::ContinueOutsideLoop,
+            ),
+            _ => panic!("Expected break or continue statement"),
+        };
+
+        if self.loop_depth == 0 {
+            return Err(TypecheckerError::new(error_kind, *statement.range()));
+        }
+
+        Ok(CheckedStatement {
+            kind,
+            range: *statement.range(),
+        })
+    }
+
+    /// Checks the body of a `loop` or `while`, in which `break` and `continue`
+    /// are allowed.
+    fn check_loop_body(
+        &mut self,
+        body: &[ParsedStatement],
+        parent_function_return_type: &Type,
+    ) -> TypecheckerResult<Vec<CheckedStatement>> {
+        self.push_scope();
+        self.loop_depth += 1;
+        let checked_body = self.check_block(body, parent_function_return_type);
+        self.loop_depth -= 1;
+        self.pop_scope();
+        checked_body
+    }
+
+    fn check_let_statement(
+        &mut self,
+        statement: &ParsedStatement,
+    ) -> TypecheckerResult<CheckedStatement> {
+        match statement.kind() {
             ParsedStatementKind::Let {
                 name,
                 type_name,
                 initial_value,
+                mutable,
             } => {
+                if name.name() == DISCARD_NAME {
+                    return self.check_discarding_let_statement(
+                        statement,
+                        type_name,
+                        initial_value,
+                    );
+                }
+
                 if self.variable_exists(name.name()) {
                     return Err(TypecheckerError::new(
                         TypecheckerErrorKind::VariableAlreadyDefined {
@@ -468,12 +1419,12 @@ impl Typechecker {
                 }
 
                 let type_ = self.check_type(type_name)?;
-                let checked_initial_value = self.check_expression(initial_value)?;
+                let checked_initial_value = self.check_value_expression(initial_value)?;
 
-                if type_ != self.expression_type(&checked_initial_value)? {
+                if !type_.accepts(&self.expression_type(&checked_initial_value)?) {
                     return Err(TypecheckerError::new(
                         TypecheckerErrorKind::TypeMismatch {
-                            expected: type_,
+                            expected: type_.clone(),
                             actual: self.expression_type(&checked_initial_value)?,
                         },
                         checked_initial_value.range,
@@ -482,8 +1433,10 @@ impl Typechecker {
 
                 self.register_var_in_current_scope(CheckedVariable {
                     name: name.name().to_string(),
-                    type_,
+                    type_: type_.clone(),
+                    mutable: *mutable,
                 });
+                self.track_unread(name);
 
                 Ok(CheckedStatement {
                     kind: CheckedStatementKind::Let {
@@ -498,11 +1451,115 @@ impl Typechecker {
         }
     }
 
+    fn check_destructuring_let_statement(
+        &mut self,
+        statement: &ParsedStatement,
+        bindings: &[ParsedBinding],
+        initial_value: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedStatement> {
+        let checked_initial_value = self.check_value_expression(initial_value)?;
+        let types = bindings
+            .iter()
+            .map(|binding| self.check_type(&binding.type_name))
+            .collect::<TypecheckerResult<Vec<_>>>()?;
+
+        // The element counts have to match as well as the types.
+        let actual = self.expression_type(&checked_initial_value)?;
+        let matches = match &actual {
+            Type::Tuple(elements) => {
+                elements.len() == types.len()
+                    && types
+                        .iter()
+                        .zip(elements)
+                        .all(|(type_, element)| type_.accepts(element))
+            }
+            _ => false,
+        };
+        if !matches {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeMismatch {
+                    expected: Type::Tuple(types),
+                    actual,
+                },
+                *initial_value.range(),
+            ));
+        }
+
+        let mut variables = vec![];
+        for (binding, type_) in bindings.iter().zip(types) {
+            let name = &binding.name;
+            if name.name() == DISCARD_NAME {
+                variables.push(None);
+                continue;
+            }
+            if self.variable_exists(name.name()) {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::VariableAlreadyDefined {
+                        name: name.name().to_string(),
+                    },
+                    name.token().range(),
+                ));
+            }
+            let variable = CheckedVariable {
+                name: name.name().to_string(),
+                type_,
+                mutable: false,
+            };
+            self.register_var_in_current_scope(variable.clone());
+            self.track_unread(name);
+            variables.push(Some(variable));
+        }
+
+        Ok(CheckedStatement {
+            kind: CheckedStatementKind::DestructuringLet {
+                variables,
+                initial_value: checked_initial_value,
+            },
+            range: *statement.range(),
+        })
+    }
+
+    /// A `let` binding to `_` only evaluates its initial value for its side
+    /// effects, so it is lowered to an expression statement.
+    fn check_discarding_let_statement(
+        &mut self,
+        statement: &ParsedStatement,
+        type_name: &TypeName,
+        initial_value: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedStatement> {
+        let type_ = self.check_type(type_name)?;
+        let checked_initial_value = self.check_expression(initial_value)?;
+
+        if !type_.accepts(&self.expression_type(&checked_initial_value)?) {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeMismatch {
+                    expected: type_.clone(),
+                    actual: self.expression_type(&checked_initial_value)?,
+                },
+                checked_initial_value.range,
+            ));
+        }
+
+        Ok(CheckedStatement {
+            kind: CheckedStatementKind::Expression {
+                expression: checked_initial_value,
+            },
+            range: *statement.range(),
+        })
+    }
+
     fn check_return_statement(
         &mut self,
         statement: &ParsedStatement,
         parent_function_return_type: &Type,
     ) -> TypecheckerResult<CheckedStatement> {
+        if self.in_defer {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::InvalidDeferredStatement,
+                *statement.range(),
+            ));
+        }
+
         match statement.kind() {
             ParsedStatementKind::Return { value } => {
                 if parent_function_return_type == &Type::Void && value.is_some() {
@@ -522,12 +1579,13 @@ impl Typechecker {
                     })
                 } else {
                     let value = value.clone().unwrap();
-                    let checked_value = self.check_expression(&value)?;
+                    let checked_value = self.check_value_expression(&value)?;
 
-                    if parent_function_return_type != &self.expression_type(&checked_value)? {
+                    if !parent_function_return_type.accepts(&self.expression_type(&checked_value)?)
+                    {
                         return Err(TypecheckerError::new(
                             TypecheckerErrorKind::TypeMismatch {
-                                expected: *parent_function_return_type,
+                                expected: parent_function_return_type.clone(),
                                 actual: self.expression_type(&checked_value)?,
                             },
                             *value.range(),
@@ -610,6 +1668,23 @@ impl Typechecker {
                     None
                 };
 
+                // Only literal conditions are recognized, not computed constants.
+                let skipped_body = match checked_condition.kind() {
+                    CheckedExpressionKind::Literal(Value::Boolean(false)) => Some(&checked_body),
+                    CheckedExpressionKind::Literal(Value::Boolean(true)) => {
+                        checked_else_body.as_ref()
+                    }
+                    _ => None,
+                };
+                if let Some((first, last)) =
+                    skipped_body.and_then(|body| Some((body.first()?, body.last()?)))
+                {
+                    self.warnings.push(TypecheckerWarning::new(
+                        TypecheckerWarningKind::UnreachableCode,
+                        CodeRange::from_ranges(*first.range(), *last.range()),
+                    ));
+                }
+
                 Ok(CheckedStatement {
                     kind: CheckedStatementKind::If {
                         condition: checked_condition,
@@ -623,16 +1698,94 @@ impl Typechecker {
         }
     }
 
-    fn check_loop_statement(
+    fn check_if_let_statement(
         &mut self,
         statement: &ParsedStatement,
         parent_function_return_type: &Type,
     ) -> TypecheckerResult<CheckedStatement> {
         match statement.kind() {
-            ParsedStatementKind::Loop { body } => {
+            ParsedStatementKind::IfLet {
+                name,
+                type_name,
+                value,
+                then_body,
+                else_body,
+            } => {
+                if self.variable_exists(name.name()) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::VariableAlreadyDefined {
+                            name: name.name().to_string(),
+                        },
+                        name.token().range(),
+                    ));
+                }
+
+                let type_ = self.check_type(type_name)?;
+                let checked_value = self.check_expression(value)?;
+                let inner_type = match self.expression_type(&checked_value)? {
+                    Type::Optional(inner_type) => *inner_type,
+                    actual => {
+                        return Err(TypecheckerError::new(
+                            TypecheckerErrorKind::ExpectedOptional { actual },
+                            *value.range(),
+                        ))
+                    }
+                };
+
+                if !type_.accepts(&inner_type) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::TypeMismatch {
+                            expected: type_,
+                            actual: inner_type,
+                        },
+                        *value.range(),
+                    ));
+                }
+
+                // The binding is only visible in the then-branch.
                 self.push_scope();
-                let checked_body = self.check_block(body, parent_function_return_type)?;
+                self.register_var_in_current_scope(CheckedVariable {
+                    name: name.name().to_string(),
+                    type_: type_.clone(),
+                    mutable: false,
+                });
+                let checked_body = self.check_block(then_body, parent_function_return_type);
                 self.pop_scope();
+                let checked_body = checked_body?;
+
+                let checked_else_body = if let Some(else_body) = else_body {
+                    self.push_scope();
+                    let checked_else_body =
+                        self.check_block(else_body, parent_function_return_type)?;
+                    self.pop_scope();
+                    Some(checked_else_body)
+                } else {
+                    None
+                };
+
+                Ok(CheckedStatement {
+                    kind: CheckedStatementKind::IfLet {
+                        name: name.name().to_string(),
+                        type_,
+                        value: checked_value,
+                        then_body: checked_body,
+                        else_body: checked_else_body,
+                    },
+                    range: *statement.range(),
+                })
+            }
+            _ => panic!("Expected if let statement"),
+        }
+    }
+
+    fn check_loop_statement(
+        &mut self,
+        statement: &ParsedStatement,
+        parent_function_return_type: &Type,
+    ) -> TypecheckerResult<CheckedStatement> {
+        match statement.kind() {
+            ParsedStatementKind::Loop { body } => {
+                let checked_body = self.check_loop_body(body, parent_function_return_type)?;
 
                 Ok(CheckedStatement {
                     kind: CheckedStatementKind::Loop {
@@ -673,9 +1826,7 @@ impl Typechecker {
                     ));
                 }
 
-                self.push_scope();
-                let checked_block = self.check_block(block, parent_function_return_type)?;
-                self.pop_scope();
+                let checked_block = self.check_loop_body(block, parent_function_return_type)?;
 
                 Ok(CheckedStatement {
                     kind: CheckedStatementKind::While {
@@ -708,10 +1859,60 @@ impl Typechecker {
                     ));
                 }
 
+                if self.is_constant(name.name()) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::ConstantAssignment {
+                            name: name.name().to_string(),
+                        },
+                        name.token().range(),
+                    ));
+                }
+
                 let variable = self.get_variable_by_name(name.name()).unwrap();
-                let checked_value = self.check_expression(value)?;
+                if !variable.mutable {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::AssignmentToImmutable {
+                            name: name.name().to_string(),
+                        },
+                        name.token().range(),
+                    ));
+                }
+                let checked_value = self.check_value_expression(value)?;
+
+                if matches!(
+                    operator,
+                    AssignmentOperator::AmpersandAmpersandEquals
+                        | AssignmentOperator::PipePipeEquals
+                ) && variable.type_ != Type::Boolean
+                {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::TypeMismatch {
+                            expected: Type::Boolean,
+                            actual: variable.type_,
+                        },
+                        name.token().range(),
+                    ));
+                }
 
-                if variable.type_ != self.expression_type(&checked_value)? {
+                if let Some(infix_operator) = operator.infix_operator() {
+                    if !infix_operator_supports(infix_operator, &variable.type_) {
+                        return Err(TypecheckerError::new(
+                            TypecheckerErrorKind::UnsupportedInfixOperator {
+                                operator: infix_operator,
+                                type_: variable.type_,
+                            },
+                            *statement.range(),
+                        ));
+                    }
+                }
+
+                let value_type = self.expression_type(&checked_value)?;
+                // Only a plain assignment can wrap a value into an optional.
+                let compatible = match operator {
+                    AssignmentOperator::Equals => variable.type_.accepts(&value_type),
+                    _ => variable.type_ == value_type,
+                };
+                if !compatible {
                     return Err(TypecheckerError::new(
                         TypecheckerErrorKind::TypeMismatch {
                             expected: variable.type_,
@@ -751,9 +1952,225 @@ impl Typechecker {
                 self.check_infix_operator_expression(expression)
             }
             ParsedExpressionKind::MethodCall { .. } => self.check_method_call(expression),
+            ParsedExpressionKind::MemberAccess { .. } => self.check_member_access(expression),
+            ParsedExpressionKind::Match { .. } => self.check_match_expression(expression),
+            ParsedExpressionKind::Struct { .. } => self.check_struct_expression(expression),
+            ParsedExpressionKind::Tuple(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.check_value_expression(element))
+                    .collect::<TypecheckerResult<Vec<_>>>()?;
+                Ok(CheckedExpression::new(
+                    CheckedExpressionKind::Tuple(elements),
+                    *expression.range(),
+                ))
+            }
+            ParsedExpressionKind::Ternary { .. } => self.check_ternary_expression(expression),
         }
     }
 
+    fn check_ternary_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let (condition, then_branch, else_branch) = match expression.kind() {
+            ParsedExpressionKind::Ternary {
+                condition,
+                then_branch,
+                else_branch,
+            } => (condition, then_branch, else_branch),
+            _ => panic!("Expected ternary expression"),
+        };
+
+        let checked_condition = self.check_expression(condition)?;
+        let condition_type = self.expression_type(&checked_condition)?;
+        if condition_type != Type::Boolean {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeMismatch {
+                    expected: Type::Boolean,
+                    actual: condition_type,
+                },
+                *condition.range(),
+            ));
+        }
+
+        let checked_then_branch = self.check_expression(then_branch)?;
+        let checked_else_branch = self.check_expression(else_branch)?;
+        let then_type = self.expression_type(&checked_then_branch)?;
+        let else_type = self.expression_type(&checked_else_branch)?;
+        if then_type != else_type {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::TypeMismatch {
+                    expected: then_type,
+                    actual: else_type,
+                },
+                *else_branch.range(),
+            ));
+        }
+
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::Ternary {
+                condition: Box::new(checked_condition),
+                then_branch: Box::new(checked_then_branch),
+                else_branch: Box::new(checked_else_branch),
+            },
+            *expression.range(),
+        ))
+    }
+
+    /// Checks an expression whose value is used, which rules out calls to
+    /// functions that return `void`.
+    fn check_value_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let checked_expression = self.check_expression(expression)?;
+        if self.expression_type(&checked_expression)? == Type::Void {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::InvalidVoidExpression,
+                *expression.range(),
+            ));
+        }
+        Ok(checked_expression)
+    }
+
+    fn check_match_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let (value, arms) = match expression.kind() {
+            ParsedExpressionKind::Match { value, arms } => (value, arms),
+            _ => panic!("Expected match expression"),
+        };
+
+        let checked_value = self.check_value_expression(value)?;
+        let value_type = self.expression_type(&checked_value)?;
+
+        let mut checked_arms: Vec<CheckedMatchArm> = vec![];
+        for arm in arms.iter() {
+            // Bindings are only visible in their own arm.
+            self.push_scope();
+            let checked_arm = self.check_match_arm(arm, &value_type);
+            self.pop_scope();
+            let checked_arm = checked_arm?;
+
+            // Every arm has to have the type of the first one.
+            if let Some(first) = checked_arms.first() {
+                let expected = self.expression_type(&first.value)?;
+                let actual = self.expression_type(&checked_arm.value)?;
+                if expected != actual {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::TypeMismatch { expected, actual },
+                        *arm.value.range(),
+                    ));
+                }
+            }
+            checked_arms.push(checked_arm);
+        }
+
+        let exhaustive = checked_arms.iter().any(|arm| {
+            arm.guard.is_none()
+                && matches!(
+                    arm.pattern,
+                    CheckedPattern::Wildcard | CheckedPattern::Binding(_)
+                )
+        });
+        if !exhaustive {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::NonExhaustiveMatch,
+                *expression.range(),
+            ));
+        }
+
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::Match {
+                value: Box::new(checked_value),
+                arms: checked_arms,
+            },
+            *expression.range(),
+        ))
+    }
+
+    fn check_match_arm(
+        &mut self,
+        arm: &ParsedMatchArm,
+        value_type: &Type,
+    ) -> TypecheckerResult<CheckedMatchArm> {
+        let pattern = match &arm.pattern {
+            ParsedPattern::Binding(name) if name.name() == DISCARD_NAME => CheckedPattern::Wildcard,
+            ParsedPattern::Binding(name) => {
+                if self.variable_exists(name.name()) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::VariableAlreadyDefined {
+                            name: name.name().to_string(),
+                        },
+                        name.token().range(),
+                    ));
+                }
+                let variable = CheckedVariable {
+                    name: name.name().to_string(),
+                    type_: value_type.clone(),
+                    mutable: false,
+                };
+                self.register_var_in_current_scope(variable.clone());
+                CheckedPattern::Binding(variable)
+            }
+            ParsedPattern::Value(pattern) => {
+                let checked_pattern = self.check_expression(pattern)?;
+                let is_constant = match checked_pattern.kind() {
+                    CheckedExpressionKind::Literal(_)
+                    | CheckedExpressionKind::EnumVariant { .. } => true,
+                    CheckedExpressionKind::PrefixOperator { expression, .. } => {
+                        matches!(expression.kind(), CheckedExpressionKind::Literal(_))
+                    }
+                    _ => false,
+                };
+                if !is_constant {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::InvalidPattern,
+                        *pattern.range(),
+                    ));
+                }
+
+                let pattern_type = self.expression_type(&checked_pattern)?;
+                if !value_type.accepts(&pattern_type) {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::TypeMismatch {
+                            expected: value_type.clone(),
+                            actual: pattern_type,
+                        },
+                        *pattern.range(),
+                    ));
+                }
+                CheckedPattern::Value(checked_pattern)
+            }
+        };
+
+        let guard = match &arm.guard {
+            Some(guard) => {
+                let checked_guard = self.check_expression(guard)?;
+                let guard_type = self.expression_type(&checked_guard)?;
+                if guard_type != Type::Boolean {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::TypeMismatch {
+                            expected: Type::Boolean,
+                            actual: guard_type,
+                        },
+                        *guard.range(),
+                    ));
+                }
+                Some(checked_guard)
+            }
+            None => None,
+        };
+
+        Ok(CheckedMatchArm {
+            pattern,
+            guard,
+            value: self.check_expression(&arm.value)?,
+        })
+    }
+
     fn check_literal_expression(
         &mut self,
         expression: &ParsedExpression,
@@ -769,46 +2186,269 @@ impl Typechecker {
         ))
     }
 
-    fn check_variable_expression(
+    fn check_variable_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let name_ident = match expression.kind() {
+            ParsedExpressionKind::Variable(name) => name,
+            _ => panic!("Expected variable expression"),
+        };
+
+        if name_ident.name() == DISCARD_NAME {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::DiscardRead,
+                name_ident.token().range(),
+            ));
+        }
+
+        if !self.variable_exists(name_ident.name()) {
+            // A function used by name becomes a function value.
+            if let Some(definition) = self.get_function_definition_by_name(name_ident.name()) {
+                return Ok(CheckedExpression::new(
+                    CheckedExpressionKind::Literal(Value::Function(definition.name)),
+                    *expression.range(),
+                ));
+            }
+
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::VariableNotDefined {
+                    name: name_ident.name().to_string(),
+                },
+                name_ident.token().range(),
+            ));
+        }
+
+        let checked_variable = self.check_variable(name_ident)?;
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::Variable(checked_variable),
+            *expression.range(),
+        ))
+    }
+
+    fn check_variable(&mut self, ident: &Identifier) -> TypecheckerResult<CheckedVariable> {
+        let variable = self.get_variable_by_name(ident.name());
+        if let Some(variable) = variable {
+            self.mark_read(ident.name());
+            Ok(variable)
+        } else {
+            Err(TypecheckerError::new(
+                TypecheckerErrorKind::VariableNotDefined {
+                    name: ident.name().to_string(),
+                },
+                ident.token().range(),
+            ))
+        }
+    }
+
+    fn check_function_call_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let function_call = match expression.kind() {
+            ParsedExpressionKind::FunctionCall(function_call) => function_call,
+            _ => panic!("Expected function call expression"),
+        };
+
+        if builtin::OPTIONAL_FUNCTIONS.contains(&function_call.name.name()) {
+            return self.check_optional_function_call(expression);
+        }
+
+        if function_call.name.name() == builtin::TYPEOF_FUNCTION {
+            return self.check_typeof_call(expression);
+        }
+
+        // A variable holding a function shadows a function with the same name.
+        if let Some(variable) = self.get_variable_by_name(function_call.name.name()) {
+            self.mark_read(function_call.name.name());
+            return self.check_variable_call(expression, variable);
+        }
+
+        let function_definition =
+            match self.get_function_definition_by_name(function_call.name.name()) {
+                Some(function_definition) => function_definition,
+                None => {
+                    return Err(TypecheckerError::new(
+                        TypecheckerErrorKind::FunctionNotDefined {
+                            name: function_call.name.name().to_string(),
+                        },
+                        *expression.range(),
+                    ))
+                }
+            };
+
+        self.warn_if_deprecated(&function_definition, *expression.range());
+
+        let parameter_types: Vec<_> = function_definition
+            .parameters
+            .iter()
+            .map(|parameter| parameter.type_.clone())
+            .collect();
+        let checked_arguments =
+            self.check_arguments(&function_call.arguments, &parameter_types, expression)?;
+
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::FunctionCall {
+                name: function_definition.name,
+                arguments: checked_arguments,
+            },
+            *expression.range(),
+        ))
+    }
+
+    /// Checks a call through a variable that holds a function value.
+    fn check_variable_call(
+        &mut self,
+        expression: &ParsedExpression,
+        variable: CheckedVariable,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let function_call = match expression.kind() {
+            ParsedExpressionKind::FunctionCall(function_call) => function_call,
+            _ => panic!("Expected function call expression"),
+        };
+
+        let Type::Function { parameters, .. } = &variable.type_ else {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::NotCallable {
+                    type_: variable.type_,
+                },
+                function_call.name.token().range(),
+            ));
+        };
+
+        let checked_arguments =
+            self.check_arguments(&function_call.arguments, parameters, expression)?;
+
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::VariableCall {
+                variable,
+                arguments: checked_arguments,
+            },
+            *expression.range(),
+        ))
+    }
+
+    /// Checks that the receiver of a method that modifies it is a variable.
+    fn check_mutable_receiver(
+        &self,
+        receiver: &CheckedExpression,
+        method_name: &str,
+    ) -> TypecheckerResult<()> {
+        match receiver.kind() {
+            CheckedExpressionKind::Variable(variable) if self.is_constant(&variable.name) => {
+                Err(TypecheckerError::new(
+                    TypecheckerErrorKind::ConstantAssignment {
+                        name: variable.name.clone(),
+                    },
+                    *receiver.range(),
+                ))
+            }
+            CheckedExpressionKind::Variable(variable) if !variable.mutable => {
+                Err(TypecheckerError::new(
+                    TypecheckerErrorKind::AssignmentToImmutable {
+                        name: variable.name.clone(),
+                    },
+                    *receiver.range(),
+                ))
+            }
+            CheckedExpressionKind::Variable(_) => Ok(()),
+            _ => Err(TypecheckerError::new(
+                TypecheckerErrorKind::TemporaryReceiver {
+                    method_name: method_name.to_string(),
+                },
+                *receiver.range(),
+            )),
+        }
+    }
+
+    /// Warns about a call to a function marked with `@deprecated`.
+    fn warn_if_deprecated(&mut self, definition: &CheckedFunctionDefinition, range: CodeRange) {
+        if let Some(message) = &definition.deprecation {
+            self.warnings.push(TypecheckerWarning::new(
+                TypecheckerWarningKind::DeprecatedFunction {
+                    name: definition.name.clone(),
+                    message: message.clone(),
+                },
+                range,
+            ));
+        }
+    }
+
+    fn check_arguments(
+        &mut self,
+        arguments: &[ParsedExpression],
+        parameter_types: &[Type],
+        call: &ParsedExpression,
+    ) -> TypecheckerResult<Vec<CheckedExpression>> {
+        if arguments.len() != parameter_types.len() {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::WrongArgumentCount {
+                    expected: parameter_types.len(),
+                    found: arguments.len(),
+                },
+                *call.range(),
+            ));
+        }
+
+        let mut checked_arguments = vec![];
+        for (argument, parameter_type) in arguments.iter().zip(parameter_types.iter()) {
+            let checked_argument = self.check_value_expression(argument)?;
+            let argument_type = self.expression_type(&checked_argument)?;
+            if !parameter_type.accepts(&argument_type) {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::TypeMismatch {
+                        expected: parameter_type.clone(),
+                        actual: argument_type,
+                    },
+                    *argument.range(),
+                ));
+            }
+            checked_arguments.push(checked_argument);
+        }
+        Ok(checked_arguments)
+    }
+
+    fn check_optional_function_call(
         &mut self,
         expression: &ParsedExpression,
     ) -> TypecheckerResult<CheckedExpression> {
-        let name_ident = match expression.kind() {
-            ParsedExpressionKind::Variable(name) => name,
-            _ => panic!("Expected variable expression"),
+        let function_call = match expression.kind() {
+            ParsedExpressionKind::FunctionCall(function_call) => function_call,
+            _ => panic!("Expected function call expression"),
         };
 
-        if !self.variable_exists(name_ident.name()) {
+        if function_call.arguments.len() != 1 {
             return Err(TypecheckerError::new(
-                TypecheckerErrorKind::VariableNotDefined {
-                    name: name_ident.name().to_string(),
+                TypecheckerErrorKind::WrongArgumentCount {
+                    expected: 1,
+                    found: function_call.arguments.len(),
                 },
-                name_ident.token().range(),
+                *expression.range(),
             ));
         }
 
-        let checked_variable = self.check_variable(name_ident)?;
+        let argument = &function_call.arguments[0];
+        let checked_argument = self.check_expression(argument)?;
+        match self.expression_type(&checked_argument)? {
+            Type::Optional(_) | Type::None => {}
+            actual => {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::ExpectedOptional { actual },
+                    *argument.range(),
+                ))
+            }
+        }
+
         Ok(CheckedExpression::new(
-            CheckedExpressionKind::Variable(checked_variable),
+            CheckedExpressionKind::FunctionCall {
+                name: function_call.name.name().to_string(),
+                arguments: vec![checked_argument],
+            },
             *expression.range(),
         ))
     }
 
-    fn check_variable(&mut self, ident: &Identifier) -> TypecheckerResult<CheckedVariable> {
-        let variable = self.get_variable_by_name(ident.name());
-        if let Some(variable) = variable {
-            Ok(variable)
-        } else {
-            Err(TypecheckerError::new(
-                TypecheckerErrorKind::VariableNotDefined {
-                    name: ident.name().to_string(),
-                },
-                ident.token().range(),
-            ))
-        }
-    }
-
-    fn check_function_call_expression(
+    fn check_typeof_call(
         &mut self,
         expression: &ParsedExpression,
     ) -> TypecheckerResult<CheckedExpression> {
@@ -817,40 +2457,20 @@ impl Typechecker {
             _ => panic!("Expected function call expression"),
         };
 
-        let function_definition =
-            match self.get_function_definition_by_name(function_call.name.name()) {
-                Some(function_definition) => function_definition,
-                None => {
-                    return Err(TypecheckerError::new(
-                        TypecheckerErrorKind::FunctionNotDefined {
-                            name: function_call.name.name().to_string(),
-                        },
-                        *expression.range(),
-                    ))
-                }
-            };
-
-        if function_call.arguments.len() != function_definition.parameters.len() {
+        if function_call.arguments.len() != 1 {
             return Err(TypecheckerError::new(
-                TypecheckerErrorKind::ArgumentCountMismatch {
-                    expected: function_definition.parameters.len(),
-                    actual: function_call.arguments.len(),
+                TypecheckerErrorKind::WrongArgumentCount {
+                    expected: 1,
+                    found: function_call.arguments.len(),
                 },
                 *expression.range(),
             ));
         }
 
-        let mut checked_arguments = vec![];
-        for argument in function_call.arguments.iter() {
-            let checked_argument = self.check_expression(argument)?;
-            checked_arguments.push(checked_argument);
-        }
-
+        let checked_argument = self.check_expression(&function_call.arguments[0])?;
+        let type_ = self.expression_type(&checked_argument)?;
         Ok(CheckedExpression::new(
-            CheckedExpressionKind::FunctionCall {
-                name: function_call.name.name().to_string(),
-                arguments: checked_arguments,
-            },
+            CheckedExpressionKind::Literal(Value::String(type_.to_string())),
             *expression.range(),
         ))
     }
@@ -932,7 +2552,45 @@ impl Typechecker {
         let left_type = self.expression_type(&checked_left)?;
         let right_type = self.expression_type(&checked_right)?;
 
-        if left_type != right_type {
+        if left_type == Type::Void || right_type == Type::Void {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::InvalidVoidExpression,
+                CodeRange::from_ranges(*left.range(), *right.range()),
+            ));
+        }
+
+        // `a < b < c` parses as `(a < b) < c`, which is either a confusing
+        // `bool` and `int` mismatch or, for `a == b == c` over bools, silently
+        // means something other than it reads. Explicit parentheses opt out.
+        if is_comparison_operator(*operator) {
+            let chained = [left, right]
+                .into_iter()
+                .filter(|side| !side.is_parenthesized())
+                .find_map(|side| match side.kind() {
+                    ParsedExpressionKind::InfixOperator { operator, .. }
+                        if is_comparison_operator(*operator) =>
+                    {
+                        Some(*operator)
+                    }
+                    _ => None,
+                });
+            if let Some(chained) = chained {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::ChainedComparison {
+                        first: chained,
+                        second: *operator,
+                    },
+                    CodeRange::from_ranges(*left.range(), *right.range()),
+                ));
+            }
+        }
+
+        let is_optional = |type_: &Type| matches!(type_, Type::Optional(_) | Type::None);
+        let is_optional_equality = matches!(
+            operator,
+            TokenKind::EqualsEquals | TokenKind::ExclamationMarkEquals
+        ) && optionals_comparable(&left_type, &right_type);
+        if !is_optional_equality && (left_type != right_type || is_optional(&left_type)) {
             return Err(TypecheckerError::new(
                 TypecheckerErrorKind::IncompatibleInfixSides {
                     left: left_type,
@@ -943,6 +2601,16 @@ impl Typechecker {
             ));
         }
 
+        if !infix_operator_supports(*operator, &left_type) {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::UnsupportedInfixOperator {
+                    operator: *operator,
+                    type_: left_type,
+                },
+                CodeRange::from_ranges(*left.range(), *right.range()),
+            ));
+        }
+
         Ok(CheckedExpression::new(
             CheckedExpressionKind::InfixOperator {
                 left: Box::new(checked_left),
@@ -957,13 +2625,17 @@ impl Typechecker {
         &mut self,
         expression: &ParsedExpression,
     ) -> TypecheckerResult<CheckedExpression> {
-        let (expression, call) = match expression.kind() {
+        let (receiver, call) = match expression.kind() {
             ParsedExpressionKind::MethodCall { expression, call } => (expression, call),
             _ => panic!("Expected method call expression"),
         };
 
-        let checked_expression = self.check_expression(expression)?;
-        let type_ = self.expression_type(&checked_expression)?;
+        if let Some(enum_name) = self.enum_named_by(receiver) {
+            return self.check_enum_function_call(enum_name, call, expression);
+        }
+
+        let checked_receiver = self.check_expression(receiver)?;
+        let type_ = self.expression_type(&checked_receiver)?;
 
         let function_definition = match self.get_method(&type_, call.name.name()) {
             Some(function_definition) => function_definition,
@@ -977,46 +2649,222 @@ impl Typechecker {
             }
         };
 
-        if call.arguments.len() != function_definition.parameters.len() {
+        self.warn_if_deprecated(&function_definition, call.name.token().range());
+
+        let parameter_types: Vec<_> = function_definition
+            .parameters
+            .iter()
+            .map(|parameter| parameter.type_.clone())
+            .collect();
+        let checked_arguments =
+            self.check_arguments(&call.arguments, &parameter_types, expression)?;
+
+        if builtin::method_mutates(&type_, call.name.name()) {
+            self.check_mutable_receiver(&checked_receiver, call.name.name())?;
+        }
+
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::MethodCall {
+                receiver: Box::new(checked_receiver),
+                type_,
+                method_name: call.name.name().to_string(),
+                arguments: checked_arguments,
+            },
+            *expression.range(),
+        ))
+    }
+
+    /// Checks a call on an enum itself, like `Status.from_int(404)`.
+    fn check_enum_function_call(
+        &mut self,
+        enum_name: String,
+        call: &ParsedFunctionCall,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        if call.name.name() != builtin::ENUM_FROM_INT_FUNCTION {
             return Err(TypecheckerError::new(
-                TypecheckerErrorKind::ArgumentCountMismatch {
-                    expected: function_definition.parameters.len(),
-                    actual: call.arguments.len(),
+                TypecheckerErrorKind::FunctionNotDefined {
+                    name: format!("{}.{}", enum_name, call.name.name()),
                 },
                 call.name.token().range(),
             ));
         }
 
-        let mut checked_arguments = vec![];
-        for argument in call.arguments.iter() {
-            let checked_argument = self.check_expression(argument)?;
-            checked_arguments.push(checked_argument);
+        let mut arguments = self.check_arguments(&call.arguments, &[Type::Integer], expression)?;
+        let discriminants = self.enums[&enum_name]
+            .iter()
+            .map(|(_, value)| *value)
+            .collect();
+        Ok(CheckedExpression::new(
+            CheckedExpressionKind::EnumFromInt {
+                enum_name,
+                discriminants,
+                value: Box::new(arguments.remove(0)),
+            },
+            *expression.range(),
+        ))
+    }
+
+    fn check_struct_expression(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let (name, fields) = match expression.kind() {
+            ParsedExpressionKind::Struct { name, fields } => (name, fields),
+            _ => panic!("Expected struct expression"),
+        };
+
+        let Some(declared_fields) = self.structs.get(name.name()).cloned() else {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::UnknownType {
+                    type_name: name.name().to_string(),
+                },
+                name.token().range(),
+            ));
+        };
+
+        let mut checked_fields: Vec<(String, CheckedExpression)> = vec![];
+        for field in fields.iter() {
+            let Some((_, type_)) = declared_fields
+                .iter()
+                .find(|(declared, _)| declared == field.name.name())
+            else {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::MemberNotDefined {
+                        type_: Type::Struct(name.name().to_string()),
+                        member: field.name.name().to_string(),
+                    },
+                    field.name.token().range(),
+                ));
+            };
+            if checked_fields
+                .iter()
+                .any(|(checked, _)| checked == field.name.name())
+            {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::FieldAlreadyDefined {
+                        name: field.name.name().to_string(),
+                    },
+                    field.name.token().range(),
+                ));
+            }
+
+            let value = self.check_value_expression(&field.value)?;
+            let actual = self.expression_type(&value)?;
+            if !type_.accepts(&actual) {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::TypeMismatch {
+                        expected: type_.clone(),
+                        actual,
+                    },
+                    *field.value.range(),
+                ));
+            }
+            checked_fields.push((field.name.name().to_string(), value));
+        }
+
+        if let Some((missing, _)) = declared_fields
+            .iter()
+            .find(|(declared, _)| !checked_fields.iter().any(|(name, _)| name == declared))
+        {
+            return Err(TypecheckerError::new(
+                TypecheckerErrorKind::MissingField {
+                    struct_name: name.name().to_string(),
+                    field: missing.clone(),
+                },
+                *expression.range(),
+            ));
         }
 
         Ok(CheckedExpression::new(
-            CheckedExpressionKind::MethodCall {
-                type_,
-                method_name: call.name.name().to_string(),
-                arguments: checked_arguments,
+            CheckedExpressionKind::Struct {
+                name: name.name().to_string(),
+                fields: checked_fields,
             },
             *expression.range(),
         ))
     }
 
+    fn check_member_access(
+        &mut self,
+        expression: &ParsedExpression,
+    ) -> TypecheckerResult<CheckedExpression> {
+        let (receiver, member) = match expression.kind() {
+            ParsedExpressionKind::MemberAccess { expression, member } => (expression, member),
+            _ => panic!("Expected member access expression"),
+        };
+
+        // The members of an enum are its variants.
+        if let Some(enum_name) = self.enum_named_by(receiver) {
+            let Some((_, value)) = self.enums[&enum_name]
+                .iter()
+                .find(|(variant, _)| variant == member.name())
+            else {
+                return Err(TypecheckerError::new(
+                    TypecheckerErrorKind::VariantNotDefined {
+                        enum_name,
+                        variant: member.name().to_string(),
+                    },
+                    member.token().range(),
+                ));
+            };
+            return Ok(CheckedExpression::new(
+                CheckedExpressionKind::EnumVariant {
+                    variant: member.name().to_string(),
+                    value: *value,
+                    enum_name,
+                },
+                *expression.range(),
+            ));
+        }
+
+        let checked_receiver = self.check_expression(receiver)?;
+        let type_ = self.expression_type(&checked_receiver)?;
+        if let Type::Struct(struct_name) = &type_ {
+            if self.structs[struct_name]
+                .iter()
+                .any(|(field, _)| field == member.name())
+            {
+                return Ok(CheckedExpression::new(
+                    CheckedExpressionKind::FieldAccess {
+                        expression: Box::new(checked_receiver),
+                        field: member.name().to_string(),
+                    },
+                    *expression.range(),
+                ));
+            }
+        }
+        Err(TypecheckerError::new(
+            TypecheckerErrorKind::MemberNotDefined {
+                type_,
+                member: member.name().to_string(),
+            },
+            member.token().range(),
+        ))
+    }
+
+    /// Returns the name of the enum that `expression` names, if it is the
+    /// bare name of an enum that no variable shadows.
+    fn enum_named_by(&mut self, expression: &ParsedExpression) -> Option<String> {
+        match expression.kind() {
+            ParsedExpressionKind::Variable(name)
+                if self.enums.contains_key(name.name()) && !self.variable_exists(name.name()) =>
+            {
+                Some(name.name().to_string())
+            }
+            _ => None,
+        }
+    }
+
     fn check_type(&mut self, type_name: &TypeName) -> TypecheckerResult<Type> {
-        match type_name.name() {
-            "void" => Ok(Type::Void),
-            "int" => Ok(Type::Integer),
-            "float" => Ok(Type::Float),
-            "string" => Ok(Type::String),
-            "bool" => Ok(Type::Boolean),
-            _ => Err(TypecheckerError::new(
+        type_from_name(type_name.name(), &self.enums, &self.structs).ok_or_else(|| {
+            TypecheckerError::new(
                 TypecheckerErrorKind::UnknownType {
                     type_name: type_name.name().to_string(),
                 },
                 type_name.token().range(),
-            )),
-        }
+            )
+        })
     }
 
     fn expression_type(&self, expression: &CheckedExpression) -> TypecheckerResult<Type> {
@@ -1026,8 +2874,29 @@ impl Typechecker {
                 Value::Float(_) => Ok(Type::Float),
                 Value::String(_) => Ok(Type::String),
                 Value::Boolean(_) => Ok(Type::Boolean),
+                Value::Void => Ok(Type::Void),
+                Value::None => Ok(Type::None),
+                Value::Array(_) => panic!("Arrays cannot be written as literals"),
+                Value::Tuple(_) => panic!("Tuples cannot be written as literals"),
+                Value::Struct(_) => panic!("Structs cannot be written as literals"),
+                Value::Function(name) => match self.get_function_definition_by_name(name) {
+                    Some(definition) => Ok(definition.function_type()),
+                    None => Err(TypecheckerError::new(
+                        TypecheckerErrorKind::FunctionNotDefined { name: name.clone() },
+                        *expression.range(),
+                    )),
+                },
             },
-            CheckedExpressionKind::Variable(variable) => Ok(variable.type_),
+            CheckedExpressionKind::Variable(variable) => Ok(variable.type_.clone()),
+            CheckedExpressionKind::FunctionCall { name, arguments }
+                if builtin::OPTIONAL_FUNCTIONS.contains(&name.as_str()) =>
+            {
+                match (name.as_str(), self.expression_type(&arguments[0])?) {
+                    ("unwrap", Type::Optional(inner)) => Ok(*inner),
+                    ("unwrap", _) => Ok(Type::None),
+                    _ => Ok(Type::Boolean),
+                }
+            }
             CheckedExpressionKind::FunctionCall { name, .. } => {
                 match self.get_function_definition_by_name(name) {
                     Some(function_definition) => Ok(function_definition.return_type),
@@ -1066,7 +2935,7 @@ impl Typechecker {
                 let left_type = self.expression_type(left)?;
                 let right_type = self.expression_type(right)?;
 
-                if left_type != right_type {
+                if left_type != right_type && !optionals_comparable(&left_type, &right_type) {
                     return Err(TypecheckerError::new(
                         TypecheckerErrorKind::TypeMismatch {
                             expected: left_type,
@@ -1085,7 +2954,7 @@ impl Typechecker {
                         Type::Integer => Ok(Type::Integer),
                         Type::Float => Ok(Type::Float),
                         Type::String => Ok(Type::String),
-                        Type::Boolean => Ok(Type::Boolean),
+                        array @ Type::Array(_) => Ok(array),
                         _ => panic!("Invalid infix operator"),
                     },
                     TokenKind::EqualsEquals
@@ -1101,10 +2970,39 @@ impl Typechecker {
                     _ => panic!("Invalid infix operator"),
                 }
             }
+            CheckedExpressionKind::VariableCall { variable, .. } => match &variable.type_ {
+                Type::Function { return_type, .. } => Ok(*return_type.clone()),
+                _ => panic!("Only function values can be called"),
+            },
+            CheckedExpressionKind::Ternary { then_branch, .. } => self.expression_type(then_branch),
+            CheckedExpressionKind::EnumVariant { enum_name, .. } => {
+                Ok(Type::Enum(enum_name.clone()))
+            }
+            CheckedExpressionKind::EnumFromInt { enum_name, .. } => {
+                Ok(Type::Optional(Box::new(Type::Enum(enum_name.clone()))))
+            }
+            CheckedExpressionKind::Match { arms, .. } => self.expression_type(&arms[0].value),
+            CheckedExpressionKind::Struct { name, .. } => Ok(Type::Struct(name.clone())),
+            CheckedExpressionKind::FieldAccess { expression, field } => {
+                let Type::Struct(name) = self.expression_type(expression)? else {
+                    panic!("Typechecker should have checked that only structs have fields");
+                };
+                let (_, type_) = self.structs[&name]
+                    .iter()
+                    .find(|(name, _)| name == field)
+                    .expect("Typechecker should have checked that the field exists");
+                Ok(type_.clone())
+            }
+            CheckedExpressionKind::Tuple(elements) => Ok(Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| self.expression_type(element))
+                    .collect::<TypecheckerResult<_>>()?,
+            )),
             CheckedExpressionKind::MethodCall {
                 type_, method_name, ..
             } => {
-                if self.methods.get(type_).is_some() {
+                if self.methods.contains_key(type_) {
                     if let Some(method) = self.get_method(type_, method_name) {
                         return Ok(method.return_type);
                     }
@@ -1112,7 +3010,7 @@ impl Typechecker {
 
                 Err(TypecheckerError::new(
                     TypecheckerErrorKind::MethodNotDefined {
-                        type_: *type_,
+                        type_: type_.clone(),
                         method_name: method_name.to_string(),
                     },
                     *expression.range(),
@@ -1122,11 +3020,38 @@ impl Typechecker {
     }
 
     fn push_scope(&mut self) {
-        self.scope_stack.push(Scope { variables: vec![] });
+        self.scope_stack.push(Scope::default());
     }
 
     fn pop_scope(&mut self) {
-        self.scope_stack.pop();
+        let Some(scope) = self.scope_stack.pop() else {
+            return;
+        };
+        for (name, range) in scope.unread {
+            self.warnings.push(TypecheckerWarning::new(
+                TypecheckerWarningKind::UnusedVariable { name },
+                range,
+            ));
+        }
+    }
+
+    /// Reports `name` as unused if its scope ends before it is read, unless
+    /// it starts with `_`.
+    fn track_unread(&mut self, name: &Identifier) {
+        if name.name().starts_with('_') {
+            return;
+        }
+        let current_scope = self.scope_stack.last_mut().unwrap();
+        current_scope
+            .unread
+            .push((name.name().to_string(), name.token().range()));
+    }
+
+    fn mark_read(&mut self, name: &str) {
+        // Variables can't be shadowed, so the name is declared only once.
+        for scope in self.scope_stack.iter_mut() {
+            scope.unread.retain(|(unread, _)| unread != name);
+        }
     }
 
     fn register_var_in_current_scope(&mut self, variable: CheckedVariable) {
@@ -1146,7 +3071,26 @@ impl Typechecker {
                 }
             }
         }
-        None
+        self.global_scope
+            .variables
+            .iter()
+            .find(|variable| variable.name == name)
+            .cloned()
+    }
+
+    /// Whether `name` is a constant. Variables can't shadow constants, so the
+    /// name never also refers to a local variable.
+    fn is_constant(&self, name: &str) -> bool {
+        self.global_scope
+            .variables
+            .iter()
+            .any(|variable| variable.name == name)
+    }
+
+    /// Makes a function that the host implements, named `name` with the
+    /// function type `signature`, callable from the checked program.
+    pub fn register_native(&mut self, name: &str, signature: Type) {
+        self.register_function(CheckedFunctionDefinition::from_signature(name, signature));
     }
 
     fn register_function(&mut self, function: CheckedFunctionDefinition) {
@@ -1154,11 +3098,18 @@ impl Typechecker {
     }
 
     fn register_method(&mut self, type_: Type, method: CheckedFunctionDefinition) {
-        let methods = self.methods.entry(type_).or_insert_with(HashMap::new);
+        let methods = self.methods.entry(type_).or_default();
         methods.insert(method.name.clone(), method);
     }
 
-    fn get_function_definition_by_name(&self, name: &str) -> Option<CheckedFunctionDefinition> {
+    pub fn get_function_definition_by_name(&self, name: &str) -> Option<CheckedFunctionDefinition> {
+        // Nested functions shadow the functions declared outside of them.
+        let name = self
+            .scope_stack
+            .iter()
+            .rev()
+            .find_map(|scope| scope.functions.get(name))
+            .map_or(name, |registered_name| registered_name.as_str());
         self.functions.get(name).cloned()
     }
 
@@ -1167,3 +3118,210 @@ impl Typechecker {
         methods.get(name).cloned()
     }
 }
+
+/// Resolves a type name such as `int`, `[string]` or `float?`, where `enums`
+/// and `structs` hold the declared enums and structs. Returns `None` for unknown names and for `void`
+/// used as an element or optional type.
+fn type_from_name(
+    name: &str,
+    enums: &HashMap<String, Vec<(String, i64)>>,
+    structs: &HashMap<String, Vec<(String, Type)>>,
+) -> Option<Type> {
+    // Checked first, so the `?` in `fn() -> int?` belongs to the return type.
+    if let Some(signature) = name.strip_prefix("fn(") {
+        // `signature` is the rest of `fn(int, [string]) -> bool`.
+        let (parameters, return_type) = split_top_level(signature, ')');
+        let return_type = return_type?.strip_prefix(" -> ")?;
+        let mut parameter_types = vec![];
+        let mut rest = Some(parameters);
+        while let Some(parameters) = rest.filter(|parameters| !parameters.is_empty()) {
+            let (parameter, remainder) = split_top_level(parameters, ',');
+            match type_from_name(parameter.trim(), enums, structs)? {
+                Type::Void => return None,
+                parameter => parameter_types.push(parameter),
+            }
+            rest = remainder;
+        }
+        return Some(Type::Function {
+            parameters: parameter_types,
+            return_type: Box::new(type_from_name(return_type, enums, structs)?),
+        });
+    }
+
+    if let Some(inner) = name.strip_suffix('?') {
+        return match type_from_name(inner, enums, structs)? {
+            Type::Void => None,
+            inner => Some(Type::Optional(Box::new(inner))),
+        };
+    }
+
+    if let Some(elements) = name
+        .strip_prefix('(')
+        .and_then(|name| name.strip_suffix(')'))
+    {
+        let mut element_types = vec![];
+        let mut rest = Some(elements);
+        while let Some(elements) = rest {
+            let (element, remainder) = split_top_level(elements, ',');
+            match type_from_name(element.trim(), enums, structs)? {
+                Type::Void => return None,
+                element => element_types.push(element),
+            }
+            rest = remainder;
+        }
+        // `(int)` is just `int` in parentheses, not a tuple.
+        return (element_types.len() > 1).then_some(Type::Tuple(element_types));
+    }
+
+    if let Some(element) = name
+        .strip_prefix('[')
+        .and_then(|name| name.strip_suffix(']'))
+    {
+        return match type_from_name(element, enums, structs)? {
+            Type::Void => None,
+            element => Some(Type::Array(Box::new(element))),
+        };
+    }
+
+    match name {
+        "void" => Some(Type::Void),
+        "int" => Some(Type::Integer),
+        "float" => Some(Type::Float),
+        "string" => Some(Type::String),
+        "bool" => Some(Type::Boolean),
+        _ if enums.contains_key(name) => Some(Type::Enum(name.to_string())),
+        _ if structs.contains_key(name) => Some(Type::Struct(name.to_string())),
+        _ => None,
+    }
+}
+
+/// The value of an enum variant, which has to be an integer literal.
+fn discriminant(expression: &ParsedExpression) -> Option<i64> {
+    match expression.kind() {
+        ParsedExpressionKind::Literal(Value::Integer(value)) => Some(*value),
+        ParsedExpressionKind::PrefixOperator {
+            operator,
+            expression,
+        } => match (operator, expression.kind()) {
+            (PrefixOperator::Minus, ParsedExpressionKind::Literal(Value::Integer(value))) => {
+                value.checked_neg()
+            }
+            (PrefixOperator::Plus, ParsedExpressionKind::Literal(Value::Integer(value))) => {
+                Some(*value)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `operator` can be used on two values of `type_`. Enums can only be
+/// compared for equality, and arrays can only be concatenated.
+fn infix_operator_supports(operator: TokenKind, type_: &Type) -> bool {
+    match operator {
+        TokenKind::Plus => matches!(
+            type_,
+            Type::Integer | Type::Float | Type::String | Type::Array(_)
+        ),
+        TokenKind::Minus
+        | TokenKind::Asterisk
+        | TokenKind::Slash
+        | TokenKind::Percent
+        | TokenKind::LessThan
+        | TokenKind::LessThanEquals
+        | TokenKind::GreaterThan
+        | TokenKind::GreaterThanEquals => matches!(type_, Type::Integer | Type::Float),
+        TokenKind::EqualsEquals | TokenKind::ExclamationMarkEquals => !matches!(
+            type_,
+            Type::Array(_) | Type::Function { .. } | Type::Tuple(_) | Type::Struct(_)
+        ),
+        TokenKind::AmpersandAmpersand | TokenKind::PipePipe => *type_ == Type::Boolean,
+        _ => false,
+    }
+}
+
+/// The names a constant value refers to. Constant values can only contain
+/// literals, names and prefix operators.
+fn constant_dependencies(expression: &ParsedExpression) -> Vec<&Identifier> {
+    match expression.kind() {
+        ParsedExpressionKind::Variable(name) => vec![name],
+        ParsedExpressionKind::PrefixOperator { expression, .. } => {
+            constant_dependencies(expression)
+        }
+        _ => vec![],
+    }
+}
+
+/// Splits `text` at the first `separator` that is not nested in brackets or
+/// parentheses, returning the text before it and the text after it, if any.
+fn split_top_level(text: &str, separator: char) -> (&str, Option<&str>) {
+    let mut depth = 0;
+    for (index, char) in text.char_indices() {
+        match char {
+            _ if char == separator && depth == 0 => {
+                return (&text[..index], Some(&text[index + 1..]));
+            }
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    (text, None)
+}
+
+/// Whether control never continues past `statement`, because it returns on
+/// every path or loops forever.
+fn statement_terminates(statement: &CheckedStatement) -> bool {
+    match statement.kind() {
+        CheckedStatementKind::Return { .. }
+        | CheckedStatementKind::Break
+        | CheckedStatementKind::Continue => true,
+        CheckedStatementKind::If {
+            then_body,
+            else_body: Some(else_body),
+            ..
+        }
+        | CheckedStatementKind::IfLet {
+            then_body,
+            else_body: Some(else_body),
+            ..
+        } => block_terminates(then_body) && block_terminates(else_body),
+        // A `loop` can only be left by returning or breaking out of it.
+        CheckedStatementKind::Loop { block } => !block_breaks(block),
+        _ => false,
+    }
+}
+
+fn block_terminates(block: &[CheckedStatement]) -> bool {
+    block.iter().any(statement_terminates)
+}
+
+/// Whether `block` contains a `break` that leaves the loop it is the body of.
+/// A `break` in a nested loop only leaves that nested loop.
+fn block_breaks(block: &[CheckedStatement]) -> bool {
+    block.iter().any(|statement| match statement.kind() {
+        CheckedStatementKind::Break => true,
+        CheckedStatementKind::If {
+            then_body,
+            else_body,
+            ..
+        }
+        | CheckedStatementKind::IfLet {
+            then_body,
+            else_body,
+            ..
+        } => block_breaks(then_body) || else_body.as_deref().is_some_and(block_breaks),
+        _ => false,
+    })
+}
+
+/// Whether `left` and `right` can be compared with `==` and `!=`: `none` can be
+/// compared with any optional, and optionals with the same inner type can be
+/// compared with each other.
+fn optionals_comparable(left: &Type, right: &Type) -> bool {
+    match (left, right) {
+        (Type::Optional(_) | Type::None, Type::None) | (Type::None, Type::Optional(_)) => true,
+        (Type::Optional(left), Type::Optional(right)) => left == right,
+        _ => false,
+    }
+}
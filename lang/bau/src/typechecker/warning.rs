@@ -0,0 +1,54 @@
+use crate::error::print_warning;
+use crate::source::{CodeRange, Source};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypecheckerWarningKind {
+    DeprecatedFunction { name: String, message: String },
+    UnreachableCode,
+    UnusedVariable { name: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypecheckerWarning {
+    kind: TypecheckerWarningKind,
+    range: CodeRange,
+}
+
+impl TypecheckerWarning {
+    pub fn new(kind: TypecheckerWarningKind, range: CodeRange) -> Self {
+        Self { kind, range }
+    }
+
+    pub fn kind(&self) -> &TypecheckerWarningKind {
+        &self.kind
+    }
+
+    pub fn range(&self) -> &CodeRange {
+        &self.range
+    }
+
+    pub fn print(&self, source: &Source) {
+        print_warning(source, Some(&self.range), &self.to_string());
+    }
+}
+
+impl std::fmt::Display for TypecheckerWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let str = match &self.kind {
+            TypecheckerWarningKind::DeprecatedFunction { name, message } => {
+                if message.is_empty() {
+                    format!("Function `{}` is deprecated", name)
+                } else {
+                    format!("Function `{}` is deprecated: {}", name, message)
+                }
+            }
+            TypecheckerWarningKind::UnreachableCode => "Unreachable code".to_string(),
+            TypecheckerWarningKind::UnusedVariable { name } => format!(
+                "Variable `{}` is never read, prefix it with `_` if that is intended",
+                name
+            ),
+        };
+
+        write!(f, "{}", str)
+    }
+}
@@ -3,13 +3,56 @@ use bau::interpreter::value::Value;
 #[macro_export]
 macro_rules! should_run_and_return_value {
     ($value:expr, $code:literal) => {
-        let bau = bau::Bau::new();
-        let result = bau.run($code);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), $value);
+        let expected: Option<Value> = $value;
+        assert_eq!(run_for_value(&expected, $code), expected);
     };
 }
 
+/// Runs `code` and returns the value it produces. `main` can only return an
+/// `int` or nothing, so values of other types are passed from `main` to the
+/// native `expect` function, which takes a value of the expected value's type.
+fn run_for_value(expected: &Option<Value>, code: &str) -> Option<Value> {
+    let Some(expected @ (Value::Float(_) | Value::Boolean(_) | Value::String(_) | Value::Array(_))) =
+        expected
+    else {
+        return bau::Bau::new().run(code).unwrap();
+    };
+
+    let actual = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let expect = {
+        let actual = std::sync::Arc::clone(&actual);
+        move |arguments: &[Value]| {
+            *actual.lock().unwrap() = Some(arguments[0].clone());
+            Ok(None)
+        }
+    };
+    let bau = bau::Bau::new().with_native_function(
+        "expect",
+        bau::typechecker::Type::Function {
+            parameters: vec![type_of(expected)],
+            return_type: Box::new(bau::typechecker::Type::Void),
+        },
+        Box::new(expect),
+    );
+    assert_eq!(bau.run(code), Ok(None));
+    let actual = actual.lock().unwrap().take();
+    actual
+}
+
+/// The type of a value that a test expects, where arrays are not empty.
+fn type_of(value: &Value) -> bau::typechecker::Type {
+    use bau::typechecker::Type;
+
+    match value {
+        Value::Integer(_) => Type::Integer,
+        Value::Float(_) => Type::Float,
+        Value::Boolean(_) => Type::Boolean,
+        Value::String(_) => Type::String,
+        Value::Array(values) => Type::Array(Box::new(type_of(&values[0]))),
+        _ => panic!("Tests can't expect a value like `{}`", value),
+    }
+}
+
 #[test]
 fn fibonaci() {
     should_run_and_return_value!(
@@ -21,9 +64,9 @@ fn fibonaci() {
         }
 
         fn fibonacci(int n) -> int {
-            let int a = 0;
-            let int b = 1;
-            let int i = 0;
+            let mut int a = 0;
+            let mut int b = 1;
+            let mut int i = 0;
             while i < n {
                 let int next = a + b;
                 a = b;
@@ -48,8 +91,8 @@ fn factorial() {
         }
 
         fn factorial(int n) -> int {
-            let int result = 1;
-            let int i = 1;
+            let mut int result = 1;
+            let mut int i = 1;
             while i <= n {
                 result *= i;
                 i += 1;
@@ -61,19 +104,50 @@ fn factorial() {
     );
 }
 
+#[test]
+fn compound_assignment() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            let mut int i = 20;
+            i -= 3;
+            i /= 2;
+            i %= 5;
+            let mut float f = 1.5;
+            f *= 3.0;
+            f -= 0.5;
+            let mut bool b = true;
+            b &&= false;
+            return i == 3 && f == 4.0 && !b;
+        }
+    "#
+    );
+}
+
 #[test]
 fn fizzbuzz() {
     should_run_and_return_value!(
-        Some(Value::String("FizzBuzz".to_string())),
+        Some(Value::String(
+            "12Fizz4BuzzFizz78FizzBuzz11Fizz1314FizzBuzz".to_string()
+        )),
         r#"
-        fn main() -> string {
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> string {
             let string result = fizzbuzz(15);
             return result;
         }
 
         fn fizzbuzz(int n) -> string {
-            let string result = "";
-            let int i = 1;
+            let mut string result = "";
+            let mut int i = 1;
             while i <= n {
                 if i % 3 == 0 {
                     result.append("Fizz");
@@ -92,3 +166,4207 @@ fn fizzbuzz() {
     "#
     );
 }
+
+#[test]
+fn token_dump() {
+    let tokens = bau::tokenizer::Tokenizer::new("let int a = 1; // one\na && b;")
+        .tokenize()
+        .iter()
+        .map(|token| token.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            "Let 0..3 0:0",
+            "Whitespace 3..4 0:3",
+            "Identifier 4..7 0:4",
+            "Whitespace 7..8 0:7",
+            "Identifier 8..9 0:8",
+            "Whitespace 9..10 0:9",
+            "Equals 10..11 0:10",
+            "Whitespace 11..12 0:11",
+            "IntLiteral 12..13 0:12",
+            "Semicolon 13..14 0:13",
+            "Whitespace 14..15 0:14",
+            "Comment 15..21 0:15",
+            "Whitespace 21..22 0:21",
+            "Identifier 22..23 1:0",
+            "Whitespace 23..24 1:1",
+            "AmpersandAmpersand 24..26 1:2",
+            "Whitespace 26..27 1:4",
+            "Identifier 27..28 1:5",
+            "Semicolon 28..29 1:6",
+            "EndOfFile 29..29 1:7",
+        ]
+    );
+}
+
+#[test]
+fn pow_negative_exponent() {
+    should_run_and_return_value!(
+        Some(Value::Float(0.5)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> float {
+            return pow(2.0, -1);
+        }
+    "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            return pow(2.0, -2) == 0.25;
+        }
+    "#
+    );
+    // An int base has to go through `ipow`.
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let float half = pow(2, -1);
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Float,
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn ipow_negative_exponent() {
+    should_run_and_return_value!(
+        Some(Value::Integer(1024)),
+        r#"
+        fn main() -> int {
+            return ipow(2, 10);
+        }
+    "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return ipow(2, -1);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::interpreter::error::ExecutionErrorKind::NegativeExponent { exponent: -1 }
+    );
+    assert_eq!(error.range().unwrap().coords.line, 2);
+    assert_eq!(error.range().unwrap().coords.column, 19);
+
+    assert_eq!(
+        execution_error_kind(bau::Bau::new(), "fn main() -> int { return ipow(10, 19); }"),
+        bau::interpreter::error::ExecutionErrorKind::PowerOverflow {
+            base: 10,
+            exponent: 19,
+        }
+    );
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new(),
+            "fn main() -> int { return ipow(1, 4294967296); }"
+        ),
+        bau::interpreter::error::ExecutionErrorKind::ExponentOutOfRange {
+            exponent: 4294967296,
+        }
+    );
+}
+
+#[test]
+fn discard_binding() {
+    should_run_and_return_value!(
+        Some(Value::Integer(3)),
+        r#"
+        fn main() -> int {
+            let int _ = side_effect();
+            let int _ = side_effect();
+            return 3;
+        }
+
+        fn side_effect() -> int {
+            return 1;
+        }
+    "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            let int _ = 1;
+            return _;
+        }
+    "#,
+        )
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        bau::error::BauError::TypecheckerError(error)
+            if error.to_string() == "`_` is a discard and cannot be read"
+    ));
+}
+
+#[test]
+fn deprecated_function_warning() {
+    let (result, warnings) = bau::Bau::new().run_with_warnings(
+        r#"
+        fn main() -> int {
+            let int a = old(1);
+            let int b = new(2);
+            return a + b;
+        }
+
+        @deprecated("use `new` instead")
+        fn old(int n) -> int {
+            return n;
+        }
+
+        fn new(int n) -> int {
+            return n;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(3))));
+    assert_eq!(warnings.len(), 1);
+    let bau::error::BauWarning::TypecheckerWarning(warning) = &warnings[0] else {
+        panic!("expected a warning in the program");
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Function `old` is deprecated: use `new` instead"
+    );
+}
+
+#[test]
+fn dump_scopes_on_error() {
+    let errors = bau::Bau::new()
+        .with_dump_scopes_on_error(true)
+        .run(
+            r#"
+        fn main() -> int {
+            let int base = 2;
+            return power(base, -1);
+        }
+
+        fn power(int base, int exponent) -> int {
+            let int result = ipow(base, exponent);
+            return result;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.scope_dump(),
+        Some("scope 0:\n    base = 2\nscope 1:\n    base = 2\n    exponent = -1\n")
+    );
+}
+
+fn format(input: &str) -> String {
+    let source = bau::source::Source::new(input);
+    bau::formatter::Formatter::new(&source).format().unwrap()
+}
+
+#[test]
+fn format_source() {
+    let input = r#"// Adds two numbers.
+@deprecated("use `+`")
+fn   add(int a,int b)->int{return a+b;} // one-liner
+
+
+fn main() -> int {
+    let int x=(1+2)*3; // nine
+    let int y = 1 - (2 - 3);
+
+
+    // compare
+    if x<y{x+=1;}else{
+        y = -(x + 1);
+    }
+    while x < 10 { x += 1; }
+    return add(x, (x + y).to_string().len());
+}
+extend int {
+    fn double() -> int { return self * 2; }
+}
+"#;
+    let expected = r#"// Adds two numbers.
+@deprecated("use `+`")
+fn add(int a, int b) -> int {
+    return a + b;
+} // one-liner
+
+fn main() -> int {
+    let int x = (1 + 2) * 3; // nine
+    let int y = 1 - (2 - 3);
+
+    // compare
+    if x < y {
+        x += 1;
+    } else {
+        y = -(x + 1);
+    }
+    while x < 10 {
+        x += 1;
+    }
+    return add(x, (x + y).to_string().len());
+}
+
+extend int {
+    fn double() -> int {
+        return self * 2;
+    }
+}
+"#;
+    assert_eq!(format(input), expected);
+}
+
+#[test]
+fn format_is_idempotent() {
+    let input = r#"
+fn main() -> int {
+    let int a = 1 - 2 - 3;   // left-associative
+    let int b = 1 - (2 - 3);
+    let int c = a + (b.to_string().len());
+    let bool d = !(a == b) || (a < b && b < c);
+
+    // trailing block comment
+    loop { return a; }
+}
+"#;
+    let once = format(input);
+    assert_eq!(format(&once), once);
+}
+
+#[test]
+fn format_comments_inside_statements() {
+    let input = r#"fn main() -> int {
+    let int x = 1 + /* inline */ 2;
+    defer print(/* message */ "done");
+    return foo(1, // first
+        2);
+}
+"#;
+    let expected = r#"fn main() -> int {
+    let int x = 1 + /* inline */ 2;
+    defer print(/* message */ "done");
+    // first
+    return foo(1, 2);
+}
+"#;
+    assert_eq!(format(input), expected);
+    assert_eq!(format(expected), expected);
+}
+
+#[test]
+fn chained_comparison() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            let int a = 1;
+            if a < 2 < 3 {
+                return 1;
+            }
+            return 0;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Comparison operators cannot be chained: write `a < b && b < c` instead"
+    );
+}
+
+#[test]
+fn chained_equality_of_bools() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            let bool a = true;
+            let bool b = true;
+            let bool c = false;
+            return a == b == c;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ChainedComparison {
+            first: bau::tokenizer::token::TokenKind::EqualsEquals,
+            second: bau::tokenizer::token::TokenKind::EqualsEquals,
+        }
+    );
+}
+
+#[test]
+fn parenthesized_comparison_is_not_chained() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            let int a = 1;
+            let int b = 2;
+            let bool c = true;
+            return (a < b) == c;
+        }
+    "#
+    );
+    assert_eq!(
+        format("fn main() -> bool {\n    return (1 < 2) == (3 > 4);\n}\n"),
+        "fn main() -> bool {\n    return (1 < 2) == (3 > 4);\n}\n"
+    );
+}
+
+#[test]
+fn integer_overflow() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            let int big = 9223372036854775807;
+            return big * 2;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::interpreter::error::ExecutionErrorKind::IntegerOverflow {
+            left: 9223372036854775807,
+            operator: bau::tokenizer::token::TokenKind::Asterisk,
+            right: 2,
+        }
+    );
+}
+
+#[test]
+fn integer_overflow_wrapping() {
+    let result = bau::Bau::new().with_wrapping(true).run(
+        r#"
+        fn main() -> int {
+            let mut int big = 9223372036854775807;
+            big += 1;
+            return big * 2;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(0))));
+}
+
+fn execution_error_kind(bau: bau::Bau, code: &str) -> bau::interpreter::error::ExecutionErrorKind {
+    let errors = bau.run(code).unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    error.kind().clone()
+}
+
+#[test]
+fn integer_division_by_zero() {
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new(),
+            r#"
+        fn main() -> int {
+            let int a = 0;
+            return 1 / a;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::DivisionByZero {
+            left: 1,
+            operator: bau::tokenizer::token::TokenKind::Slash,
+        }
+    );
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new().with_wrapping(true),
+            r#"
+        fn main() -> int {
+            return 5 % 0;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::DivisionByZero {
+            left: 5,
+            operator: bau::tokenizer::token::TokenKind::Percent,
+        }
+    );
+}
+
+#[test]
+fn integer_division_overflow() {
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new(),
+            r#"
+        fn main() -> int {
+            let int min = -9223372036854775807 - 1;
+            return min / -1;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::IntegerOverflow {
+            left: i64::MIN,
+            operator: bau::tokenizer::token::TokenKind::Slash,
+            right: -1,
+        }
+    );
+    assert_eq!(
+        bau::Bau::new().with_wrapping(true).run(
+            r#"
+        fn main() -> int {
+            let int min = -9223372036854775807 - 1;
+            return min / -1;
+        }
+    "#
+        ),
+        Ok(Some(Value::Integer(i64::MIN)))
+    );
+}
+
+#[test]
+fn integer_negation_overflow() {
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new(),
+            r#"
+        fn main() -> int {
+            let int min = -9223372036854775807 - 1;
+            return -min;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::NegationOverflow { value: i64::MIN }
+    );
+    assert_eq!(
+        bau::Bau::new().with_wrapping(true).run(
+            r#"
+        fn main() -> int {
+            let int min = -9223372036854775807 - 1;
+            return -min;
+        }
+    "#
+        ),
+        Ok(Some(Value::Integer(i64::MIN)))
+    );
+}
+
+#[test]
+fn constants() {
+    should_run_and_return_value!(
+        Some(Value::Integer(110)),
+        r#"
+        const int MAX = 100;
+        const int LIMIT = MAX;
+        const int OFFSET = -10;
+
+        fn main() -> int {
+            return LIMIT - OFFSET;
+        }
+    "#
+    );
+}
+
+#[test]
+fn constant_assignment() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        const int MAX = 100;
+
+        fn main() -> int {
+            MAX = 1;
+            return MAX;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(error.to_string(), "Cannot assign to constant `MAX`");
+}
+
+#[test]
+fn constants_cannot_be_shadowed() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        const int MAX = 100;
+
+        fn main() -> int {
+            let int MAX = 1;
+            return MAX;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::VariableAlreadyDefined {
+            name: "MAX".to_string()
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        const int MAX = 100;
+
+        fn main() -> int {
+            return limit(1);
+        }
+
+        fn limit(int MAX) -> int {
+            return MAX;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::VariableAlreadyDefined {
+            name: "MAX".to_string()
+        }
+    );
+}
+
+#[test]
+fn assignment_to_immutable() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let int x = 1;
+            x += 1;
+            return x;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::AssignmentToImmutable {
+            name: "x".to_string()
+        }
+    );
+}
+
+#[test]
+fn assignment_to_mutable() {
+    should_run_and_return_value!(
+        Some(Value::Integer(3)),
+        r#"
+        fn main() -> int {
+            let mut int x = 1;
+            x = x + 2;
+            return x;
+        }
+    "#
+    );
+}
+
+#[test]
+fn non_constant_expression() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        const int MAX = 10 * 10;
+
+        fn main() -> int {
+            return MAX;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Constant values can only contain literals and other constants"
+    );
+}
+
+#[test]
+fn recover_from_malformed_items() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn broken(int) -> int {
+            return 1;
+        }
+
+        fn main() -> int {
+            return 0;
+        }
+
+        fn also_broken() int {
+            return 2;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let messages = errors
+        .iter()
+        .map(|error| match error {
+            bau::error::BauError::ParserError(error) => error.to_string(),
+            _ => panic!("Expected a parser error"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        vec![
+            "Expected token `identifier`, but found `)` instead",
+            "Expected token `->`, but found `identifier` instead",
+        ]
+    );
+
+    let errors = bau::Bau::new()
+        .run("let int x = 1;\nfn main() -> int { return 0; }")
+        .unwrap_err();
+    let bau::error::BauError::ParserError(error) = &errors[0] else {
+        panic!("Expected a parser error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Expected an item (`fn`, `extend`, `trait`, `enum`, `struct`, `const` or an `@` attribute), but found `let` instead"
+    );
+}
+
+#[test]
+fn trait_implementation() {
+    should_run_and_return_value!(
+        Some(Value::Integer(0)),
+        r#"
+        trait Describe {
+            fn describe(int value) -> string;
+        }
+
+        extend int: Describe {
+            fn describe(int value) -> string {
+                return "an int";
+            }
+        }
+
+        fn main() -> int {
+            return 0;
+        }
+    "#
+    );
+}
+
+#[test]
+fn trait_missing_method() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        trait Describe {
+            fn describe(int value) -> string;
+            fn name() -> string;
+        }
+
+        extend int: Describe {
+            fn describe(int value) -> string {
+                return "an int";
+            }
+        }
+
+        fn main() -> int {
+            return 0;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Method `name` required by trait `Describe` is not implemented"
+    );
+}
+
+#[test]
+fn trait_method_mismatch() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        trait Describe {
+            fn describe(int value) -> string;
+        }
+
+        extend int: Describe {
+            fn describe(int value) -> int {
+                return value;
+            }
+        }
+
+        fn main() -> int {
+            return 0;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Method `describe` does not match trait `Describe`, expected `fn describe(int value) -> string`"
+    );
+}
+
+fn typechecker_error_kind(code: &str) -> bau::typechecker::error::TypecheckerErrorKind {
+    let errors = bau::Bau::new().run(code).unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    error.kind().clone()
+}
+
+#[test]
+fn too_few_arguments() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return count_of("a");
+        }
+
+        fn count_of(string text, int count) -> int {
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::WrongArgumentCount {
+            expected: 2,
+            found: 1,
+        }
+    );
+}
+
+#[test]
+fn too_many_arguments() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return count_of("a", 2, true);
+        }
+
+        fn count_of(string text, int count) -> int {
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::WrongArgumentCount {
+            expected: 2,
+            found: 3,
+        }
+    );
+}
+
+#[test]
+fn wrong_argument_type() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return count_of(2, "a");
+        }
+
+        fn count_of(string text, int count) -> int {
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::String,
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn function_already_defined() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return 0;
+        }
+
+        fn repeat(string text, int count) -> int {
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::FunctionAlreadyDefined {
+            name: "repeat".to_string(),
+        }
+    );
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return 0;
+        }
+
+        fn unwrap(int? value) -> int {
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::FunctionAlreadyDefined {
+            name: "unwrap".to_string(),
+        }
+    );
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return helper();
+        }
+
+        fn helper() -> int {
+            return 1;
+        }
+
+        fn helper() -> int {
+            return 2;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::FunctionAlreadyDefined {
+            name: "helper".to_string(),
+        }
+    );
+}
+
+#[test]
+fn void_function_call_statement() {
+    let result = bau::Bau::new().run(
+        r#"
+        fn main() -> int {
+            log("hello");
+            print("world");
+            return 3;
+        }
+
+        fn log(string message) -> void {
+            print(message);
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(bau::interpreter::value::Value::Integer(3))));
+}
+
+#[test]
+fn void_in_arithmetic() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {
+            let int x = log() + log();
+        }
+
+        fn log() -> void {
+            print("hello");
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::InvalidVoidExpression
+    );
+}
+
+#[test]
+fn void_as_value() {
+    for code in [
+        r#"fn main() -> void { let int x = print("a"); }"#,
+        r#"fn main() -> void { let void x = print("a"); }"#,
+        r#"fn main() -> void { let mut int x = 1; x = print("a"); }"#,
+        r#"fn main() -> int { return print("a"); }"#,
+        r#"fn main() -> void { take(print("a")); } fn take(int a) -> void {}"#,
+    ] {
+        assert_eq!(
+            typechecker_error_kind(code),
+            bau::typechecker::error::TypecheckerErrorKind::InvalidVoidExpression,
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn optional_is_some() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            let int? x = 3;
+            return is_some(x);
+        }
+    "#
+    );
+}
+
+#[test]
+fn optional_is_none() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            return is_none(none);
+        }
+    "#
+    );
+}
+
+#[test]
+fn optional_unwrap() {
+    should_run_and_return_value!(
+        Some(Value::Integer(4)),
+        r#"
+        fn main() -> int {
+            let int? x = 3;
+            return unwrap(x) + 1;
+        }
+    "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            let int? x = none;
+            return unwrap(x);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::interpreter::error::ExecutionErrorKind::UnwrapNone
+    );
+    assert_eq!(error.range().unwrap().coords.line, 3);
+    assert_eq!(error.range().unwrap().coords.column, 19);
+}
+
+#[test]
+fn optional_expected() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            return is_some(3);
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ExpectedOptional {
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn logical_compound_assignment() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(false)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            let mut bool flag = true;
+            flag &&= false;
+            return flag;
+        }
+    "#
+    );
+
+    // `expensive` would abort execution if `||=` evaluated it.
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            let mut bool flag = true;
+            flag ||= expensive();
+            return flag;
+        }
+
+        fn expensive() -> bool {
+            let int _ = ipow(2, -1);
+            return false;
+        }
+    "#
+    );
+}
+
+#[test]
+fn logical_compound_assignment_type() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let mut int count = 1;
+            count ||= true;
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Boolean,
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn nested_block_comment() {
+    let tokens = bau::tokenizer::Tokenizer::new("a /* one /* two */\n three */ b")
+        .tokenize()
+        .iter()
+        .map(|token| token.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            "Identifier 0..1 0:0",
+            "Whitespace 1..2 0:1",
+            "Comment 2..28 0:2",
+            "Whitespace 28..29 1:9",
+            "Identifier 29..30 1:10",
+            "EndOfFile 30..30 1:11",
+        ]
+    );
+}
+
+#[test]
+fn unterminated_block_comment() {
+    let tokens = bau::tokenizer::Tokenizer::new("a /* one /* two */\n b")
+        .tokenize()
+        .iter()
+        .map(|token| token.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            "Identifier 0..1 0:0",
+            "Whitespace 1..2 0:1",
+            "Invalid 2..21 0:2",
+            "EndOfFile 21..21 1:2",
+        ]
+    );
+}
+
+#[test]
+fn errors_sorted_by_position() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return missing();
+        }
+
+        const int LIMIT = true;
+    "#,
+        )
+        .unwrap_err();
+    let lines = errors
+        .iter()
+        .map(|error| error.range().unwrap().coords.line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines, vec![2, 5]);
+}
+
+#[test]
+fn unreachable_after_loop() {
+    let (result, warnings) = bau::Bau::new().run_with_warnings(
+        r#"
+        fn main() -> int {
+            return 1;
+        }
+
+        fn spin() -> int {
+            loop {}
+            let int _x = 1;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(1))));
+    assert_eq!(warnings.len(), 1);
+    let bau::error::BauWarning::TypecheckerWarning(warning) = &warnings[0] else {
+        panic!("expected a warning in the program");
+    };
+    assert_eq!(warning.to_string(), "Unreachable code");
+    assert_eq!(warning.range().coords.line, 7);
+}
+
+#[test]
+fn main_arguments() {
+    let (result, output) = bau::Bau::new()
+        .with_arguments(vec!["a".to_string(), "b".to_string()])
+        .run_with_stdout_capture(
+            r#"
+        fn main([string] args) -> void {
+            print(join(args, ","));
+        }
+    "#,
+        );
+    assert_eq!(result, Ok(None));
+    assert_eq!(output, "a,b\n");
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main(int count) -> int {
+            return count;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::InvalidMainSignature
+    );
+}
+
+#[test]
+fn print_nested_values() {
+    let value = Value::Array(vec![
+        Value::Array(vec![Value::Integer(1), Value::None]),
+        Value::Array(vec![]),
+        Value::Array(vec![Value::Array(vec![Value::String("deep".to_string())])]),
+    ]);
+    assert_eq!(value.to_string(), "[[1, none], [], [[deep]]]");
+}
+
+#[test]
+fn user_panic() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            check(-1);
+            return 0;
+        }
+
+        fn check(int n) -> void {
+            if n < 0 {
+                panic("boom");
+            }
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::interpreter::error::ExecutionErrorKind::UserPanic {
+            message: "boom".to_string()
+        }
+    );
+    assert_eq!(error.to_string(), "Script panicked: boom");
+    assert_eq!(error.range().unwrap().coords.line, 8);
+}
+
+#[test]
+fn infix_operand_errors() {
+    use bau::interpreter::error::ExecutionErrorKind;
+    use bau::tokenizer::token::TokenKind;
+    use bau::typechecker::{CheckedExpression, CheckedExpressionKind};
+
+    let range = bau::source::CodeRange::new(
+        bau::source::Span::new(0, 0),
+        bau::source::SourceCoords::new(0, 0),
+    );
+    let literal = |value| CheckedExpression::new(CheckedExpressionKind::Literal(value), range);
+    let mut interpreter = bau::interpreter::Interpreter::new();
+
+    let error = interpreter
+        .evaluate_infix_operator(
+            TokenKind::LessThan,
+            &literal(Value::Integer(1)),
+            &literal(Value::Float(2.0)),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error.kind(),
+        &ExecutionErrorKind::MismatchedOperandTypes {
+            left: "int",
+            right: "float",
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "The operands have different types: `int` and `float`"
+    );
+
+    let error = interpreter
+        .evaluate_infix_operator(
+            TokenKind::Percent,
+            &literal(Value::String("a".to_string())),
+            &literal(Value::String("b".to_string())),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error.kind(),
+        &ExecutionErrorKind::UnsupportedOperator {
+            operator: TokenKind::Percent,
+            type_: "string",
+        }
+    );
+}
+
+#[test]
+fn assignment_in_condition() {
+    for code in [
+        "fn main() -> void { let int x = 1; if x = 5 {} }",
+        "fn main() -> void { let int x = 1; while x = 5 {} }",
+    ] {
+        let errors = bau::Bau::new().run(code).unwrap_err();
+        let bau::error::BauError::ParserError(error) = &errors[0] else {
+            panic!("Expected a parser error");
+        };
+        assert_eq!(
+            error.kind(),
+            &bau::parser::error::ParserErrorKind::AssignmentInCondition
+        );
+        assert!(error.to_string().contains("did you mean `==`?"));
+    }
+}
+
+#[test]
+fn unclosed_call() {
+    for code in [
+        "fn main() -> void {\n    foo(1, 2",
+        "fn main() -> void {\n    foo(1,",
+    ] {
+        let errors = bau::Bau::new().run(code).unwrap_err();
+        let bau::error::BauError::ParserError(error) = &errors[0] else {
+            panic!("Expected a parser error");
+        };
+        assert_eq!(
+            error.kind(),
+            &bau::parser::error::ParserErrorKind::UnclosedCall {
+                name: "foo".to_string()
+            }
+        );
+        assert_eq!(error.range().coords, bau::source::SourceCoords::new(1, 7));
+        assert_eq!(
+            error.to_string(),
+            "Unclosed `(` in call to `foo` opened at line 2"
+        );
+    }
+}
+
+#[test]
+fn unexpected_token_kind() {
+    let errors = bau::Bau::new()
+        .run("fn main() -> int {\n    let int x = 1\n    return x;\n}")
+        .unwrap_err();
+    let bau::error::BauError::ParserError(error) = &errors[0] else {
+        panic!("Expected a parser error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::parser::error::ParserErrorKind::UnexpectedToken {
+            found: bau::tokenizer::token::TokenKind::Return,
+            expected: bau::tokenizer::token::TokenKind::Semicolon,
+        }
+    );
+}
+
+#[test]
+fn too_many_arguments_in_parser() {
+    let arguments = vec!["1"; 10_000].join(", ");
+    let code = format!("fn main() -> int {{ return sum({}); }}", arguments);
+    let errors = bau::Bau::new().run(&code).unwrap_err();
+    let bau::error::BauError::ParserError(error) = &errors[0] else {
+        panic!("Expected a parser error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::parser::error::ParserErrorKind::TooManyArguments { max: 255 }
+    );
+
+    let errors = bau::Bau::new()
+        .with_max_call_args(1)
+        .run("fn main() -> int { return ipow(2, 3); }")
+        .unwrap_err();
+    let bau::error::BauError::ParserError(error) = &errors[0] else {
+        panic!("Expected a parser error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::parser::error::ParserErrorKind::TooManyArguments { max: 1 }
+    );
+}
+
+#[test]
+fn assertions() {
+    should_run_and_return_value!(
+        Some(Value::Integer(0)),
+        r#"
+        fn main() -> int {
+            assert(1 < 2);
+            assert_eq(ipow(2, 3), 8);
+            return 0;
+        }
+    "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            assert_eq(1 + 1, 3);
+            return 0;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Assertion failed: `2` is not equal to `3`"
+    );
+    assert_eq!(error.range().unwrap().coords.line, 2);
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            assert("x");
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Boolean,
+            actual: bau::typechecker::Type::String,
+        }
+    );
+}
+
+#[test]
+fn trailing_commas() {
+    should_run_and_return_value!(
+        Some(Value::Integer(3)),
+        r#"
+        fn main() -> int {
+            return add(1, 2,);
+        }
+
+        fn add(
+            int a,
+            int b,
+        ) -> int {
+            return a + b;
+        }
+    "#
+    );
+
+    let source = bau::source::Source::new("fn main() -> int { return add(,); }");
+    assert!(bau::parser::Parser::new(&source).parse_top_level().is_err());
+}
+
+#[test]
+fn parse_large_argument_list() {
+    let parameters = (0..20_000)
+        .map(|i| format!("int p{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arguments = vec!["1"; 20_000].join(", ");
+    let code = format!(
+        "fn main() -> int {{ return f({}); }}\nfn f({}) -> int {{ return 1; }}",
+        arguments, parameters
+    );
+    let source = bau::source::Source::new(&code);
+    let items = bau::parser::Parser::new(&source)
+        .with_max_arguments(usize::MAX)
+        .parse_top_level()
+        .unwrap();
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn source_range_for() {
+    let text = "fn main() -> int {\n    return 1;\n}\n";
+    let source = bau::source::Source::new(text);
+    let tokens = bau::tokenizer::Tokenizer::new(text).tokenize();
+
+    for token in tokens.iter() {
+        let range = token.range();
+        assert_eq!(source.range_for(range.span.start, range.span.end), range);
+    }
+
+    let range = source.range_for(23, 29);
+    assert_eq!(&text[range.span.start..range.span.end], "return");
+    assert_eq!(range.coords, bau::source::SourceCoords::new(1, 4));
+}
+
+#[test]
+fn runtime_wrong_argument_count() {
+    use bau::typechecker::{
+        CheckedFunctionDefinition, CheckedFunctionItem, CheckedFunctionParameter, Type,
+    };
+
+    let function = CheckedFunctionItem {
+        definition: CheckedFunctionDefinition {
+            name: "double".to_string(),
+            parameters: vec![CheckedFunctionParameter {
+                name: "n".to_string(),
+                type_: Type::Integer,
+            }],
+            return_type: Type::Integer,
+            deprecation: None,
+        },
+        body: vec![],
+    };
+    let range = bau::source::Source::new("double()").range_for(0, 8);
+    let error = bau::interpreter::Interpreter::new()
+        .evaluate_function(&function, &[], range)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Function `double` expects 1 arguments but got 0"
+    );
+    assert_eq!(error.range(), Some(&range));
+}
+
+#[test]
+fn call_function_stored_in_variable() {
+    should_run_and_return_value!(
+        Some(Value::Integer(10)),
+        r#"
+            fn main() -> int {
+                let fn(int) -> int f = double;
+                return f(5);
+            }
+
+            fn double(int n) -> int {
+                return n * 2;
+            }
+        "#
+    );
+}
+
+#[test]
+fn pass_function_as_argument() {
+    should_run_and_return_value!(
+        Some(Value::Integer(9)),
+        r#"
+            fn main() -> int {
+                return apply(square, 3);
+            }
+
+            fn apply(fn(int) -> int f, int value) -> int {
+                return f(value);
+            }
+
+            fn square(int n) -> int {
+                return n * n;
+            }
+        "#
+    );
+}
+
+#[test]
+fn call_non_function_variable() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let int f = 5;
+            return f(5);
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::NotCallable {
+            type_: bau::typechecker::Type::Integer
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let fn(int) -> int f = greet;
+            return f(5);
+        }
+
+        fn greet(string name) -> int {
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Function {
+                parameters: vec![bau::typechecker::Type::Integer],
+                return_type: Box::new(bau::typechecker::Type::Integer),
+            },
+            actual: bau::typechecker::Type::Function {
+                parameters: vec![bau::typechecker::Type::String],
+                return_type: Box::new(bau::typechecker::Type::Integer),
+            },
+        }
+    );
+}
+
+#[test]
+fn minus_is_never_part_of_a_number() {
+    let tokens = bau::tokenizer::Tokenizer::new("a-5")
+        .tokenize()
+        .iter()
+        .map(|token| token.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            "Identifier 0..1 0:0",
+            "Minus 1..2 0:1",
+            "IntLiteral 2..3 0:2",
+            "EndOfFile 3..3 0:3",
+        ]
+    );
+}
+
+#[test]
+fn subtraction_spacing() {
+    should_run_and_return_value!(
+        Some(Value::Integer(5)),
+        r#"
+            fn main() -> int {
+                let int a = 10;
+                return a - 5;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(5)),
+        r#"
+            fn main() -> int {
+                let int a = 10;
+                return a-5;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(15)),
+        r#"
+            fn main() -> int {
+                let int a = 10;
+                return a - -5;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(-5)),
+        r#"
+            fn main() -> int {
+                return -5;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Float(-2.5)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> float {
+                return -2.5;
+            }
+        "#
+    );
+}
+
+#[test]
+fn runtime_error_backtrace() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return divide(1);
+        }
+
+        fn divide(int n) -> int {
+            return ipow(n, -1);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    let frames = error
+        .backtrace()
+        .iter()
+        .map(|frame| frame.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(frames, vec!["divide (called at 3:20)", "main"]);
+}
+
+#[test]
+fn string_methods() {
+    should_run_and_return_value!(
+        Some(Value::Array(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+            Value::String("c".to_string()),
+        ])),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> [string] {
+                return "a,b,c".split(",");
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                let string text = "hello world";
+                return text.contains("lo w");
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(2)),
+        r#"
+            fn main() -> int {
+                return "héllo".index_of("l");
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(-1)),
+        r#"
+            fn main() -> int {
+                return "hello".index_of("x");
+            }
+        "#
+    );
+}
+
+#[test]
+fn string_method_wrong_argument_type() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            return "abc".contains(1);
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::String,
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn block_scopes() {
+    // Every iteration and branch gets a fresh scope, so their `let`s don't clash.
+    should_run_and_return_value!(
+        Some(Value::Integer(12)),
+        r#"
+            fn main() -> int {
+                let mut int total = 0;
+                let mut int i = 0;
+                while i < 3 {
+                    let int doubled = i * 2;
+                    if doubled > 2 {
+                        let int bonus = 10;
+                        total += bonus;
+                    } else {
+                        let int bonus = 1;
+                        total += bonus;
+                    }
+                    i += 1;
+                }
+                return total;
+            }
+        "#
+    );
+}
+
+#[test]
+fn loop_control_flow() {
+    // `return` inside a `while` leaves the function immediately.
+    should_run_and_return_value!(
+        Some(Value::Integer(3)),
+        r#"
+            fn main() -> int {
+                let mut int i = 0;
+                while i < 10 {
+                    i += 1;
+                    if i == 3 {
+                        return i;
+                    }
+                }
+                return -1;
+            }
+        "#
+    );
+    // `break` leaves only the loop.
+    should_run_and_return_value!(
+        Some(Value::Integer(104)),
+        r#"
+            fn main() -> int {
+                let mut int i = 0;
+                while i < 10 {
+                    i += 1;
+                    if i == 4 {
+                        break;
+                    }
+                }
+                return i + 100;
+            }
+        "#
+    );
+    // `continue` skips the rest of the body and re-checks the condition.
+    should_run_and_return_value!(
+        Some(Value::Integer(25)),
+        r#"
+            fn main() -> int {
+                let mut int i = 0;
+                let mut int sum = 0;
+                while i < 10 {
+                    i += 1;
+                    if i % 2 == 0 {
+                        continue;
+                    }
+                    sum += i;
+                }
+                return sum;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(5)),
+        r#"
+            fn main() -> int {
+                let mut int i = 0;
+                loop {
+                    i += 1;
+                    if i < 5 {
+                        continue;
+                    }
+                    break;
+                }
+                return i;
+            }
+        "#
+    );
+}
+
+#[test]
+fn loop_control_outside_loop() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            break;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::BreakOutsideLoop
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            if true {
+                continue;
+            }
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ContinueOutsideLoop
+    );
+}
+
+#[test]
+fn loop_control_in_nested_function() {
+    // A nested function doesn't see the loops around its declaration.
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            loop {
+                fn stop() -> void {
+                    break;
+                }
+                stop();
+            }
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::BreakOutsideLoop
+    );
+}
+
+#[test]
+fn format_loop_control() {
+    assert_eq!(
+        format("fn main() -> int {\n    loop {break ;}\n    while true {continue;}\n}\n"),
+        "fn main() -> int {\n    loop {\n        break;\n    }\n    while true {\n        continue;\n    }\n}\n"
+    );
+}
+
+#[test]
+fn extend_int_with_method() {
+    should_run_and_return_value!(
+        Some(Value::Integer(23)),
+        r#"
+            extend int {
+                fn double() -> int {
+                    return self * 2;
+                }
+
+                fn add_times(int other, int times) -> int {
+                    return self + other * times;
+                }
+            }
+
+            fn main() -> int {
+                let int x = 5;
+                return x.double().add_times(4, 3) + 1;
+            }
+        "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        extend int {
+            fn double() -> int {
+                return self * 2;
+            }
+        }
+
+        fn main() -> int {
+            return 5.double(1);
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::WrongArgumentCount {
+            expected: 0,
+            found: 1,
+        }
+    );
+}
+
+#[test]
+fn string_append() {
+    should_run_and_return_value!(
+        Some(Value::String("a-1".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                let mut string text = "a";
+                text.append("-");
+                text.append(1.to_string());
+                return text;
+            }
+        "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            "a".append("b");
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TemporaryReceiver {
+            method_name: "append".to_string()
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let string text = "a";
+            text.append("b");
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::AssignmentToImmutable {
+            name: "text".to_string()
+        }
+    );
+}
+
+#[test]
+fn loop_local_variables() {
+    // Each iteration gets a fresh scope, and the variables declared in it
+    // don't leak out of the loop.
+    should_run_and_return_value!(
+        Some(Value::Integer(106)),
+        r#"
+            fn main() -> int {
+                let mut int i = 0;
+                let mut int total = 0;
+                while i < 3 {
+                    let int doubled = i * 2;
+                    total += doubled;
+                    i += 1;
+                }
+                loop {
+                    let int step = 1;
+                    if i == 5 {
+                        break;
+                    }
+                    i += step;
+                }
+                let int doubled = 100;
+                return total + doubled;
+            }
+        "#
+    );
+}
+
+#[test]
+fn optional_equality() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                return none == none;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                let int? a = 5;
+                let int? b = 5;
+                return a == b;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Boolean(false)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                let int? a = 5;
+                return a == none;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                let int? a = none;
+                let int? b = 5;
+                return a != b;
+            }
+        "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            let int? a = 5;
+            let string? b = "5";
+            return a == b;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::IncompatibleInfixSides {
+            left: bau::typechecker::Type::Optional(Box::new(bau::typechecker::Type::Integer)),
+            operator: bau::tokenizer::token::TokenKind::EqualsEquals,
+            right: bau::typechecker::Type::Optional(Box::new(bau::typechecker::Type::String)),
+        }
+    );
+}
+
+#[test]
+fn string_iteration_methods() {
+    should_run_and_return_value!(
+        Some(Value::Array(vec![
+            Value::String("a".to_string()),
+            Value::String("é".to_string()),
+        ])),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> [string] {
+                return "aé".chars();
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Array(vec![
+            Value::Integer(65),
+            Value::Integer(195),
+            Value::Integer(169)
+        ])),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> [int] {
+                return "Aé".bytes();
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Array(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ])),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> [string] {
+                // String literals have no escapes, so this is a real newline.
+                return "a
+b".lines();
+            }
+        "#
+    );
+}
+
+#[test]
+fn break_out_of_loop() {
+    should_run_and_return_value!(
+        Some(Value::Integer(5)),
+        r#"
+            fn main() -> int {
+                let mut int counter = 0;
+                loop {
+                    counter += 1;
+                    if counter == 5 {
+                        break;
+                    }
+                }
+                return counter;
+            }
+        "#
+    );
+}
+
+#[test]
+fn repeat_string() {
+    should_run_and_return_value!(
+        Some(Value::String("ababab".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                assert(repeat("ab", 0) == "");
+                return repeat("ab", 3);
+            }
+        "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+            fn main() -> void {
+                print(repeat("ab", -1));
+            }
+        "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Panic: Cannot repeat a string a negative number of times, `-1`"
+    );
+}
+
+#[test]
+fn join_strings() {
+    should_run_and_return_value!(
+        Some(Value::String("a-b-c".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return join("a,b,c".split(","), "-");
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("a".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return join("a".split(","), "-");
+            }
+        "#
+    );
+
+    // There are no array literals, so the empty array comes from `main`.
+    let (result, output) = bau::Bau::new().run_with_stdout_capture(
+        r#"
+        fn main([string] args) -> void {
+            print(join(args, "-"));
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(None));
+    assert_eq!(output, "\n");
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> string {
+            return join("a,b", ",");
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Array(Box::new(bau::typechecker::Type::String)),
+            actual: bau::typechecker::Type::String,
+        }
+    );
+}
+
+#[test]
+fn return_from_loop() {
+    should_run_and_return_value!(
+        Some(Value::Integer(32)),
+        r#"
+            fn main() -> int {
+                let mut int counter = 1;
+                loop {
+                    counter *= 2;
+                    if counter > 20 {
+                        return counter;
+                    }
+                }
+            }
+        "#
+    );
+}
+
+#[test]
+fn return_from_nested_loops() {
+    should_run_and_return_value!(
+        Some(Value::Integer(12)),
+        r#"
+            fn main() -> int {
+                let mut int outer = 0;
+                loop {
+                    outer += 1;
+                    let mut int inner = 0;
+                    while true {
+                        inner += 1;
+                        if inner == 3 {
+                            break;
+                        }
+                        if outer == 4 {
+                            return outer * inner * 3;
+                        }
+                    }
+                }
+            }
+        "#
+    );
+}
+
+#[test]
+fn seeded_random_is_reproducible() {
+    let code = r#"
+        fn main() -> void {
+            let mut string numbers = "";
+            let mut int i = 0;
+            while i < 5 {
+                numbers.append(random_int(0, 1000000).to_string());
+                numbers.append(",");
+                i += 1;
+            }
+            numbers.append(random_float().to_string());
+            print(numbers);
+        }
+    "#;
+    let run = |seed| {
+        let (result, output) = bau::Bau::new()
+            .with_seed(Some(seed))
+            .run_with_stdout_capture(code);
+        assert_eq!(result, Ok(None));
+        output
+    };
+
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
+
+#[test]
+fn step_limit() {
+    let code = r#"
+        fn main() -> int {
+            let mut int sum = 0;
+            loop {
+                sum += random_int(0, 10);
+            }
+        }
+    "#;
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new()
+                .with_seed(Some(42))
+                .with_max_steps(Some(1000)),
+            code
+        ),
+        bau::interpreter::error::ExecutionErrorKind::StepLimitExceeded { max_steps: 1000 }
+    );
+
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new().with_max_steps(Some(1000)),
+            r#"
+        fn main() -> void {
+            loop {}
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::StepLimitExceeded { max_steps: 1000 }
+    );
+
+    let result = bau::Bau::new().with_max_steps(Some(3)).run(
+        r#"
+        fn main() -> int {
+            let int a = 1;
+            return a;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(1))));
+}
+
+#[test]
+fn random_int_empty_range() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return random_int(3, 3);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.to_string(),
+        "Panic: `random_int(3, 3)` has an empty range"
+    );
+}
+
+#[test]
+fn infinite_recursion_overflows() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return recurse(0);
+        }
+
+        fn recurse(int n) -> int {
+            return recurse(n + 1);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::interpreter::error::ExecutionErrorKind::StackOverflow { depth: 1000 }
+    );
+    assert_eq!(error.backtrace().len(), 1000);
+}
+
+#[test]
+fn call_depth_limit_too_large() {
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new().with_max_call_depth(200000),
+            r#"
+        fn main() -> int {
+            return 0;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::CallDepthLimitTooLarge {
+            max_call_depth: 200000,
+            limit: bau::interpreter::Interpreter::MAX_CALL_DEPTH,
+        }
+    );
+}
+
+#[test]
+fn radix_formatting() {
+    should_run_and_return_value!(
+        Some(Value::String("ff".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return to_hex(255);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("101".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return to_binary(5);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("-8000000000000000".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return to_hex(-9223372036854775807 - 1);
+            }
+        "#
+    );
+}
+
+#[test]
+fn ternary_expression() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> bool {
+                return (true ? 1 : 2) == 1;
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("big".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                let int x = 50;
+                return x < 10 ? "small" : x < 100 ? "big" : "huge";
+            }
+        "#
+    );
+    // Only the branch that is taken is evaluated.
+    should_run_and_return_value!(
+        Some(Value::Integer(1)),
+        r#"
+            fn main() -> int {
+                return false ? ipow(2, -1) : 1;
+            }
+        "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            return true ? 1 : "two";
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Integer,
+            actual: bau::typechecker::Type::String,
+        }
+    );
+}
+
+#[test]
+fn format_ternary() {
+    assert_eq!(
+        format("fn main() -> int {\n    return (a?b:c)?d:e+(f?g:h);\n}\n"),
+        "fn main() -> int {\n    return (a ? b : c) ? d : e + (f ? g : h);\n}\n"
+    );
+}
+
+#[test]
+fn float_utilities() {
+    should_run_and_return_value!(
+        Some(Value::Float(1.0)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> float {
+                return clamp(1.5, 0.0, 1.0);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Float(5.0)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> float {
+                return lerp(0.0, 10.0, 0.5);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Float(75.0)),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> float {
+                return map_range(0.5, 0.0, 2.0, 50.0, 150.0);
+            }
+        "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> float {
+            return clamp(1, 0.0, 1.0);
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Float,
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn float_formatting_and_rounding() {
+    should_run_and_return_value!(
+        Some(Value::String("3.14".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return to_string(3.14159, 2);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("1.0".to_string())),
+        r#"
+            fn main() -> void {
+                expect(actual());
+            }
+
+            fn actual() -> string {
+                return to_string(1.0, 1);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(3)),
+        r#"
+            fn main() -> int {
+                return round(2.5);
+            }
+        "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(-2)),
+        r#"
+            fn main() -> int {
+                return round(-1.6);
+            }
+        "#
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> int {
+            return round(1e300);
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::ExecutionError(error) = &errors[0] else {
+        panic!("Expected an execution error");
+    };
+    assert!(error.to_string().starts_with("Panic: Cannot round"));
+}
+
+#[test]
+fn error_underline_counts_characters() {
+    colored::control::set_override(false);
+
+    // Identifiers are ASCII-only, so the multi-byte characters live in a string.
+    let code = "fn main() -> void { let string s = \"héllo wörld\"; let string t = s + 1; }";
+    let errors = bau::Bau::new().run(code).unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    let source = bau::source::Source::new(code);
+    let output = bau::error::format_error(&source, Some(error.range()), &error.to_string());
+
+    let lines = output.lines().collect::<Vec<_>>();
+    let source_line = lines[1].split_once("| ").unwrap().1;
+    let underline = lines[2].split_once("| ").unwrap().1;
+    let caret_column = underline.chars().position(|c| c == '^').unwrap();
+    let caret_len = underline.chars().filter(|c| *c == '^').count();
+    let highlighted = source_line
+        .chars()
+        .skip(caret_column)
+        .take(caret_len)
+        .collect::<String>();
+    assert_eq!(highlighted, "s + 1");
+}
+
+#[test]
+fn crlf_line_endings() {
+    let coords = |code: &str| {
+        bau::tokenizer::Tokenizer::new(code)
+            .tokenize()
+            .iter()
+            .filter(|token| !token.is(bau::tokenizer::token::TokenKind::Whitespace))
+            .map(|token| (token.kind(), token.range().coords))
+            .collect::<Vec<_>>()
+    };
+    let code = "fn main() -> int {\n    // comment\n    return 1;\n}\n";
+    assert_eq!(coords(&code.replace('\n', "\r\n")), coords(code));
+
+    let comment = bau::tokenizer::Tokenizer::new("// comment\r\n").tokenize()[0].clone();
+    assert_eq!(comment.range().span.len(), "// comment".len());
+
+    should_run_and_return_value!(
+        Some(Value::String("a\r\nb".to_string())),
+        "fn main() -> void {\r\n    expect(\"a\r\nb\");\r\n}\r\n"
+    );
+}
+
+#[test]
+fn tokenizer_always_advances() {
+    for input in [
+        "$ a",
+        "\u{3000}\u{a0}a",
+        "\"unterminated",
+        "€€€ 1",
+        "\0\0",
+        "// é",
+        "1.",
+        "@",
+    ] {
+        let tokens = bau::tokenizer::Tokenizer::new(input).tokenize();
+        let mut cursor = 0;
+        for token in &tokens {
+            assert_eq!(token.range().span.start, cursor, "{:?}", input);
+            cursor = token.range().span.end;
+        }
+        assert_eq!(cursor, input.len(), "{:?}", input);
+        assert!(tokens
+            .last()
+            .unwrap()
+            .is(bau::tokenizer::token::TokenKind::EndOfFile));
+    }
+
+    let tokens = bau::tokenizer::Tokenizer::new("$ a").tokenize();
+    assert_eq!(tokens[0].to_string(), "Invalid 0..1 0:0");
+    assert_eq!(tokens[2].to_string(), "Identifier 2..3 0:2");
+}
+
+#[test]
+fn void_main() {
+    assert_eq!(bau::Bau::new().run("fn main() -> void { }"), Ok(None));
+    assert_eq!(
+        bau::Bau::new().run(
+            r#"
+        fn main() -> void {
+            if true {
+                return;
+            }
+            print("unreachable");
+        }
+    "#
+        ),
+        Ok(None)
+    );
+    assert_eq!(
+        bau::Bau::new().with_arguments(vec!["hi".to_string()]).run(
+            r#"
+        fn main([string] args) -> void {
+            print(join(args, " "));
+        }
+    "#
+        ),
+        Ok(None)
+    );
+}
+
+#[test]
+fn if_let_binds_inner_value() {
+    should_run_and_return_value!(
+        Some(Value::Integer(4)),
+        r#"
+        fn main() -> int {
+            let int? maybe = 3;
+            if let int x = maybe {
+                return x + 1;
+            } else {
+                return 0;
+            }
+        }
+    "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(0)),
+        r#"
+        fn main() -> int {
+            let int? maybe = none;
+            if let int x = maybe {
+                return x + 1;
+            } else {
+                return 0;
+            }
+        }
+    "#
+    );
+}
+
+#[test]
+fn if_let_errors() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+            fn main() -> int {
+                if let int x = 3 {
+                    return x;
+                }
+                return 0;
+            }
+        "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ExpectedOptional {
+            actual: bau::typechecker::Type::Integer,
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+            fn main() -> int {
+                let int? maybe = 3;
+                if let int x = maybe {
+                } else {
+                    return x;
+                }
+                return 0;
+            }
+        "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::VariableNotDefined {
+            name: "x".to_string(),
+        }
+    );
+}
+
+#[test]
+fn format_if_let() {
+    assert_eq!(
+        format("fn main() -> void {\n    if let int x=maybe {\n        print(x);\n    } else {\n        print(0);\n    }\n}\n"),
+        "fn main() -> void {\n    if let int x = maybe {\n        print(x);\n    } else {\n        print(0);\n    }\n}\n"
+    );
+}
+
+#[test]
+fn main_is_checked_before_running() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn helper() -> int {
+            return 1;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::MainFunctionNotDefined
+    );
+
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main(int x) -> string {
+            return "unreachable";
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::typechecker::error::TypecheckerErrorKind::InvalidMainSignature
+    );
+    assert_eq!(error.range().coords.line, 1);
+}
+
+#[test]
+fn main_return_type() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> string {
+            return "not an exit code";
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::typechecker::error::TypecheckerErrorKind::InvalidMainReturnType {
+            found: bau::typechecker::Type::String,
+        }
+    );
+
+    assert_eq!(
+        bau::Bau::new().run("fn main() -> int { return 3; }"),
+        Ok(Some(Value::Integer(3)))
+    );
+    assert_eq!(bau::Bau::new().run("fn main() -> void {}"), Ok(None));
+}
+
+#[test]
+fn custom_prelude() {
+    let bau = bau::Bau::new().with_prelude(Some(
+        r#"
+        fn double(int x) -> int {
+            return x * 2;
+        }
+    "#
+        .to_string(),
+    ));
+    assert_eq!(
+        bau.run(
+            r#"
+        fn main() -> int {
+            return double(21);
+        }
+    "#
+        ),
+        Ok(Some(Value::Integer(42)))
+    );
+
+    let errors = bau::Bau::new()
+        .with_prelude(Some("fn broken(".to_string()))
+        .run("fn main() -> void {}")
+        .unwrap_err();
+    let bau::error::BauError::PreludeError(error) = &errors[0] else {
+        panic!("Expected a prelude error");
+    };
+    assert!(matches!(**error, bau::error::BauError::ParserError(_)));
+}
+
+#[test]
+fn nested_function() {
+    should_run_and_return_value!(
+        Some(Value::Integer(9)),
+        r#"
+        fn main() -> int {
+            fn square(int x) -> int {
+                return x * x;
+            }
+            return square(3);
+        }
+    "#
+    );
+    should_run_and_return_value!(
+        Some(Value::Integer(120)),
+        r#"
+        fn main() -> int {
+            fn factorial(int n) -> int {
+                if n <= 1 {
+                    return 1;
+                }
+                return n * factorial(n - 1);
+            }
+            return factorial(5);
+        }
+    "#
+    );
+    // A nested function shadows a global one, but only inside its block.
+    should_run_and_return_value!(
+        Some(Value::Integer(21)),
+        r#"
+        fn main() -> int {
+            fn value() -> int {
+                return 20;
+            }
+            return value() + other();
+        }
+
+        fn other() -> int {
+            return value();
+        }
+
+        fn value() -> int {
+            return 1;
+        }
+    "#
+    );
+}
+
+#[test]
+fn nested_function_scope() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+            fn main() -> int {
+                fn square(int x) -> int {
+                    return x * x;
+                }
+                return other();
+            }
+
+            fn other() -> int {
+                return square(2);
+            }
+        "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::FunctionNotDefined {
+            name: "square".to_string(),
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+            fn main() -> int {
+                let int y = 2;
+                fn add_y(int x) -> int {
+                    return x + y;
+                }
+                return add_y(1);
+            }
+        "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::VariableNotDefined {
+            name: "y".to_string(),
+        }
+    );
+}
+
+#[test]
+fn format_nested_function() {
+    assert_eq!(
+        format("fn main() -> int {\n    fn square(int x)->int {\n        return x*x;\n    }\n    return square(3);\n}\n"),
+        "fn main() -> int {\n    fn square(int x) -> int {\n        return x * x;\n    }\n    return square(3);\n}\n"
+    );
+}
+
+#[test]
+fn typeof_builtin() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            return typeof(1.0) == "float";
+        }
+    "#
+    );
+    should_run_and_return_value!(
+        Some(Value::String("int? [string] fn(int) -> bool".to_string())),
+        r#"
+        fn main([string] args) -> void {
+            expect(actual(args));
+        }
+
+        fn actual([string] args) -> string {
+            let int? maybe = 3;
+            let mut string types = typeof(maybe);
+            types.append(" ");
+            types.append(typeof(args));
+            types.append(" ");
+            types.append(typeof(is_even));
+            return types;
+        }
+
+        fn is_even(int x) -> bool {
+            return x % 2 == 0;
+        }
+    "#
+    );
+}
+
+#[test]
+fn duplicate_parameter() {
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn f(int a, int a) -> int {
+            return a;
+        }
+
+        fn main() -> void {}
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::typechecker::error::TypecheckerErrorKind::DuplicateParameter {
+            name: "a".to_string(),
+        }
+    );
+    assert_eq!(error.range().coords.line, 1);
+    assert_eq!(error.range().coords.column, 24);
+}
+
+#[test]
+fn mixed_numeric_operands() {
+    // Ints and floats are never converted implicitly, so mixing them is an error.
+    let errors = bau::Bau::new()
+        .run(
+            r#"
+        fn main() -> void {
+            let float sum = 1 + 2.0;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::typechecker::error::TypecheckerErrorKind::IncompatibleInfixSides {
+            left: bau::typechecker::Type::Integer,
+            operator: bau::tokenizer::token::TokenKind::Plus,
+            right: bau::typechecker::Type::Float,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "The `+` operator cannot mix `int` and `float`, as ints and floats are not converted implicitly"
+    );
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            return 1.0 < 2;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::IncompatibleInfixSides {
+            left: bau::typechecker::Type::Float,
+            operator: bau::tokenizer::token::TokenKind::LessThan,
+            right: bau::typechecker::Type::Integer,
+        }
+    );
+}
+
+#[test]
+fn array_concatenation() {
+    should_run_and_return_value!(
+        Some(Value::Array(vec![
+            Value::Integer(97),
+            Value::Integer(98),
+            Value::Integer(99),
+        ])),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> [int] {
+            let [int] first = "ab".bytes();
+            let [int] second = "c".bytes();
+            return first + second;
+        }
+    "#
+    );
+
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> [int] {
+            let [int] numbers = "ab".bytes();
+            let [string] letters = "ab".chars();
+            return numbers + letters;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::IncompatibleInfixSides {
+            left: bau::typechecker::Type::Array(Box::new(bau::typechecker::Type::Integer)),
+            operator: bau::tokenizer::token::TokenKind::Plus,
+            right: bau::typechecker::Type::Array(Box::new(bau::typechecker::Type::String)),
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> [int] {
+            let [int] numbers = "ab".bytes();
+            return numbers - numbers;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::UnsupportedInfixOperator {
+            operator: bau::tokenizer::token::TokenKind::Minus,
+            type_: bau::typechecker::Type::Array(Box::new(bau::typechecker::Type::Integer)),
+        }
+    );
+}
+
+/// Runs `code` with `prelude` using the `bau` binary and returns the
+/// diagnostics it reported.
+fn run_with_prelude_and_capture_diagnostics(name: &str, prelude: &str, code: &str) -> String {
+    let path = |kind: &str| {
+        std::env::temp_dir().join(format!(
+            "bau-test-{}-{}-{}.bau",
+            name,
+            kind,
+            std::process::id()
+        ))
+    };
+    let (prelude_path, program_path) = (path("prelude"), path("program"));
+    std::fs::write(&prelude_path, prelude).unwrap();
+    std::fs::write(&program_path, code).unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bau"))
+        .arg(&program_path)
+        .arg("--prelude")
+        .arg(&prelude_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    std::fs::remove_file(&prelude_path).unwrap();
+    std::fs::remove_file(&program_path).unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn prelude_error_refers_to_prelude_source() {
+    let output = run_with_prelude_and_capture_diagnostics(
+        "prelude-typechecker-error",
+        "fn broken() -> int {\n    return missing;\n}\n",
+        "fn main() -> int {\n    return broken();\n}\n",
+    );
+    assert!(output.starts_with("In prelude `"), "{}", output);
+    assert!(output.contains("2 |     return missing;"), "{}", output);
+}
+
+#[test]
+fn prelude_runtime_error_refers_to_prelude_source() {
+    let output = run_with_prelude_and_capture_diagnostics(
+        "prelude-runtime-error",
+        "// The prelude.\n\nfn fail() -> void {\n    panic(\"in the prelude\");\n}\n",
+        "fn main() -> void {\n    fail();\n}\n",
+    );
+    assert!(output.starts_with("In prelude `"), "{}", output);
+    assert!(
+        output.contains("4 |     panic(\"in the prelude\");"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn prelude_warning_refers_to_prelude_source() {
+    let output = run_with_prelude_and_capture_diagnostics(
+        "prelude-warning",
+        "fn one() -> int {\n    let int unused = 0;\n    return 1;\n}\n",
+        "fn main() -> int {\n    let int alsounused = 0;\n    return one();\n}\n",
+    );
+    assert!(output.starts_with("In prelude `"), "{}", output);
+    assert!(output.contains("2 |     let int unused = 0;"), "{}", output);
+    // Warnings in the program are still shown against the program.
+    assert!(
+        output.contains("2 |     let int alsounused = 0;"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn unreachable_constant_if_branch() {
+    let (result, warnings) = bau::Bau::new().run_with_warnings(
+        r#"
+        fn main() -> int {
+            if false {
+                return 1;
+            }
+            if true {
+                print("taken");
+            } else {
+                return 2;
+            }
+            return 3;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(3))));
+    let lines = warnings
+        .iter()
+        .map(|warning| {
+            let bau::error::BauWarning::TypecheckerWarning(warning) = warning else {
+                panic!("expected a warning in the program");
+            };
+            assert_eq!(warning.to_string(), "Unreachable code");
+            warning.range().coords.line
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lines, vec![3, 8]);
+
+    // Computed conditions are not evaluated.
+    let (_, warnings) = bau::Bau::new().run_with_warnings(
+        r#"
+        fn main() -> int {
+            if 1 > 2 {
+                return 1;
+            }
+            return 3;
+        }
+    "#,
+    );
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn enum_discriminants() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        enum Status {
+            Ok = 200,
+            NotFound = 404,
+            Gone,
+        }
+
+        fn describe(Status status) -> string {
+            return status == Status.Ok ? "ok" : "error";
+        }
+
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            assert(Status.NotFound.to_int() == 404);
+            assert(Status.Gone.to_int() == 405);
+            assert(describe(Status.Ok) == "ok");
+            assert(typeof(Status.Gone) == "Status");
+
+            let Status? found = Status.from_int(404);
+            assert(unwrap(found) == Status.NotFound);
+            let Status? missing = Status.from_int(201);
+            return is_none(missing);
+        }
+    "#
+    );
+}
+
+#[test]
+fn enum_duplicate_discriminant() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        enum Status {
+            Ok = 200,
+            Success = 200,
+        }
+
+        fn main() -> void {}
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::DuplicateDiscriminant {
+            variant: "Success".to_string(),
+            other: "Ok".to_string(),
+            value: 200,
+        }
+    );
+
+    // Implicit values count on from the variant before them.
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        enum Level {
+            Low = 1,
+            High = 0,
+            Medium,
+        }
+
+        fn main() -> void {}
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::DuplicateDiscriminant {
+            variant: "Medium".to_string(),
+            other: "Low".to_string(),
+            value: 1,
+        }
+    );
+}
+
+#[test]
+fn format_enum() {
+    assert_eq!(
+        format("enum Status { Ok=200, NotFound = -1 }\n"),
+        "enum Status {\n    Ok = 200,\n    NotFound = -1,\n}\n"
+    );
+}
+
+#[test]
+fn forward_reference() {
+    should_run_and_return_value!(
+        Some(Value::Integer(6)),
+        r#"
+        fn main() -> int {
+            return factorial(3);
+        }
+
+        fn factorial(int n) -> int {
+            return n <= 1 ? 1 : n * factorial(n - 1);
+        }
+    "#
+    );
+}
+
+/// Runs `code` with the `bau` binary and returns what it printed.
+fn run_and_capture_output(name: &str, code: &str) -> String {
+    let path = std::env::temp_dir().join(format!("bau-test-{}-{}.bau", name, std::process::id()));
+    std::fs::write(&path, code).unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_bau"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn defer_runs_when_function_returns() {
+    let output = run_and_capture_output(
+        "defer",
+        r#"
+        fn greet(bool early) -> int {
+            defer print("deferred first");
+            defer print("deferred last");
+            print("body");
+            if early {
+                return 1;
+            }
+            return 2;
+        }
+
+        fn count() -> void {
+            let mut int i = 0;
+            while i < 3 {
+                defer print(i.to_string());
+                i += 1;
+            }
+            print("counted");
+        }
+
+        fn main() -> void {
+            greet(true);
+            greet(false);
+            count();
+        }
+    "#,
+    );
+    assert_eq!(
+        output,
+        "body\ndeferred last\ndeferred first\n\
+         body\ndeferred last\ndeferred first\n\
+         counted\n2\n1\n0\n"
+    );
+}
+
+#[test]
+fn defer_cannot_return() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            defer if true {
+                return 1;
+            }
+            return 0;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::InvalidDeferredStatement
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {
+            loop {
+                defer break;
+            }
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::BreakOutsideLoop
+    );
+}
+
+#[test]
+fn format_defer() {
+    assert_eq!(
+        format("fn main() -> void {\n    defer   print(\"done\");\n    defer if true {\n        print(\"x\");\n    }\n}\n"),
+        "fn main() -> void {\n    defer print(\"done\");\n    defer if true {\n        print(\"x\");\n    }\n}\n"
+    );
+}
+
+#[test]
+fn undefined_function_statement() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {
+            nonexistent();
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::FunctionNotDefined {
+            name: "nonexistent".to_string(),
+        }
+    );
+}
+
+#[test]
+fn run_missing_file() {
+    let errors = bau::Bau::new()
+        .run_file("this/file/does/not/exist.bau")
+        .unwrap_err();
+    let bau::error::BauError::IoError { path, .. } = &errors[0] else {
+        panic!("Expected an IO error");
+    };
+    assert_eq!(path, "this/file/does/not/exist.bau");
+    assert_eq!(errors[0].range(), None);
+}
+
+#[test]
+fn integer_modulo() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            return 7 % 3 == 1 && -7 % 3 == -1;
+        }
+    "#
+    );
+    assert_eq!(
+        execution_error_kind(
+            bau::Bau::new(),
+            r#"
+        fn main() -> int {
+            let int divisor = 0;
+            return 7 % divisor;
+        }
+    "#
+        ),
+        bau::interpreter::error::ExecutionErrorKind::DivisionByZero {
+            left: 7,
+            operator: bau::tokenizer::token::TokenKind::Percent,
+        }
+    );
+}
+
+#[test]
+fn float_modulo() {
+    should_run_and_return_value!(
+        Some(Value::Float(1.5)),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> float {
+            assert(-5.5 % 2.0 == -1.5);
+            return 5.5 % 2.0;
+        }
+    "#
+    );
+}
+
+#[test]
+fn constants_in_any_order() {
+    should_run_and_return_value!(
+        Some(Value::Integer(100)),
+        r#"
+        fn first() -> int {
+            return LIMIT;
+        }
+
+        fn second() -> int {
+            return LIMIT;
+        }
+
+        fn main() -> int {
+            assert(first() == second());
+            return first();
+        }
+
+        const int LIMIT = MAX;
+        const int MAX = 100;
+    "#
+    );
+}
+
+#[test]
+fn constant_cycle() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        const int MAX = MAX;
+
+        fn main() -> void {}
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ConstantCycle {
+            name: "MAX".to_string()
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        const int LOW = -HIGH;
+        const int HIGH = LOW;
+
+        fn main() -> void {}
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::ConstantCycle {
+            name: "LOW".to_string()
+        }
+    );
+}
+
+#[test]
+fn value_to_json() {
+    assert_eq!(Value::Integer(-42).to_json(), "-42");
+    assert_eq!(Value::Float(2.5).to_json(), "2.5");
+    assert_eq!(Value::Float(f64::NAN).to_json(), "null");
+    assert_eq!(Value::Boolean(true).to_json(), "true");
+    assert_eq!(
+        Value::String("say \"hi\"\n\\".to_string()).to_json(),
+        r#""say \"hi\"\n\\""#
+    );
+    assert_eq!(Value::None.to_json(), "null");
+    assert_eq!(
+        Value::Array(vec![Value::Integer(1), Value::String("a".to_string())]).to_json(),
+        r#"[1,"a"]"#
+    );
+}
+
+#[test]
+fn capture_stdout() {
+    let (result, output) = bau::Bau::new().run_with_stdout_capture(
+        r#"
+        fn main() -> int {
+            print("one");
+            print("two");
+            print(3.to_string());
+            return 3;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(3))));
+    assert_eq!(output, "one\ntwo\n3\n");
+}
+
+#[test]
+fn source_tokens_with_text() {
+    use bau::tokenizer::token::TokenKind;
+
+    let source = bau::source::Source::new("fn main");
+    let tokens = source
+        .tokens()
+        .into_iter()
+        .filter(|(token, _)| !token.is(TokenKind::Whitespace) && !token.is(TokenKind::EndOfFile))
+        .map(|(token, text)| (token.kind(), text))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![(TokenKind::Fn, "fn"), (TokenKind::Identifier, "main")]
+    );
+}
+
+#[test]
+fn compound_assignment_operator_types() {
+    should_run_and_return_value!(
+        Some(Value::String("ab".to_string())),
+        r#"
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> string {
+            let mut string s = "a";
+            s += "b";
+            return s;
+        }
+    "#
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {
+            let mut string s = "";
+            s %= "a";
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::UnsupportedInfixOperator {
+            operator: bau::tokenizer::token::TokenKind::Percent,
+            type_: bau::typechecker::Type::String,
+        }
+    );
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> void {}
+
+        fn actual() -> bool {
+            return true + false;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::UnsupportedInfixOperator {
+            operator: bau::tokenizer::token::TokenKind::Plus,
+            type_: bau::typechecker::Type::Boolean,
+        }
+    );
+}
+
+/// Runs every `.bau` file in `tests/fixtures` and compares what it prints
+/// with the file's `// expect: <output>` directive.
+#[test]
+fn fixtures() {
+    let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut paths = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "bau"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let code = std::fs::read_to_string(&path).unwrap();
+        let expected = code
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("// expect:"))
+            .unwrap_or_else(|| panic!("`{}` has no `// expect:` directive", path.display()))
+            .trim();
+        let (result, output) = bau::Bau::new().run_with_stdout_capture(&code);
+        if let Err(errors) = result {
+            panic!("`{}` failed: {:?}", path.display(), errors);
+        }
+        assert_eq!(output.trim_end(), expected, "in `{}`", path.display());
+    }
+}
+
+#[test]
+fn value_conversions() {
+    assert_eq!(i64::try_from(Value::from(42)), Ok(42));
+    assert_eq!(f64::try_from(Value::from(2.5)), Ok(2.5));
+    assert_eq!(bool::try_from(Value::from(true)), Ok(true));
+    assert_eq!(String::try_from(Value::from("bau")), Ok("bau".to_string()));
+
+    assert_eq!(Value::Integer(42).as_integer(), Some(42));
+    assert_eq!(Value::Float(2.5).as_float(), Some(2.5));
+    assert_eq!(Value::Boolean(true).as_boolean(), Some(true));
+    assert_eq!(Value::String("bau".to_string()).as_string(), Some("bau"));
+    assert_eq!(Value::Float(2.5).as_integer(), None);
+
+    let error = i64::try_from(Value::String("42".to_string())).unwrap_err();
+    assert_eq!(
+        error,
+        bau::interpreter::value::ValueConversionError {
+            expected: "int",
+            found: "string",
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Expected a value of type `int`, but found `string` instead"
+    );
+    assert!(bool::try_from(Value::None).is_err());
+}
+
+#[test]
+fn token_on_third_line() {
+    colored::control::set_override(false);
+
+    let code = "let int a = 1;\n\nlet int b = 2;";
+    let tokens = bau::tokenizer::Tokenizer::new(code).tokenize();
+    let token = tokens
+        .iter()
+        .rev()
+        .find(|token| token.kind() == bau::tokenizer::token::TokenKind::Identifier)
+        .unwrap();
+    // Lines are counted from zero, but shown counted from one.
+    assert_eq!(token.range().coords, bau::source::SourceCoords::new(2, 8));
+
+    let source = bau::source::Source::new(code);
+    let output = bau::error::format_error(&source, Some(&token.range()), "here");
+    let source_line = output.lines().nth(1).unwrap();
+    assert!(source_line.trim_start().starts_with("3 |"));
+    assert!(source_line.ends_with("let int b = 2;"));
+}
+
+#[test]
+fn range_outside_of_source() {
+    colored::control::set_override(false);
+
+    let range = bau::source::CodeRange::new(
+        bau::source::Span::new(40, 45),
+        bau::source::SourceCoords::new(3, 4),
+    );
+    let source = bau::source::Source::new("fn main() -> int {}");
+    let output = bau::error::format_error(&source, Some(&range), "here");
+    assert_eq!(output, "error: here\n");
+}
+
+#[test]
+fn source_too_large() {
+    let errors = bau::Bau::new()
+        .with_max_source_size(16)
+        .run("fn main() -> int { return 1; }")
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![bau::error::BauError::SourceTooLarge {
+            size: 30,
+            limit: 16
+        }]
+    );
+}
+
+#[test]
+fn match_guards() {
+    should_run_and_return_value!(
+        Some(Value::Boolean(true)),
+        r#"
+        fn sign(int x) -> string {
+            return match x { n if n > 0 => "positive", 0 => "zero", _ => "negative" };
+        }
+
+        fn main() -> void {
+            expect(actual());
+        }
+
+        fn actual() -> bool {
+            assert(sign(5) == "positive");
+            assert(sign(0) == "zero");
+            return sign(-3) == "negative";
+        }
+    "#
+    );
+}
+
+#[test]
+fn match_enum_variants() {
+    should_run_and_return_value!(
+        Some(Value::Integer(404)),
+        r#"
+        enum Status {
+            Ok = 200,
+            NotFound = 404,
+        }
+
+        fn main() -> int {
+            let Status status = Status.NotFound;
+            return match status { Status.Ok => 0, s => s.to_int() };
+        }
+    "#
+    );
+}
+
+#[test]
+fn match_needs_catch_all_arm() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let int x = 1;
+            return match x { n if n > 0 => 1, 0 => 0 };
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::NonExhaustiveMatch
+    );
+}
+
+#[test]
+fn format_match() {
+    assert_eq!(
+        format("fn main() -> int {\n    return match 1 {n if n>0=>1,_=>0};\n}\n"),
+        "fn main() -> int {\n    return match 1 { n if n > 0 => 1, _ => 0 };\n}\n"
+    );
+}
+
+#[test]
+fn tuple_destructuring() {
+    should_run_and_return_value!(
+        Some(Value::Integer(31)),
+        r#"
+        fn divmod(int a, int b) -> (int, int) {
+            return (a / b, a % b);
+        }
+
+        fn main() -> int {
+            let (int q, int r) = divmod(7, 2);
+            let (int _, int s) = divmod(9, 4);
+            return q * 10 + r * s;
+        }
+    "#
+    );
+}
+
+#[test]
+fn tuple_destructuring_mismatch() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        fn main() -> int {
+            let (int q, int r, int s) = (1, 2);
+            return q;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::TypeMismatch {
+            expected: bau::typechecker::Type::Tuple(vec![
+                bau::typechecker::Type::Integer,
+                bau::typechecker::Type::Integer,
+                bau::typechecker::Type::Integer,
+            ]),
+            actual: bau::typechecker::Type::Tuple(vec![
+                bau::typechecker::Type::Integer,
+                bau::typechecker::Type::Integer,
+            ]),
+        }
+    );
+}
+
+#[test]
+fn format_tuple() {
+    assert_eq!(
+        format("fn main() -> int {\n    let (int a,int b)=(1,2);\n    return a;\n}\n"),
+        "fn main() -> int {\n    let (int a, int b) = (1, 2);\n    return a;\n}\n"
+    );
+}
+
+#[test]
+fn struct_field_access() {
+    should_run_and_return_value!(
+        Some(Value::Integer(23)),
+        r#"
+        struct Point {
+            int x;
+            int y;
+        }
+
+        fn point(int x, int y) -> Point {
+            return Point { x: x, y: y };
+        }
+
+        fn main() -> int {
+            let Point p = point(2, 3);
+            return point(20, 0).x + p.y;
+        }
+    "#
+    );
+}
+
+#[test]
+fn struct_missing_field() {
+    assert_eq!(
+        typechecker_error_kind(
+            r#"
+        struct Point {
+            int x;
+            int y;
+        }
+
+        fn main() -> int {
+            let Point p = Point { x: 1 };
+            return p.x;
+        }
+    "#
+        ),
+        bau::typechecker::error::TypecheckerErrorKind::MissingField {
+            struct_name: "Point".to_string(),
+            field: "y".to_string(),
+        }
+    );
+}
+
+#[test]
+fn format_struct() {
+    assert_eq!(
+        format("struct Point {\nint x;  int y;\n}\n\nfn main() -> int {\n    return Point {x:1,y:2}.x;\n}\n"),
+        "struct Point {\n    int x;\n    int y;\n}\n\nfn main() -> int {\n    return Point { x: 1, y: 2 }.x;\n}\n"
+    );
+}
+
+#[test]
+fn native_function() {
+    use bau::typechecker::Type;
+
+    let bau = bau::Bau::new().with_native_function(
+        "double",
+        Type::Function {
+            parameters: vec![Type::Integer],
+            return_type: Box::new(Type::Integer),
+        },
+        Box::new(|arguments| {
+            let value = i64::try_from(arguments[0].clone()).unwrap();
+            Ok(Some(Value::Integer(value * 2)))
+        }),
+    );
+    let result = bau.run(
+        r#"
+        fn main() -> int {
+            return double(21);
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(42))));
+}
+
+#[test]
+fn invalid_native_functions() {
+    use bau::typechecker::Type;
+
+    let int_to_int = || Type::Function {
+        parameters: vec![Type::Integer],
+        return_type: Box::new(Type::Integer),
+    };
+    let program = "fn main() -> int { return 0; }";
+
+    let errors = bau::Bau::new()
+        .with_native_function("answer", Type::Integer, Box::new(|_| Ok(None)))
+        .run(program)
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![bau::error::BauError::InvalidNativeSignature {
+            name: "answer".to_string(),
+            signature: Type::Integer,
+        }]
+    );
+
+    let errors = bau::Bau::new()
+        .with_native_function("repeat", int_to_int(), Box::new(|_| Ok(None)))
+        .with_native_function("double", int_to_int(), Box::new(|_| Ok(None)))
+        .with_native_function("double", int_to_int(), Box::new(|_| Ok(None)))
+        .run(program)
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            bau::error::BauError::NativeFunctionAlreadyDefined {
+                name: "repeat".to_string(),
+            },
+            bau::error::BauError::NativeFunctionAlreadyDefined {
+                name: "double".to_string(),
+            },
+        ]
+    );
+
+    let errors = bau::Bau::new()
+        .with_native_function("double", int_to_int(), Box::new(|_| Ok(None)))
+        .run(
+            r#"
+        fn main() -> int {
+            return double(1);
+        }
+
+        fn double(int x) -> int {
+            return x * 2;
+        }
+    "#,
+        )
+        .unwrap_err();
+    let bau::error::BauError::TypecheckerError(error) = &errors[0] else {
+        panic!("Expected a typechecker error");
+    };
+    assert_eq!(
+        error.kind(),
+        &bau::typechecker::error::TypecheckerErrorKind::FunctionAlreadyDefined {
+            name: "double".to_string(),
+        }
+    );
+}
+
+#[test]
+fn native_function_return_value_is_checked() {
+    use bau::interpreter::error::ExecutionErrorKind;
+    use bau::typechecker::Type;
+
+    let returning = |return_type: Type, value: Option<Value>| {
+        bau::Bau::new().with_native_function(
+            "native",
+            Type::Function {
+                parameters: vec![],
+                return_type: Box::new(return_type),
+            },
+            Box::new(move |_| Ok(value.clone())),
+        )
+    };
+
+    assert_eq!(
+        execution_error_kind(
+            returning(Type::Integer, None),
+            "fn main() -> int { return native(); }"
+        ),
+        ExecutionErrorKind::InvalidNativeReturnValue {
+            expected: Box::new(Type::Integer),
+            found: "void",
+        }
+    );
+    assert_eq!(
+        execution_error_kind(
+            returning(Type::Integer, Some(Value::String("1".to_string()))),
+            "fn main() -> int { return native(); }"
+        ),
+        ExecutionErrorKind::InvalidNativeReturnValue {
+            expected: Box::new(Type::Integer),
+            found: "string",
+        }
+    );
+    assert_eq!(
+        execution_error_kind(
+            returning(Type::Void, Some(Value::Integer(1))),
+            "fn main() -> void { native(); }"
+        ),
+        ExecutionErrorKind::InvalidNativeReturnValue {
+            expected: Box::new(Type::Void),
+            found: "int",
+        }
+    );
+
+    let optional = Type::Optional(Box::new(Type::Array(Box::new(Type::Integer))));
+    assert_eq!(
+        returning(optional.clone(), Some(Value::None))
+            .run("fn main() -> void { assert(is_none(native())); }"),
+        Ok(None)
+    );
+    assert_eq!(
+        execution_error_kind(
+            returning(
+                optional.clone(),
+                Some(Value::Array(vec![Value::Float(1.0)]))
+            ),
+            "fn main() -> void { assert(is_some(native())); }"
+        ),
+        ExecutionErrorKind::InvalidNativeReturnValue {
+            expected: Box::new(optional),
+            found: "array",
+        }
+    );
+}
+
+#[test]
+fn unused_variable() {
+    let (result, warnings) = bau::Bau::new().run_with_warnings(
+        r#"
+        fn main() -> int {
+            let int used = 1;
+            let int unused = 2;
+            let int _ignored = 3;
+            return used;
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(Some(Value::Integer(1))));
+    assert_eq!(warnings.len(), 1);
+    let bau::error::BauWarning::TypecheckerWarning(warning) = &warnings[0] else {
+        panic!("expected a warning in the program");
+    };
+    assert_eq!(
+        warning.kind(),
+        &bau::typechecker::warning::TypecheckerWarningKind::UnusedVariable {
+            name: "unused".to_string()
+        }
+    );
+    assert_eq!(warning.range().coords.line, 3);
+}
+
+#[test]
+fn print_self_referential_struct() {
+    use bau::typechecker::Type;
+
+    let bau = bau::Bau::new().with_native_function(
+        "describe",
+        Type::Function {
+            parameters: vec![Type::Struct("Node".to_string())],
+            return_type: Box::new(Type::String),
+        },
+        Box::new(|arguments| Ok(Some(Value::String(arguments[0].to_string())))),
+    );
+    let (result, output) = bau.run_with_stdout_capture(
+        r#"
+        struct Node {
+            int value;
+            Node? next;
+        }
+
+        fn main() -> void {
+            let Node last = Node { value: 2, next: none };
+            let Node first = Node { value: 1, next: last };
+            print(describe(first));
+        }
+    "#,
+    );
+    assert_eq!(result, Ok(None));
+    assert_eq!(output, "{ next: { next: none, value: 2 }, value: 1 }\n");
+}
@@ -0,0 +1,217 @@
+use bau::interpreter::builtin::BUILTIN_FUNCTIONS;
+use bau::parser::{ParsedItemKind, ParsedStatement, ParsedStatementKind, Parser};
+use bau::source::Source;
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, Position};
+
+use crate::util;
+
+const KEYWORDS: &[&str] = &[
+    "fn", "extend", "trait", "enum", "struct", "let", "mut", "const", "if", "else", "loop",
+    "while", "return", "continue", "break", "defer", "match",
+];
+
+pub fn get_completions(text: &str, position: Position) -> Vec<CompletionItem> {
+    let mut completions = KEYWORDS
+        .iter()
+        .map(|keyword| CompletionItem {
+            label: keyword.to_string(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            ..CompletionItem::default()
+        })
+        .collect::<Vec<_>>();
+
+    let source = Source::new(text);
+    let Ok(items) = Parser::new(&source).parse_top_level() else {
+        return completions;
+    };
+
+    for (name, definition) in BUILTIN_FUNCTIONS.iter() {
+        completions.push(CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some(definition.to_string()),
+            ..CompletionItem::default()
+        });
+    }
+
+    for item in items.iter() {
+        if let ParsedItemKind::Function(function) = item.kind() {
+            completions.push(CompletionItem {
+                label: function.name.name().to_string(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(util::function_signature(function)),
+                ..CompletionItem::default()
+            });
+        }
+    }
+
+    let offset = util::position_to_offset(text, position);
+    let enclosing_function = util::functions(&items)
+        .into_iter()
+        .find(|function| function.range.span.start <= offset && offset <= function.range.span.end);
+    if let Some(function) = enclosing_function {
+        let mut variables = function
+            .parameters
+            .iter()
+            .map(|parameter| (parameter.name.name(), parameter.type_name.name()))
+            .collect::<Vec<_>>();
+        visible_variables(
+            &function.body,
+            offset,
+            function.range.span.end,
+            &mut variables,
+        );
+
+        for (name, type_name) in variables {
+            completions.push(CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail: Some(type_name.to_string()),
+                ..CompletionItem::default()
+            });
+        }
+    }
+
+    completions
+}
+
+/// Collects the variables declared in `block` before `offset`, descending into
+/// nested blocks that contain `offset`. `block_end` is the offset at which the
+/// block ends.
+fn visible_variables<'a>(
+    block: &'a [ParsedStatement],
+    offset: usize,
+    block_end: usize,
+    variables: &mut Vec<(&'a str, &'a str)>,
+) {
+    for (i, statement) in block.iter().enumerate() {
+        let start = statement.range().span.start;
+        if start >= offset {
+            break;
+        }
+
+        // Statement ranges don't always cover nested blocks, so a nested block
+        // is considered to extend up to the start of the next statement.
+        let end = block
+            .get(i + 1)
+            .map(|next| next.range().span.start)
+            .unwrap_or(block_end);
+        let contains_offset = offset < end;
+
+        match statement.kind() {
+            ParsedStatementKind::Let {
+                name, type_name, ..
+            } => variables.push((name.name(), type_name.name())),
+            ParsedStatementKind::DestructuringLet { bindings, .. } => variables.extend(
+                bindings
+                    .iter()
+                    .map(|binding| (binding.name.name(), binding.type_name.name())),
+            ),
+            ParsedStatementKind::If {
+                then_body,
+                else_body,
+                ..
+            } if contains_offset => if_body_variables(
+                then_body,
+                else_body.as_deref(),
+                None,
+                offset,
+                end,
+                variables,
+            ),
+            ParsedStatementKind::IfLet {
+                name,
+                type_name,
+                then_body,
+                else_body,
+                ..
+            } if contains_offset => {
+                let binding = (name.name(), type_name.name());
+                if_body_variables(
+                    then_body,
+                    else_body.as_deref(),
+                    Some(binding),
+                    offset,
+                    end,
+                    variables,
+                )
+            }
+            ParsedStatementKind::Loop { body } if contains_offset => {
+                visible_variables(body, offset, end, variables)
+            }
+            ParsedStatementKind::While { block, .. } if contains_offset => {
+                visible_variables(block, offset, end, variables)
+            }
+            // A nested function can't see the variables of the function around it.
+            ParsedStatementKind::FunctionDef(function) if contains_offset => {
+                variables.clear();
+                variables.extend(
+                    function
+                        .parameters
+                        .iter()
+                        .map(|parameter| (parameter.name.name(), parameter.type_name.name())),
+                );
+                visible_variables(&function.body, offset, end, variables)
+            }
+            ParsedStatementKind::Defer { statement } if contains_offset => {
+                visible_variables(std::slice::from_ref(statement), offset, end, variables)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collects the variables visible at `offset` in whichever branch of an `if`
+/// contains it. The `binding` of an `if let` is only visible in the then-branch.
+fn if_body_variables<'a>(
+    then_body: &'a [ParsedStatement],
+    else_body: Option<&'a [ParsedStatement]>,
+    binding: Option<(&'a str, &'a str)>,
+    offset: usize,
+    end: usize,
+    variables: &mut Vec<(&'a str, &'a str)>,
+) {
+    let in_else_body = else_body
+        .and_then(|else_body| else_body.first())
+        .is_some_and(|first| first.range().span.start <= offset);
+    match else_body {
+        Some(else_body) if in_else_body => visible_variables(else_body, offset, end, variables),
+        _ => {
+            variables.extend(binding);
+            visible_variables(then_body, offset, end, variables)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(completions: &[CompletionItem], kind: CompletionItemKind) -> Vec<&str> {
+        completions
+            .iter()
+            .filter(|completion| completion.kind == Some(kind))
+            .map(|completion| completion.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn completes_variables_in_scope() {
+        let text = "fn main(int x) -> int {\n    let int a = 1;\n    \n    let int b = 2;\n    return a;\n}";
+        let completions = get_completions(text, Position::new(2, 4));
+        assert_eq!(
+            labels(&completions, CompletionItemKind::VARIABLE),
+            vec!["x", "a"]
+        );
+        assert!(labels(&completions, CompletionItemKind::FUNCTION).contains(&"main"));
+    }
+
+    #[test]
+    fn parse_error_completes_keywords_only() {
+        let completions = get_completions("fn main( {", Position::new(0, 9));
+        assert_eq!(completions.len(), KEYWORDS.len());
+        assert!(completions
+            .iter()
+            .all(|completion| completion.kind == Some(CompletionItemKind::KEYWORD)));
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use bau::parser::{ParsedExpressionKind, ParsedItemKind, Parser};
+use bau::source::{CodeRange, Source};
+use bau::tokenizer::token::TokenKind;
+use tower_lsp::lsp_types::{Location, Position, Url};
+
+use crate::util;
+
+pub fn get_definition(uri: &Url, text: &str, position: Position) -> Option<Location> {
+    let token = util::token_at_position(text, position)?;
+    if !token.is(TokenKind::Identifier) {
+        return None;
+    }
+
+    let source = Source::new(text);
+    let items = Parser::new(&source).parse_top_level().ok()?;
+
+    // Map every function name to the range of the name in its first
+    // declaration. A call without a receiver can only refer to a top-level
+    // function, and a method call only to a method of an `extend` item.
+    let mut functions: HashMap<&str, CodeRange> = HashMap::new();
+    let mut methods: HashMap<&str, CodeRange> = HashMap::new();
+    for item in items.iter() {
+        match item.kind() {
+            ParsedItemKind::Function(function) => {
+                functions
+                    .entry(function.name.name())
+                    .or_insert(function.name.token().range());
+            }
+            ParsedItemKind::Extend(extend) => {
+                for method in extend.functions.iter() {
+                    methods
+                        .entry(method.name.name())
+                        .or_insert(method.name.token().range());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut declaration = None;
+    for function in util::functions(&items) {
+        util::walk_block(&function.body, &mut |expression| {
+            let (call, declarations) = match expression.kind() {
+                ParsedExpressionKind::FunctionCall(call) => (call, &functions),
+                ParsedExpressionKind::MethodCall { call, .. } => (call, &methods),
+                _ => return,
+            };
+            if call.name.token().range().span == token.range().span {
+                declaration = declarations.get(call.name.name()).copied();
+            }
+        });
+    }
+
+    let declaration = declaration?;
+    Some(Location::new(
+        uri.clone(),
+        util::code_range_to_range(text, &declaration),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Range;
+
+    fn definition(text: &str, line: u32, character: u32) -> Option<Range> {
+        let uri = Url::parse("file:///test.bau").unwrap();
+        get_definition(&uri, text, Position::new(line, character)).map(|location| location.range)
+    }
+
+    #[test]
+    fn definition_of_call() {
+        let text = "fn main() -> int {\n    return add(1, 2);\n}\n\nfn add(int a, int b) -> int {\n    return a + b;\n}";
+        assert_eq!(
+            definition(text, 1, 12),
+            Some(Range::new(Position::new(4, 3), Position::new(4, 6)))
+        );
+    }
+
+    #[test]
+    fn duplicate_name_resolves_to_first_declaration() {
+        let text = "fn main() -> int {\n    return one();\n}\n\nfn one() -> int {\n    return 1;\n}\n\nfn one() -> int {\n    return 2;\n}";
+        assert_eq!(
+            definition(text, 1, 11),
+            Some(Range::new(Position::new(4, 3), Position::new(4, 6)))
+        );
+    }
+
+    #[test]
+    fn free_calls_and_method_calls_resolve_separately() {
+        let text = "extend int {\n    fn add(int other) -> int {\n        return other;\n    }\n}\n\nfn main() -> int {\n    return add(1, 2) + 3.add(4);\n}\n\nfn add(int a, int b) -> int {\n    return a + b;\n}";
+        assert_eq!(
+            definition(text, 7, 12),
+            Some(Range::new(Position::new(10, 3), Position::new(10, 6)))
+        );
+        assert_eq!(
+            definition(text, 7, 26),
+            Some(Range::new(Position::new(1, 7), Position::new(1, 10)))
+        );
+    }
+
+    #[test]
+    fn builtins_have_no_definition() {
+        let text = "fn main() -> void {\n    print(\"a\");\n}";
+        assert_eq!(definition(text, 1, 5), None);
+    }
+}
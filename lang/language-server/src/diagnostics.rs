@@ -0,0 +1,96 @@
+use bau::parser::Parser;
+use bau::source::{CodeRange, Source};
+use bau::typechecker::Typechecker;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+
+use crate::util;
+
+pub fn get_diagnostics(text: &str) -> Vec<Diagnostic> {
+    let source = Source::new(text);
+    let items = match Parser::new(&source).parse_top_level() {
+        Ok(items) => items,
+        Err(errors) => {
+            return errors
+                .iter()
+                .map(|error| {
+                    diagnostic(
+                        text,
+                        error.range(),
+                        DiagnosticSeverity::ERROR,
+                        error.to_string(),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    let mut typechecker = Typechecker::new();
+    typechecker.check_items(&items);
+
+    let errors = typechecker.errors().iter().map(|error| {
+        diagnostic(
+            text,
+            error.range(),
+            DiagnosticSeverity::ERROR,
+            error.to_string(),
+        )
+    });
+    let warnings = typechecker.warnings().iter().map(|warning| {
+        diagnostic(
+            text,
+            warning.range(),
+            DiagnosticSeverity::WARNING,
+            warning.to_string(),
+        )
+    });
+    errors.chain(warnings).collect()
+}
+
+fn diagnostic(
+    text: &str,
+    range: &CodeRange,
+    severity: DiagnosticSeverity,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: util::code_range_to_range(text, range),
+        severity: Some(severity),
+        source: Some("bau".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_clear_once_valid() {
+        let diagnostics = get_diagnostics("fn main() -> int {\n    return true;\n}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+
+        assert_eq!(
+            get_diagnostics("fn main() -> int {\n    return 1;\n}"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn parser_errors_are_reported() {
+        let diagnostics = get_diagnostics("fn main( {");
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR)));
+    }
+
+    #[test]
+    fn warnings_are_reported() {
+        let diagnostics =
+            get_diagnostics("fn main() -> int {\n    let int a = 1;\n    return 1;\n}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+}
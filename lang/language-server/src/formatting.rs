@@ -0,0 +1,21 @@
+use bau::formatter::Formatter;
+use bau::source::{CodeRange, Source, SourceCoords, Span};
+use tower_lsp::lsp_types::TextEdit;
+
+use crate::util;
+
+/// Formats the whole document, replacing it in a single edit. Returns `None`
+/// if the document does not parse.
+pub fn get_formatting(text: &str) -> Option<Vec<TextEdit>> {
+    let source = Source::new(text);
+    let formatted = Formatter::new(&source).format().ok()?;
+    if formatted == text {
+        return Some(vec![]);
+    }
+
+    let whole_document = CodeRange::new(Span::new(0, text.len()), SourceCoords::new(0, 0));
+    Some(vec![TextEdit::new(
+        util::code_range_to_range(text, &whole_document),
+        formatted,
+    )])
+}
@@ -0,0 +1,299 @@
+use bau::parser::{ParsedFunctionItem, ParsedItem, ParsedStatementKind, Parser};
+use bau::source::{Source, Span};
+use bau::tokenizer::token::TokenKind;
+use bau::typechecker::{
+    CheckedExpression, CheckedExpressionKind, CheckedItemKind, CheckedPattern, CheckedStatement,
+    CheckedStatementKind, Typechecker,
+};
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkedString, Position};
+
+use crate::util;
+
+pub fn get_hover(text: &str, position: Position) -> Option<Hover> {
+    let token = util::token_at_position(text, position)?;
+    if !token.is(TokenKind::Identifier) {
+        return None;
+    }
+
+    let source = Source::new(text);
+    let items = Parser::new(&source).parse_top_level().ok()?;
+    let contents = match get_declaration_hover(&items, token.range().span) {
+        Some(contents) => contents,
+        None => get_expression_hover(&items, token.range().span.start)?,
+    };
+
+    Some(Hover {
+        contents: HoverContents::Scalar(MarkedString::LanguageString(
+            tower_lsp::lsp_types::LanguageString {
+                language: "bau".to_string(),
+                value: contents,
+            },
+        )),
+        range: Some(util::code_range_to_range(text, &token.range())),
+    })
+}
+
+/// Describes the function, parameter or variable declared at `span`.
+fn get_declaration_hover(items: &[ParsedItem], span: Span) -> Option<String> {
+    let mut functions = util::functions(items);
+    for function in util::functions(items) {
+        util::walk_statements(&function.body, &mut |statement| {
+            if let ParsedStatementKind::FunctionDef(function) = statement.kind() {
+                functions.push(function);
+            }
+        });
+    }
+
+    for function in functions {
+        if let Some(contents) = get_function_declaration_hover(function, span) {
+            return Some(contents);
+        }
+    }
+    None
+}
+
+fn get_function_declaration_hover(function: &ParsedFunctionItem, span: Span) -> Option<String> {
+    if function.name.token().range().span == span {
+        return Some(util::function_signature(function));
+    }
+
+    let mut declarations = function
+        .parameters
+        .iter()
+        .map(|parameter| (&parameter.name, &parameter.type_name))
+        .collect::<Vec<_>>();
+    util::walk_statements(&function.body, &mut |statement| match statement.kind() {
+        ParsedStatementKind::Let {
+            name, type_name, ..
+        }
+        | ParsedStatementKind::IfLet {
+            name, type_name, ..
+        } => declarations.push((name, type_name)),
+        ParsedStatementKind::DestructuringLet { bindings, .. } => declarations.extend(
+            bindings
+                .iter()
+                .map(|binding| (&binding.name, &binding.type_name)),
+        ),
+        _ => {}
+    });
+
+    declarations
+        .into_iter()
+        .find(|(name, _)| name.token().range().span == span)
+        .map(|(name, type_name)| format!("{} {}", type_name.name(), name.name()))
+}
+
+/// Describes the innermost checked expression that contains `offset`.
+fn get_expression_hover(items: &[ParsedItem], offset: usize) -> Option<String> {
+    let mut typechecker = Typechecker::new();
+    let checked_items = typechecker.check_items(items);
+
+    let mut enclosing = None;
+    for item in checked_items.iter() {
+        let functions = match item.kind() {
+            CheckedItemKind::Function(function) => vec![function],
+            CheckedItemKind::Extend(extend) => extend.methods.iter().collect(),
+            CheckedItemKind::Const(_) => vec![],
+        };
+        for function in functions {
+            find_in_block(&function.body, offset, &mut enclosing);
+        }
+    }
+
+    match enclosing?.kind() {
+        CheckedExpressionKind::Variable(variable)
+        | CheckedExpressionKind::VariableCall { variable, .. } => {
+            Some(format!("{} {}", variable.type_, variable.name))
+        }
+        CheckedExpressionKind::FunctionCall { name, .. } => Some(
+            typechecker
+                .get_function_definition_by_name(name)?
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+fn find_in_block<'a>(
+    block: &'a [CheckedStatement],
+    offset: usize,
+    enclosing: &mut Option<&'a CheckedExpression>,
+) {
+    for statement in block {
+        match statement.kind() {
+            CheckedStatementKind::Let { initial_value, .. }
+            | CheckedStatementKind::DestructuringLet { initial_value, .. } => {
+                find_in_expression(initial_value, offset, enclosing)
+            }
+            CheckedStatementKind::VariableAssignment { value, .. } => {
+                find_in_expression(value, offset, enclosing)
+            }
+            CheckedStatementKind::Return { value } => {
+                if let Some(value) = value {
+                    find_in_expression(value, offset, enclosing)
+                }
+            }
+            CheckedStatementKind::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                find_in_expression(condition, offset, enclosing);
+                find_in_block(then_body, offset, enclosing);
+                if let Some(else_body) = else_body {
+                    find_in_block(else_body, offset, enclosing);
+                }
+            }
+            CheckedStatementKind::IfLet {
+                value,
+                then_body,
+                else_body,
+                ..
+            } => {
+                find_in_expression(value, offset, enclosing);
+                find_in_block(then_body, offset, enclosing);
+                if let Some(else_body) = else_body {
+                    find_in_block(else_body, offset, enclosing);
+                }
+            }
+            CheckedStatementKind::Expression { expression } => {
+                find_in_expression(expression, offset, enclosing)
+            }
+            CheckedStatementKind::Loop { block } => find_in_block(block, offset, enclosing),
+            CheckedStatementKind::While { condition, block } => {
+                find_in_expression(condition, offset, enclosing);
+                find_in_block(block, offset, enclosing);
+            }
+            CheckedStatementKind::Break | CheckedStatementKind::Continue => {}
+            CheckedStatementKind::FunctionDef(function) => {
+                find_in_block(&function.body, offset, enclosing)
+            }
+            CheckedStatementKind::Defer { statement } => {
+                find_in_block(std::slice::from_ref(statement), offset, enclosing)
+            }
+        }
+    }
+}
+
+/// Finds the smallest expression that contains `offset`.
+fn find_in_expression<'a>(
+    expression: &'a CheckedExpression,
+    offset: usize,
+    enclosing: &mut Option<&'a CheckedExpression>,
+) {
+    let span = expression.range().span;
+    if span_contains(span, offset) {
+        match enclosing {
+            Some(current) if current.range().span.len() <= span.len() => {}
+            _ => *enclosing = Some(expression),
+        }
+    }
+
+    match expression.kind() {
+        CheckedExpressionKind::Literal(_)
+        | CheckedExpressionKind::Variable(_)
+        | CheckedExpressionKind::EnumVariant { .. } => {}
+        CheckedExpressionKind::EnumFromInt { value, .. } => {
+            find_in_expression(value, offset, enclosing)
+        }
+        CheckedExpressionKind::FunctionCall { arguments, .. }
+        | CheckedExpressionKind::VariableCall { arguments, .. }
+        | CheckedExpressionKind::Tuple(arguments) => {
+            for argument in arguments {
+                find_in_expression(argument, offset, enclosing);
+            }
+        }
+        CheckedExpressionKind::MethodCall {
+            receiver,
+            arguments,
+            ..
+        } => {
+            find_in_expression(receiver, offset, enclosing);
+            for argument in arguments {
+                find_in_expression(argument, offset, enclosing);
+            }
+        }
+        CheckedExpressionKind::PrefixOperator { expression, .. }
+        | CheckedExpressionKind::FieldAccess { expression, .. } => {
+            find_in_expression(expression, offset, enclosing)
+        }
+        CheckedExpressionKind::Struct { fields, .. } => {
+            for (_, value) in fields {
+                find_in_expression(value, offset, enclosing);
+            }
+        }
+        CheckedExpressionKind::InfixOperator { left, right, .. } => {
+            find_in_expression(left, offset, enclosing);
+            find_in_expression(right, offset, enclosing);
+        }
+        CheckedExpressionKind::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            find_in_expression(condition, offset, enclosing);
+            find_in_expression(then_branch, offset, enclosing);
+            find_in_expression(else_branch, offset, enclosing);
+        }
+        CheckedExpressionKind::Match { value, arms } => {
+            find_in_expression(value, offset, enclosing);
+            for arm in arms {
+                if let CheckedPattern::Value(pattern) = &arm.pattern {
+                    find_in_expression(pattern, offset, enclosing);
+                }
+                if let Some(guard) = &arm.guard {
+                    find_in_expression(guard, offset, enclosing);
+                }
+                find_in_expression(&arm.value, offset, enclosing);
+            }
+        }
+    }
+}
+
+fn span_contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset < span.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "fn main() -> int {\n    let int a = 1;\n    return add(a, 2);\n}\n\nfn add(int a, int b) -> int {\n    return a + b;\n}";
+
+    fn hover_text(line: u32, character: u32) -> Option<String> {
+        let hover = get_hover(TEXT, Position::new(line, character))?;
+        let HoverContents::Scalar(MarkedString::LanguageString(contents)) = hover.contents else {
+            panic!("Expected a language string");
+        };
+        Some(contents.value)
+    }
+
+    #[test]
+    fn hover_on_call_shows_signature() {
+        assert_eq!(
+            hover_text(2, 11),
+            Some("fn add(int a, int b) -> int".to_string())
+        );
+    }
+
+    #[test]
+    fn hover_on_variable_shows_type() {
+        assert_eq!(hover_text(2, 15), Some("int a".to_string()));
+    }
+
+    #[test]
+    fn hover_on_declarations() {
+        assert_eq!(
+            hover_text(5, 3),
+            Some("fn add(int a, int b) -> int".to_string())
+        );
+        assert_eq!(hover_text(5, 18), Some("int b".to_string()));
+        assert_eq!(hover_text(1, 12), Some("int a".to_string()));
+    }
+
+    #[test]
+    fn hover_outside_identifiers() {
+        assert_eq!(hover_text(2, 4), None);
+        assert_eq!(get_hover("fn main( {", Position::new(0, 3)), None);
+    }
+}
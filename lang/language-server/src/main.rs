@@ -1,12 +1,22 @@
+use std::collections::HashMap;
+
+use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result as RpcResult;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
+mod completion;
+mod definition;
+mod diagnostics;
+mod formatting;
+mod hover;
 mod semantic_tokens;
+mod util;
 
 #[derive(Debug)]
 struct Backend {
     client: Client,
+    documents: RwLock<HashMap<Url, String>>,
 }
 
 #[tower_lsp::async_trait]
@@ -18,6 +28,20 @@ impl LanguageServer for Backend {
         Ok(InitializeResult {
             server_info: None,
             capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                            include_text: Some(true),
+                        })),
+                        ..TextDocumentSyncOptions::default()
+                    },
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                completion_provider: Some(CompletionOptions::default()),
+                document_formatting_provider: Some(OneOf::Left(true)),
                 semantic_tokens_provider: Some(
                     SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(
                         SemanticTokensRegistrationOptions {
@@ -51,6 +75,89 @@ impl LanguageServer for Backend {
         Ok(())
     }
 
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.update_document(
+            params.text_document.uri,
+            params.text_document.text,
+            Some(params.text_document.version),
+        )
+        .await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // We only support full document syncing, so the last change contains the whole text.
+        if let Some(change) = params.content_changes.pop() {
+            self.update_document(
+                params.text_document.uri,
+                change.text,
+                Some(params.text_document.version),
+            )
+            .await;
+        }
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if let Some(text) = params.text {
+            self.update_document(params.text_document.uri, text, None)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents
+            .write()
+            .await
+            .remove(&params.text_document.uri);
+        self.client
+            .publish_diagnostics(params.text_document.uri, vec![], None)
+            .await;
+    }
+
+    async fn hover(&self, params: HoverParams) -> RpcResult<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let documents = self.documents.read().await;
+        let Some(text) = documents.get(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        Ok(hover::get_hover(text, position.position))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> RpcResult<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let documents = self.documents.read().await;
+        let Some(text) = documents.get(&uri) else {
+            return Ok(None);
+        };
+        Ok(definition::get_definition(&uri, text, position.position)
+            .map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> RpcResult<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let documents = self.documents.read().await;
+        let Some(text) = documents.get(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        Ok(Some(CompletionResponse::Array(
+            completion::get_completions(text, position.position),
+        )))
+    }
+
+    async fn formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> RpcResult<Option<Vec<TextEdit>>> {
+        let documents = self.documents.read().await;
+        let Some(text) = documents.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        Ok(formatting::get_formatting(text))
+    }
+
     async fn semantic_tokens_full(
         &self,
         params: SemanticTokensParams,
@@ -63,12 +170,23 @@ impl LanguageServer for Backend {
     }
 }
 
+impl Backend {
+    async fn update_document(&self, uri: Url, text: String, version: Option<i32>) {
+        let diagnostics = diagnostics::get_diagnostics(&text);
+        self.documents.write().await.insert(uri.clone(), text);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let (stdin, stdout) = (tokio::io::stdin(), tokio::io::stdout());
-    #[cfg(feature = "runtime-agnostic")]
-    let (stdin, stdout) = (stdin.compat(), stdout.compat_write());
 
-    let (service, socket) = LspService::new(|client| Backend { client });
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        documents: RwLock::new(HashMap::new()),
+    });
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -1,3 +1,7 @@
+use std::collections::{HashMap, HashSet};
+
+use bau::parser::{ParsedExpressionKind, ParsedItemKind, ParsedStatementKind, Parser};
+use bau::source::Source;
 use bau::tokenizer::token::TokenKind;
 use tower_lsp::jsonrpc::Result as RpcResult;
 use tower_lsp::lsp_types::{
@@ -5,6 +9,13 @@ use tower_lsp::lsp_types::{
     SemanticTokensResult,
 };
 
+use crate::util;
+
+const TYPE: u32 = 5;
+const PARAMETER: u32 = 6;
+const VARIABLE: u32 = 7;
+const FUNCTION: u32 = 8;
+
 pub fn get_tokens_legend() -> SemanticTokensLegend {
     SemanticTokensLegend {
         token_types: vec![
@@ -16,6 +27,7 @@ pub fn get_tokens_legend() -> SemanticTokensLegend {
             SemanticTokenType::TYPE,      // 5
             SemanticTokenType::PARAMETER, // 6
             SemanticTokenType::VARIABLE,  // 7
+            SemanticTokenType::FUNCTION,  // 8
         ],
         token_modifiers: vec![],
     }
@@ -25,18 +37,19 @@ pub fn handle_semantic_tokens_full(
     params: SemanticTokensParams,
 ) -> RpcResult<Option<SemanticTokensResult>> {
     let file = params.text_document.uri.path();
-    let tokens = get_semantic_tokens(file);
+    let file_content = std::fs::read_to_string(file).unwrap();
+    let tokens = get_semantic_tokens(&file_content);
     Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
         result_id: None,
         data: tokens,
     })))
 }
 
-fn get_semantic_tokens(file: &str) -> Vec<SemanticToken> {
-    let file_content = std::fs::read_to_string(file).unwrap();
-    let source = bau::source::Source::new(&file_content);
+fn get_semantic_tokens(text: &str) -> Vec<SemanticToken> {
+    let source = Source::new(text);
     let mut tokenizer = bau::tokenizer::Tokenizer::new(source.text());
     let bau_tokens = tokenizer.tokenize();
+    let identifier_types = get_identifier_token_types(&source);
     let mut semantic_tokens = Vec::new();
 
     let mut prev_line = 0;
@@ -44,9 +57,12 @@ fn get_semantic_tokens(file: &str) -> Vec<SemanticToken> {
 
     for bau_token in bau_tokens.iter() {
         let token_length = bau_token.range().span.len();
-        let token_type = match bau_token_to_semantic_token_type(bau_token.kind()) {
-            Some(token_type) => token_type,
-            None => continue,
+        let token_type = match identifier_types.get(&bau_token.range().span.start) {
+            Some(token_type) if bau_token.is(TokenKind::Identifier) => *token_type,
+            _ => match bau_token_to_semantic_token_type(bau_token.kind()) {
+                Some(token_type) => token_type,
+                None => continue,
+            },
         };
 
         let line = bau_token.range().coords.line as u32;
@@ -73,12 +89,101 @@ fn get_semantic_tokens(file: &str) -> Vec<SemanticToken> {
     semantic_tokens
 }
 
+/// Classifies identifiers by their role in the parsed document, keyed by the
+/// start offset of their token. Identifiers that are plain variables, or all
+/// identifiers when the document fails to parse, are left out.
+fn get_identifier_token_types(source: &Source) -> HashMap<usize, u32> {
+    let mut types = HashMap::new();
+    let Ok(items) = Parser::new(source).parse_top_level() else {
+        return types;
+    };
+
+    for item in items.iter() {
+        match item.kind() {
+            ParsedItemKind::Extend(extend) => {
+                types.insert(extend.type_name.token().range().span.start, TYPE);
+                if let Some(trait_name) = &extend.trait_name {
+                    types.insert(trait_name.token().range().span.start, TYPE);
+                }
+            }
+            ParsedItemKind::Enum(enum_item) => {
+                types.insert(enum_item.name.token().range().span.start, TYPE);
+            }
+            ParsedItemKind::Struct(struct_item) => {
+                types.insert(struct_item.name.token().range().span.start, TYPE);
+                for field in struct_item.fields.iter() {
+                    types.insert(field.type_name.token().range().span.start, TYPE);
+                }
+            }
+            ParsedItemKind::Trait(trait_item) => {
+                types.insert(trait_item.name.token().range().span.start, TYPE);
+                for method in trait_item.methods.iter() {
+                    types.insert(method.name.token().range().span.start, FUNCTION);
+                    types.insert(method.return_type_name.token().range().span.start, TYPE);
+                    for parameter in method.parameters.iter() {
+                        types.insert(parameter.type_name.token().range().span.start, TYPE);
+                        types.insert(parameter.name.token().range().span.start, PARAMETER);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for function in util::functions(&items) {
+        types.insert(function.name.token().range().span.start, FUNCTION);
+        types.insert(function.return_type_name.token().range().span.start, TYPE);
+
+        let mut parameters = HashSet::new();
+        for parameter in function.parameters.iter() {
+            types.insert(parameter.type_name.token().range().span.start, TYPE);
+            types.insert(parameter.name.token().range().span.start, PARAMETER);
+            parameters.insert(parameter.name.name());
+        }
+
+        util::walk_statements(&function.body, &mut |statement| match statement.kind() {
+            ParsedStatementKind::Let { type_name, .. } => {
+                types.insert(type_name.token().range().span.start, TYPE);
+            }
+            ParsedStatementKind::DestructuringLet { bindings, .. } => {
+                for binding in bindings {
+                    types.insert(binding.type_name.token().range().span.start, TYPE);
+                }
+            }
+            ParsedStatementKind::VariableAssignment { name, .. }
+                if parameters.contains(name.name()) =>
+            {
+                types.insert(name.token().range().span.start, PARAMETER);
+            }
+            _ => {}
+        });
+
+        util::walk_block(&function.body, &mut |expression| match expression.kind() {
+            ParsedExpressionKind::FunctionCall(call)
+            | ParsedExpressionKind::MethodCall { call, .. } => {
+                types.insert(call.name.token().range().span.start, FUNCTION);
+            }
+            ParsedExpressionKind::Variable(name) if parameters.contains(name.name()) => {
+                types.insert(name.token().range().span.start, PARAMETER);
+            }
+            _ => {}
+        });
+    }
+
+    types
+}
+
 fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
     match bau_token_kind {
         // Keywords
         TokenKind::Fn => Some(1),
         TokenKind::Extend => Some(1),
+        TokenKind::Trait => Some(1),
+        TokenKind::Enum => Some(1),
+        TokenKind::Struct => Some(1),
         TokenKind::Let => Some(1),
+        TokenKind::Mut => Some(1),
+        TokenKind::Const => Some(1),
         TokenKind::If => Some(1),
         TokenKind::Else => Some(1),
         TokenKind::Loop => Some(1),
@@ -86,15 +191,18 @@ fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
         TokenKind::Return => Some(1),
         TokenKind::Continue => Some(1),
         TokenKind::Break => Some(1),
+        TokenKind::Defer => Some(1),
+        TokenKind::Match => Some(1),
 
         // Literals
         TokenKind::StringLiteral => Some(4),
         TokenKind::IntLiteral => Some(3),
         TokenKind::FloatLiteral => Some(3),
         TokenKind::BoolLiteral => Some(3),
+        TokenKind::NoneLiteral => Some(3),
 
         // Identifiers
-        TokenKind::Identifier => Some(7),
+        TokenKind::Identifier => Some(VARIABLE),
 
         // Operators
         TokenKind::Plus => Some(2),
@@ -112,6 +220,8 @@ fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
         TokenKind::AsteriskEquals => Some(2),
         TokenKind::SlashEquals => Some(2),
         TokenKind::PercentEquals => Some(2),
+        TokenKind::AmpersandAmpersandEquals => Some(2),
+        TokenKind::PipePipeEquals => Some(2),
 
         TokenKind::EqualsEquals => Some(2),
         TokenKind::ExclamationMarkEquals => Some(2),
@@ -123,6 +233,7 @@ fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
         // Punctuation
         TokenKind::Equals => None,
         TokenKind::Arrow => None,
+        TokenKind::FatArrow => None,
         TokenKind::ParenOpen => None,
         TokenKind::ParenClose => None,
         TokenKind::BraceOpen => None,
@@ -132,6 +243,9 @@ fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
         TokenKind::Semicolon => None,
         TokenKind::Period => None,
         TokenKind::Comma => None,
+        TokenKind::Colon => None,
+        TokenKind::QuestionMark => None,
+        TokenKind::At => None,
 
         // Misc
         TokenKind::Comment => None,
@@ -140,3 +254,102 @@ fn bau_token_to_semantic_token_type(bau_token_kind: TokenKind) -> Option<u32> {
         TokenKind::Invalid => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Turns delta-encoded tokens back into absolute
+    /// `(line, column, length, type name)` tuples.
+    fn decode(tokens: &[SemanticToken]) -> Vec<(u32, u32, u32, String)> {
+        let legend = get_tokens_legend();
+        let mut line = 0;
+        let mut column = 0;
+        let mut decoded = vec![];
+        for token in tokens {
+            if token.delta_line > 0 {
+                line += token.delta_line;
+                column = token.delta_start;
+            } else {
+                column += token.delta_start;
+            }
+            let type_name = legend.token_types[token.token_type as usize]
+                .as_str()
+                .to_string();
+            decoded.push((line, column, token.length, type_name));
+        }
+        decoded
+    }
+
+    #[test]
+    fn tokens_decode_to_absolute_positions() {
+        let tokens = get_semantic_tokens(
+            "fn main() -> int {\n    let int a = 1; // one\n    return a + 2;\n}",
+        );
+        assert_eq!(
+            decode(&tokens)
+                .iter()
+                .map(|(line, column, length, type_name)| (
+                    *line,
+                    *column,
+                    *length,
+                    type_name.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0, 2, "keyword"),
+                (0, 3, 4, "function"),
+                (0, 13, 3, "type"),
+                (1, 4, 3, "keyword"),
+                (1, 8, 3, "type"),
+                (1, 12, 1, "variable"),
+                (1, 16, 1, "number"),
+                (2, 4, 6, "keyword"),
+                (2, 11, 1, "variable"),
+                (2, 13, 1, "operator"),
+                (2, 15, 1, "number"),
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_are_classified_by_role() {
+        let tokens = get_semantic_tokens(
+            "fn main() -> int {\n    let int a = double(1);\n    return a;\n}\nfn double(int n) -> int { return n * 2; }",
+        );
+        let types = tokens
+            .iter()
+            .filter(|token| [TYPE, PARAMETER, VARIABLE, FUNCTION].contains(&token.token_type))
+            .map(|token| token.token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                FUNCTION, TYPE, TYPE, VARIABLE, FUNCTION, VARIABLE, FUNCTION, TYPE, PARAMETER,
+                TYPE, PARAMETER,
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_char_operators_use_full_span() {
+        let tokens = get_semantic_tokens("a == b >= c");
+        let operators = tokens
+            .iter()
+            .filter(|token| token.token_type == 2)
+            .map(|token| token.length)
+            .collect::<Vec<_>>();
+        assert_eq!(operators, vec![2, 2]);
+    }
+
+    #[test]
+    fn compound_assignment_operators_use_full_span() {
+        let tokens = get_semantic_tokens("a += 1; b != c && d");
+        let operators = tokens
+            .iter()
+            .filter(|token| token.token_type == 2)
+            .map(|token| token.length)
+            .collect::<Vec<_>>();
+        assert_eq!(operators, vec![2, 2, 2]);
+    }
+}
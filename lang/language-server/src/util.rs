@@ -0,0 +1,234 @@
+use bau::parser::{
+    ParsedExpression, ParsedExpressionKind, ParsedFunctionItem, ParsedItem, ParsedItemKind,
+    ParsedPattern, ParsedStatement, ParsedStatementKind,
+};
+use bau::source::CodeRange;
+use bau::tokenizer::{Token, Tokenizer};
+use tower_lsp::lsp_types::{Position, Range};
+
+pub fn token_at_position(text: &str, position: Position) -> Option<Token> {
+    Tokenizer::new(text).tokenize().into_iter().find(|token| {
+        let coords = token.range().coords;
+        let column = position.character as usize;
+        coords.line == position.line as usize
+            && coords.column <= column
+            && column < coords.column + token.len()
+    })
+}
+
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (offset, char) in text.char_indices() {
+        if line == position.line && character == position.character {
+            return offset;
+        }
+        if char == '\n' {
+            if line == position.line {
+                return offset;
+            }
+            line += 1;
+            character = 0;
+        } else {
+            character += 1;
+        }
+    }
+    text.len()
+}
+
+pub fn code_range_to_range(text: &str, range: &CodeRange) -> Range {
+    let start = Position::new(range.coords.line as u32, range.coords.column as u32);
+
+    let mut end = start;
+    let end_offset = usize::min(range.span.end, text.len());
+    if let Some(spanned_text) = text.get(range.span.start..end_offset) {
+        for char in spanned_text.chars() {
+            if char == '\n' {
+                end.line += 1;
+                end.character = 0;
+            } else {
+                end.character += 1;
+            }
+        }
+    }
+
+    Range { start, end }
+}
+
+/// Formats the signature of `function` as it is written in the source, as in
+/// `fn add(int a, int b) -> int`.
+pub fn function_signature(function: &ParsedFunctionItem) -> String {
+    let parameters = function
+        .parameters
+        .iter()
+        .map(|parameter| format!("{} {}", parameter.type_name.name(), parameter.name.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "fn {}({}) -> {}",
+        function.name.name(),
+        parameters,
+        function.return_type_name.name()
+    )
+}
+
+/// Returns all functions declared in `items`, including the methods of `extend` items.
+pub fn functions(items: &[ParsedItem]) -> Vec<&ParsedFunctionItem> {
+    let mut functions = vec![];
+    for item in items {
+        match item.kind() {
+            ParsedItemKind::Function(function) => functions.push(function),
+            ParsedItemKind::Extend(extend) => functions.extend(extend.functions.iter()),
+            ParsedItemKind::Const(_)
+            | ParsedItemKind::Trait(_)
+            | ParsedItemKind::Enum(_)
+            | ParsedItemKind::Struct(_) => {}
+        }
+    }
+    functions
+}
+
+/// Calls `f` for every statement in `block`, including statements in nested blocks.
+pub fn walk_statements<'a>(block: &'a [ParsedStatement], f: &mut impl FnMut(&'a ParsedStatement)) {
+    for statement in block {
+        f(statement);
+        match statement.kind() {
+            ParsedStatementKind::If {
+                then_body,
+                else_body,
+                ..
+            }
+            | ParsedStatementKind::IfLet {
+                then_body,
+                else_body,
+                ..
+            } => {
+                walk_statements(then_body, f);
+                if let Some(else_body) = else_body {
+                    walk_statements(else_body, f);
+                }
+            }
+            ParsedStatementKind::Loop { body } => walk_statements(body, f),
+            ParsedStatementKind::While { block, .. } => walk_statements(block, f),
+            ParsedStatementKind::FunctionDef(function) => walk_statements(&function.body, f),
+            ParsedStatementKind::Defer { statement } => {
+                walk_statements(std::slice::from_ref(statement), f)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Calls `f` for every expression in `block`, including nested subexpressions.
+pub fn walk_block<'a>(block: &'a [ParsedStatement], f: &mut impl FnMut(&'a ParsedExpression)) {
+    for statement in block {
+        match statement.kind() {
+            ParsedStatementKind::Let { initial_value, .. }
+            | ParsedStatementKind::DestructuringLet { initial_value, .. } => {
+                walk_expression(initial_value, f)
+            }
+            ParsedStatementKind::VariableAssignment { value, .. } => walk_expression(value, f),
+            ParsedStatementKind::Return { value } => {
+                if let Some(value) = value {
+                    walk_expression(value, f);
+                }
+            }
+            ParsedStatementKind::Expression { expression } => walk_expression(expression, f),
+            ParsedStatementKind::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                if let Some(condition) = condition {
+                    walk_expression(condition, f);
+                }
+                walk_block(then_body, f);
+                if let Some(else_body) = else_body {
+                    walk_block(else_body, f);
+                }
+            }
+            ParsedStatementKind::IfLet {
+                value,
+                then_body,
+                else_body,
+                ..
+            } => {
+                walk_expression(value, f);
+                walk_block(then_body, f);
+                if let Some(else_body) = else_body {
+                    walk_block(else_body, f);
+                }
+            }
+            ParsedStatementKind::Loop { body } => walk_block(body, f),
+            ParsedStatementKind::While { condition, block } => {
+                if let Some(condition) = condition {
+                    walk_expression(condition, f);
+                }
+                walk_block(block, f);
+            }
+            ParsedStatementKind::Break | ParsedStatementKind::Continue => {}
+            ParsedStatementKind::FunctionDef(function) => walk_block(&function.body, f),
+            ParsedStatementKind::Defer { statement } => {
+                walk_block(std::slice::from_ref(statement), f)
+            }
+        }
+    }
+}
+
+pub fn walk_expression<'a>(
+    expression: &'a ParsedExpression,
+    f: &mut impl FnMut(&'a ParsedExpression),
+) {
+    f(expression);
+    match expression.kind() {
+        ParsedExpressionKind::Literal(_) | ParsedExpressionKind::Variable(_) => {}
+        ParsedExpressionKind::FunctionCall(call) => {
+            for argument in call.arguments.iter() {
+                walk_expression(argument, f);
+            }
+        }
+        ParsedExpressionKind::Tuple(elements) => {
+            for element in elements {
+                walk_expression(element, f);
+            }
+        }
+        ParsedExpressionKind::Struct { fields, .. } => {
+            for field in fields {
+                walk_expression(&field.value, f);
+            }
+        }
+        ParsedExpressionKind::PrefixOperator { expression, .. }
+        | ParsedExpressionKind::MemberAccess { expression, .. } => walk_expression(expression, f),
+        ParsedExpressionKind::InfixOperator { left, right, .. } => {
+            walk_expression(left, f);
+            walk_expression(right, f);
+        }
+        ParsedExpressionKind::MethodCall { expression, call } => {
+            walk_expression(expression, f);
+            for argument in call.arguments.iter() {
+                walk_expression(argument, f);
+            }
+        }
+        ParsedExpressionKind::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            walk_expression(condition, f);
+            walk_expression(then_branch, f);
+            walk_expression(else_branch, f);
+        }
+        ParsedExpressionKind::Match { value, arms } => {
+            walk_expression(value, f);
+            for arm in arms.iter() {
+                if let ParsedPattern::Value(pattern) = &arm.pattern {
+                    walk_expression(pattern, f);
+                }
+                if let Some(guard) = &arm.guard {
+                    walk_expression(guard, f);
+                }
+                walk_expression(&arm.value, f);
+            }
+        }
+    }
+}